@@ -0,0 +1,145 @@
+use crate::agent::SmartAgent;
+use crate::game::{GameState, PlayerScore};
+use crate::skill::{MAX_SKILL_LEVEL, MIN_SKILL_LEVEL};
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+// 计算近期胜率时参考的对局数
+const RECENT_GAMES: usize = 6;
+// 每局结束后按 (近期胜率 - 目标胜率) × LEVEL_PER_WIN_RATE 调整基础等级
+const LEVEL_PER_WIN_RATE: f64 = 3.0;
+// 局中评分每高于 RATING_PIVOT RATING_PER_LEVEL 分，临时提高一级 (低于则降低)，
+// 最多调整 MAX_IN_GAME_SHIFT 级
+const RATING_PIVOT: f64 = 75.0;
+const RATING_PER_LEVEL: f64 = 15.0;
+const MAX_IN_GAME_SHIFT: f64 = 1.5;
+// 本局评分过的步数少于这个值时评分还不可靠，不做局中调整
+const MIN_RATED_MOVES: u32 = 4;
+
+/// 自适应难度：根据玩家近期的胜负调整 AI 的基础等级，使玩家的胜率接近目标；
+/// 局中再根据玩家的滚动评分临时升降等级
+#[derive(Resource, Debug, Clone)]
+pub struct AdaptiveDifficulty {
+    pub enabled: bool,
+    pub target_win_rate: f64, // 玩家的目标胜率 (和棋算半局)
+    base_level: f64,          // 按胜负记录调整的基础等级 (连续值，取整后使用)
+    in_game_shift: f64,       // 本局根据评分的临时调整
+    applied: Option<u32>,     // 最近一次设置给 AI 的等级
+    recent: VecDeque<f64>,    // 近期对局玩家的得分 (胜 1，和 0.5，负 0)
+    game_recorded: bool,      // 本局的结果是否已经记录
+}
+
+impl AdaptiveDifficulty {
+    pub fn new(enabled: bool, target_win_rate: f64, initial_level: u32) -> Self {
+        AdaptiveDifficulty {
+            enabled,
+            target_win_rate: target_win_rate.clamp(0.05, 0.95),
+            base_level: initial_level.clamp(MIN_SKILL_LEVEL, MAX_SKILL_LEVEL) as f64,
+            in_game_shift: 0.0,
+            applied: Some(initial_level.clamp(MIN_SKILL_LEVEL, MAX_SKILL_LEVEL)),
+            recent: VecDeque::new(),
+            game_recorded: false,
+        }
+    }
+
+    /// 当前实际使用的难度等级
+    pub fn effective_level(&self) -> u32 {
+        (self.base_level + self.in_game_shift)
+            .round()
+            .clamp(MIN_SKILL_LEVEL as f64, MAX_SKILL_LEVEL as f64) as u32
+    }
+
+    /// 近期对局中玩家的胜率，还没有完成的对局时为 None
+    pub fn recent_win_rate(&self) -> Option<f64> {
+        if self.recent.is_empty() {
+            return None;
+        }
+        Some(self.recent.iter().sum::<f64>() / self.recent.len() as f64)
+    }
+
+    /// 记录一局的结果 (玩家得分)，按近期胜率与目标的差距调整基础等级
+    pub fn record_game(&mut self, player_score: f64) {
+        self.recent.push_back(player_score);
+        while self.recent.len() > RECENT_GAMES {
+            self.recent.pop_front();
+        }
+        let win_rate = self.recent_win_rate().unwrap_or(self.target_win_rate);
+        self.base_level = (self.base_level
+            + LEVEL_PER_WIN_RATE * (win_rate - self.target_win_rate))
+            .clamp(MIN_SKILL_LEVEL as f64, MAX_SKILL_LEVEL as f64);
+        self.in_game_shift = 0.0;
+    }
+
+    /// 根据本局的滚动评分 (0-100) 临时调整等级
+    pub fn update_in_game(&mut self, rating: u32, rated_moves: u32) {
+        self.in_game_shift = if rated_moves < MIN_RATED_MOVES {
+            0.0
+        } else {
+            ((rating as f64 - RATING_PIVOT) / RATING_PER_LEVEL)
+                .clamp(-MAX_IN_GAME_SHIFT, MAX_IN_GAME_SHIFT)
+        };
+    }
+
+    /// 当前已经结束的对局不计入近期胜负 (例如读取的已结束存档)
+    pub fn skip_game(&mut self) {
+        self.game_recorded = true;
+    }
+
+    /// 玩家手动选择了等级：以它为新的基础等级
+    fn override_level(&mut self, level: u32) {
+        self.base_level = level as f64;
+        self.in_game_shift = 0.0;
+        self.applied = Some(level);
+    }
+}
+
+/// 系统：自适应难度开启时，对局结束后记录胜负，局中跟随评分，并把算出的等级设置给 AI
+pub fn adapt_difficulty(
+    game_state: Res<GameState>,
+    player_score: Res<PlayerScore>,
+    mut adaptive: ResMut<AdaptiveDifficulty>,
+    mut ai: ResMut<SmartAgent>,
+) {
+    if !adaptive.enabled {
+        return;
+    }
+
+    // 等级不是本系统设置的，说明玩家在下拉菜单中手动选择了等级
+    if ai.skill_level() != adaptive.applied
+        && let Some(level) = ai.skill_level()
+    {
+        adaptive.override_level(level);
+    }
+
+    if game_state.is_game_over {
+        if !adaptive.game_recorded {
+            let player_stone = ai.get_stone().opponent();
+            let player_result = match game_state.winner {
+                Some(winner) if winner == player_stone => 1.0,
+                Some(_) => 0.0,
+                None => 0.5,
+            };
+            adaptive.game_recorded = true;
+            adaptive.record_game(player_result);
+            println!(
+                "自适应难度: 本局玩家得分 {}, 近期胜率 {:.0}%",
+                player_result,
+                adaptive.recent_win_rate().unwrap_or(0.0) * 100.0
+            );
+        }
+    } else {
+        if adaptive.game_recorded {
+            adaptive.game_recorded = false;
+        }
+        if player_score.is_changed() {
+            adaptive.update_in_game(player_score.current_rating, player_score.move_count);
+        }
+    }
+
+    let level = adaptive.effective_level();
+    if ai.skill_level() != Some(level) {
+        ai.set_skill_level(level);
+        adaptive.applied = Some(level);
+        println!("自适应难度: AI 调整为 {} 级", level);
+    }
+}
@@ -1,27 +1,434 @@
-use crate::game::{GRID_SIZE, GameState, Stone};
+use crate::game::{GRID_SIZE, GameState, Stone, move_to_notation};
 use crate::game_manager::check_victory;
+use crate::patterns::{Pattern, PatternReport, analyse_position};
+use crate::renlib::OpeningBook;
+use crate::skill::SkillLevel;
+use crate::threats::{five_points, four_points};
+use crate::transposition::{Bound, TranspositionTable, TtEntry, zobrist_hash, zobrist_key};
+use crate::weights::{EvalWeights, FEATURE_COUNT, Feature};
 use bevy::prelude::*;
+use rand::Rng;
+use rand::seq::SliceRandom;
 use std::cmp;
 use std::collections::HashSet; // 用于存储相关落子位置，避免重复
-
-const WIN_SCORE: i32 = 100_000_000; // 获胜得分
-const FIVE_SCORE: i32 = 1_000_000; // 五子连珠
-const OPEN_FOUR_SCORE: i32 = 10_000; // 活四
-const HALF_FOUR_SCORE: i32 = 1_000; // 冲四/死四
-const OPEN_THREE_SCORE: i32 = 1_000; // 活三
-const HALF_THREE_SCORE: i32 = 100; // 眠三/死三
-const OPEN_TWO_SCORE: i32 = 50; // 活二
-const HALF_TWO_SCORE: i32 = 10; // 眠二/死二
-const POSITIONAL_WEIGHT: i32 = 1; // 靠近中心的微小加分
-
-// 控制 AI 考虑的有效移动半径
-// 只考虑距离现有棋子 MOVE_RADIUS 范围内的空位
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+const WIN_SCORE: i32 = 100_000_000; // 获胜得分，实际分数为 WIN_SCORE - 连五时距根节点的层数
+pub const MATE_THRESHOLD: i32 = WIN_SCORE - 1_000; // 分数的绝对值超过它表示已算出胜负
+// 搜索窗口的边界。所有分数都落在 [-INFINITY, INFINITY] 内，取负和加减 1 都不会溢出
+const INFINITY: i32 = WIN_SCORE + 1;
+// 静态评估值的上限，保证评估值不会被误认为胜负分
+const MAX_EVAL: i32 = MATE_THRESHOLD - 1;
+// 渴望窗口的初始半宽 (约一个活三的分值)，失败时按 ASPIRATION_GROWTH 倍扩大
+const ASPIRATION_WINDOW: i32 = 1_000;
+const ASPIRATION_GROWTH: i32 = 4;
+// 各棋型的分值见 weights.rs 中的 EvalWeights，可以从权重文件读取
+
+// 控制 AI 考虑的有效移动半径 (默认值，难度等级可以修改)
+// 只考虑距离现有棋子 move_radius 范围内的空位
 const MOVE_RADIUS: usize = 2; // 可以调整这个值，越大AI考虑越多，但越慢
 
-#[derive(Resource)]
+// 失误时从静态估值最高的这么多步中随机挑选
+const BLUNDER_CANDIDATES: usize = 5;
+
+// 杀手着法表记录的最大层数
+const MAX_PLY: usize = 64;
+// 杀手着法的排序分，高于任何普通落子
+const KILLER_SCORE: u64 = 1 << 50;
+// 每访问这么多个节点检查一次是否超时
+const CLOCK_CHECK_INTERVAL: u64 = 1024;
+// 静态搜索 (叶节点之后只走冲四和封堵) 的最大层数
+const MAX_QUIESCENCE_PLY: u32 = 8;
+
+#[derive(Resource, Clone)]
 pub struct SmartAgent {
     stone: Stone,
-    search_depth: u32, // Minimax 搜索深度，控制 AI 强度
+    search_depth: u32,            // Minimax 搜索深度，控制 AI 强度
+    time_limit: Option<Duration>, // 每步思考时间上限，None 表示只按深度搜索
+    weights: EvalWeights,         // 评估函数的棋型分值
+    move_radius: usize,           // 候选落子离已有棋子的最大距离
+    eval_noise: i32,              // 评估值随机扰动的幅度，0 表示不加扰动
+    blunder_chance: f64,          // 每步随手乱下的概率
+    skill_level: Option<u32>,     // 当前的难度等级，None 表示直接按深度设置
+    // 开局库，局面在库中时直接从库中选择落子
+    book: Option<Arc<OpeningBook>>,
+}
+
+/// 一次搜索的报告
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchInfo {
+    pub best_move: (usize, usize),
+    pub score: i32,
+    pub depth: u32,              // 完整搜索过的深度，没有搜索 (如随手乱下) 时为 0
+    pub nodes: u64,              // 访问过的节点数
+    pub pv: Vec<(usize, usize)>, // 主要变例：双方预计的后续着法，第一步即 best_move
+    pub elapsed: Duration,       // 搜索用时
+}
+
+impl SearchInfo {
+    /// 不经搜索直接选定的落子
+    fn unsearched(best_move: (usize, usize)) -> Self {
+        SearchInfo {
+            best_move,
+            score: 0,
+            depth: 0,
+            nodes: 0,
+            pv: vec![best_move],
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// 每秒搜索的节点数
+    pub fn nps(&self) -> u64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds > 0.0 {
+            (self.nodes as f64 / seconds) as u64
+        } else {
+            0
+        }
+    }
+
+    /// 已算出的胜负：Some(n) 表示 AI 在 n 手内连五，Some(-n) 表示对手在 n 手内连五
+    pub fn mate_in(&self) -> Option<i32> {
+        if self.score >= MATE_THRESHOLD {
+            Some((WIN_SCORE - self.score + 1) / 2)
+        } else if self.score <= -MATE_THRESHOLD {
+            Some(-(WIN_SCORE + self.score) / 2)
+        } else {
+            None
+        }
+    }
+
+    /// 分数的文字形式，如 "+120"、"mate in 3"、"mated in 2"
+    pub fn score_text(&self) -> String {
+        match self.mate_in() {
+            Some(n) if n > 0 => format!("mate in {}", n),
+            Some(n) => format!("mated in {}", -n),
+            None => format!("{:+}", self.score),
+        }
+    }
+
+    /// 主要变例的棋谱记法，如 "h8 i9 j10"
+    pub fn pv_text(&self) -> String {
+        self.pv
+            .iter()
+            .map(|&(row, col)| move_to_notation(row, col))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+impl fmt::Display for SearchInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "depth {} score {} nodes {} nps {} time {}ms pv {}",
+            self.depth,
+            self.score_text(),
+            self.nodes,
+            self.nps(),
+            self.elapsed.as_millis(),
+            self.pv_text()
+        )
+    }
+}
+
+/// 后台思考 (见 SmartAgent::start_ponder)。丢弃时会通知后台线程尽快停止
+pub struct Ponder {
+    stone: Stone,          // 后台思考的 AI 一方
+    guess: (usize, usize), // 猜测的对手应手
+    position: GameState,   // 应手之后的局面，即后台搜索的根局面
+    agent: SmartAgent,     // 开始后台思考时的 AI 设置，用于判断搜索参数是否改变
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<Option<SearchInfo>>>,
+}
+
+impl Ponder {
+    /// 猜测的对手应手
+    pub fn guess(&self) -> (usize, usize) {
+        self.guess
+    }
+
+    /// 对手实际的应手是否与猜测一致
+    pub fn is_hit(&self, game_state: &GameState) -> bool {
+        game_state.current_turn == self.stone && game_state.board == self.position.board
+    }
+
+    /// 局面已经不可能走到猜测的局面 (重新开局、悔棋等)，后台搜索没有继续的意义
+    pub fn is_stale(&self, game_state: &GameState) -> bool {
+        self.position
+            .board
+            .iter()
+            .flatten()
+            .zip(game_state.board.iter().flatten())
+            .enumerate()
+            .any(|(i, (expected, actual))| {
+                let point = (i / (GRID_SIZE + 1), i % (GRID_SIZE + 1));
+                point != self.guess && expected.is_some() && expected != actual
+            })
+    }
+
+    /// ai 的搜索参数 (深度、时间上限、候选半径、评估扰动和权重) 是否与开始后台思考时相同。
+    /// 只改变了失误概率等不影响搜索的设置时，后台搜索的结果仍然可用
+    pub fn matches(&self, ai: &SmartAgent) -> bool {
+        self.agent.searches_like(ai)
+    }
+
+    /// 后台搜索已经结束 (搜到设定深度，或用完了时间上限)
+    pub fn is_finished(&self) -> bool {
+        self.handle.as_ref().is_none_or(JoinHandle::is_finished)
+    }
+
+    /// 取出已结束的后台搜索最后一轮完整搜索的结果。搜索线程已经结束，不会阻塞
+    fn result(mut self) -> Option<SearchInfo> {
+        let handle = self.handle.take()?;
+        handle.join().ok().flatten().filter(|info| info.depth > 0)
+    }
+}
+
+impl Drop for Ponder {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// AI 自己回合在后台线程中的搜索 (见 SmartAgent::choose_move_in_background)。
+/// 丢弃时会通知后台线程尽快停止
+pub struct Thinking {
+    position: GameState, // 搜索的局面
+    agent: SmartAgent,   // 开始搜索时的 AI 设置
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<Option<SearchInfo>>>,
+}
+
+impl Thinking {
+    /// 局面已经改变 (重新开局、悔棋、载入棋谱等)，或 ai 的搜索参数与开始搜索时不同，
+    /// 搜索结果不再可用
+    pub fn is_stale(&self, game_state: &GameState, ai: &SmartAgent) -> bool {
+        game_state.current_turn != self.position.current_turn
+            || game_state.board != self.position.board
+            || !self.agent.searches_like(ai)
+    }
+
+    /// 后台搜索已经结束
+    pub fn is_finished(&self) -> bool {
+        self.handle.as_ref().is_none_or(JoinHandle::is_finished)
+    }
+
+    /// 取出已结束的后台搜索的结果。搜索线程已经结束，不会阻塞
+    fn result(mut self) -> Option<SearchInfo> {
+        self.handle.take()?.join().ok().flatten()
+    }
+}
+
+impl Drop for Thinking {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// 内置 AI 正在进行的后台搜索
+#[derive(Resource, Default)]
+pub struct Pondering {
+    pub search: Option<Ponder>,     // 玩家思考时的后台思考
+    pub thinking: Option<Thinking>, // AI 自己回合的搜索
+}
+
+/// 单次搜索过程中的临时状态
+struct SearchContext {
+    deadline: Option<Instant>,
+    stop: Option<Arc<AtomicBool>>, // 由其他线程置位以中止搜索 (后台思考)
+    aborted: bool,                 // 超时或被中止后置位，此后的搜索结果全部作废
+    nodes: u64,                    // 已访问的节点数
+    ply: usize,                    // 当前局面距根节点的层数
+    hash: u64,                     // 当前局面的 Zobrist 哈希，随落子和悔棋增量更新
+    tt: TranspositionTable,        // 搜索过的局面的分数和最佳落子
+    killers: [[Option<(usize, usize)>; 2]; MAX_PLY], // 每层最近两个引起剪枝的落子
+    history: [[[u32; GRID_SIZE + 1]; GRID_SIZE + 1]; 2], // 按颜色和位置累计的剪枝得分
+}
+
+impl SearchContext {
+    fn new(deadline: Option<Instant>, game_state: &GameState) -> Self {
+        Self::with_table(deadline, game_state, TranspositionTable::new())
+    }
+
+    fn with_table(
+        deadline: Option<Instant>,
+        game_state: &GameState,
+        tt: TranspositionTable,
+    ) -> Self {
+        SearchContext {
+            deadline,
+            stop: None,
+            aborted: false,
+            nodes: 0,
+            ply: 0,
+            hash: zobrist_hash(game_state),
+            tt,
+            killers: [[None; 2]; MAX_PLY],
+            history: [[[0; GRID_SIZE + 1]; GRID_SIZE + 1]; 2],
+        }
+    }
+
+    /// 统计节点数并检查是否超时或被中止
+    fn visit(&mut self) -> bool {
+        self.nodes += 1;
+        if self.nodes.is_multiple_of(CLOCK_CHECK_INTERVAL)
+            && (self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+                || self
+                    .stop
+                    .as_ref()
+                    .is_some_and(|stop| stop.load(Ordering::Relaxed)))
+        {
+            self.aborted = true;
+        }
+        !self.aborted
+    }
+
+    /// 落子并更新哈希，位置无效时返回 false
+    fn play(&mut self, game_state: &mut GameState, (r, c): (usize, usize), stone: Stone) -> bool {
+        if game_state.apply_move(r, c, stone).is_err() {
+            return false;
+        }
+        self.hash ^= zobrist_key(r, c, stone);
+        self.ply += 1;
+        true
+    }
+
+    /// 撤销 play 的落子
+    fn unplay(&mut self, game_state: &mut GameState, (r, c): (usize, usize), stone: Stone) {
+        game_state.undo_move(r, c);
+        self.hash ^= zobrist_key(r, c, stone);
+        self.ply -= 1;
+    }
+
+    /// 记录引起剪枝的落子：更新本层的杀手着法和历史表
+    fn record_cutoff(&mut self, mv: (usize, usize), stone: Stone, depth: u32) {
+        let ply = self.ply;
+        if ply < MAX_PLY && self.killers[ply][0] != Some(mv) {
+            self.killers[ply][1] = self.killers[ply][0];
+            self.killers[ply][0] = Some(mv);
+        }
+        let score = &mut self.history[stone as usize][mv.0][mv.1];
+        *score = score.saturating_add(depth * depth);
+    }
+
+    /// 从根节点的 first 开始，沿置换表记录的最佳落子取出最多 max_len 步主要变例
+    fn principal_variation(
+        &self,
+        game_state: &GameState,
+        first: (usize, usize),
+        stone: Stone,
+        max_len: usize,
+    ) -> Vec<(usize, usize)> {
+        let mut state = game_state.clone();
+        let mut hash = self.hash;
+        let mut stone = stone;
+        let mut next = Some(first);
+        let mut pv = Vec::new();
+        while let Some((r, c)) = next {
+            if pv.len() >= max_len || state.apply_move(r, c, stone).is_err() {
+                break;
+            }
+            pv.push((r, c));
+            hash ^= zobrist_key(r, c, stone);
+            if check_victory(&state).is_some() {
+                break;
+            }
+            stone = stone.opponent();
+            next = self.tt.probe(hash).map(|entry| entry.best_move);
+        }
+        pv
+    }
+
+    /// 落子排序：置换表中的最佳落子最先，其次是本层的杀手着法，
+    /// 其余按落子处的局部连子分排列，相同时按历史得分排列
+    fn order_moves(
+        &self,
+        game_state: &GameState,
+        moves: Vec<(usize, usize)>,
+        stone: Stone,
+    ) -> Vec<(usize, usize)> {
+        let tt_move = self.tt.probe(self.hash).map(|entry| entry.best_move);
+        let killers = self.killers.get(self.ply).copied().unwrap_or([None; 2]);
+        let mut moves_with_scores: Vec<((usize, usize), u64)> = moves
+            .into_iter()
+            .map(|m| {
+                let score = if Some(m) == tt_move {
+                    u64::MAX
+                } else if Some(m) == killers[0] {
+                    KILLER_SCORE + 1
+                } else if Some(m) == killers[1] {
+                    KILLER_SCORE
+                } else {
+                    (local_score(game_state, m) << 32)
+                        + self.history[stone as usize][m.0][m.1] as u64
+                };
+                (m, score)
+            })
+            .collect();
+        moves_with_scores.sort_by_key(|&(_, score)| cmp::Reverse(score));
+        moves_with_scores.into_iter().map(|(m, _)| m).collect()
+    }
+}
+
+/// 胜负分数按距根节点的层数计算，存入置换表时换成距本节点的层数：
+/// 从不同的着法顺序在另一层到达同一局面时，取出后再按那一层换算回来
+fn score_to_tt(score: i32, ply: usize) -> i32 {
+    if score >= MATE_THRESHOLD {
+        score + ply as i32
+    } else if score <= -MATE_THRESHOLD {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+/// score_to_tt 的逆变换
+fn score_from_tt(score: i32, ply: usize) -> i32 {
+    if score >= MATE_THRESHOLD {
+        score - ply as i32
+    } else if score <= -MATE_THRESHOLD {
+        score + ply as i32
+    } else {
+        score
+    }
+}
+
+// 落子处一侧连续棋子数对应的排序分 (连续 4 子及以上按 4 计)
+const RUN_SCORES: [u64; 5] = [0, 1, 4, 16, 64];
+
+/// 落子的局部分：沿四个方向数落子处两侧紧挨着的同色棋子，
+/// 双方的连子都计入 (既是进攻也是防守)。只看落子附近，比评估整个棋盘便宜得多。
+fn local_score(game_state: &GameState, (r, c): (usize, usize)) -> u64 {
+    let mut score = 0;
+    for (dr, dc) in [(0, 1), (1, 0), (1, 1), (1, -1)] {
+        for stone in [Stone::Black, Stone::White] {
+            let mut run = 0;
+            for sign in [1, -1] {
+                let (mut nr, mut nc) = (r as isize, c as isize);
+                loop {
+                    nr += sign * dr;
+                    nc += sign * dc;
+                    if nr < 0 || nc < 0 || nr > GRID_SIZE as isize || nc > GRID_SIZE as isize {
+                        break;
+                    }
+                    if game_state.board[nr as usize][nc as usize] != Some(stone) {
+                        break;
+                    }
+                    run += 1;
+                }
+            }
+            score += RUN_SCORES[run.min(4)];
+        }
+    }
+    score
 }
 
 impl SmartAgent {
@@ -29,14 +436,56 @@ impl SmartAgent {
         SmartAgent {
             stone,
             search_depth: depth.max(1),
+            time_limit: None,
+            weights: EvalWeights::default(),
+            move_radius: MOVE_RADIUS,
+            eval_noise: 0,
+            blunder_chance: 0.0,
+            skill_level: None,
+            book: None,
         }
     }
 
+    /// 按难度等级创建 AI，参数见 skill.rs
+    pub fn with_skill_level(stone: Stone, level: u32) -> Self {
+        let mut agent = Self::new(stone, 1);
+        agent.set_skill_level(level);
+        agent
+    }
+
     /// 为当前 AI 的棋子找到最佳落子及其 Minimax 分数。
     pub fn find_best_move_and_score(
         &self,
         game_state: &GameState,
     ) -> Option<((usize, usize), i32)> {
+        self.search(game_state)
+            .map(|info| (info.best_move, info.score))
+    }
+
+    pub fn search(&self, game_state: &GameState) -> Option<SearchInfo> {
+        self.search_with(game_state, |_| {})
+    }
+
+    /// 迭代加深搜索到设定深度。较浅一轮记入置换表的最佳落子和历史表
+    /// 让下一轮的落子排序更准确；有时间限制时超时则采用上一轮完整搜索的结果。
+    /// 每完成一轮调用一次 report。
+    pub fn search_with(
+        &self,
+        game_state: &GameState,
+        report: impl FnMut(&SearchInfo),
+    ) -> Option<SearchInfo> {
+        let deadline = self.time_limit.map(|limit| Instant::now() + limit);
+        let ctx = SearchContext::new(deadline, game_state);
+        self.search_in(ctx, game_state, report)
+    }
+
+    /// 在给定的搜索上下文中迭代加深
+    fn search_in(
+        &self,
+        mut ctx: SearchContext,
+        game_state: &GameState,
+        mut report: impl FnMut(&SearchInfo),
+    ) -> Option<SearchInfo> {
         if game_state.is_game_over {
             return None;
         }
@@ -46,24 +495,61 @@ impl SmartAgent {
             return None;
         }
 
-        // 处理只有一个可选落子的情况：计算其分数
-        if relevant_moves.len() == 1 {
-            let move_coords = relevant_moves[0];
-            return self
-                .get_score_for_move(game_state, move_coords)
-                .map(|score| (move_coords, score));
+        let started = Instant::now();
+        let mut best = None;
+        let mut scores = Vec::new();
+        for depth in 1..=self.search_depth {
+            // 评估值随走棋方交替起伏，用两轮之前 (同一方走最后一步) 的分数估计本轮的分数
+            let expected = scores.len().checked_sub(2).map(|i| scores[i]);
+            match self.search_aspiration(&mut ctx, game_state, &relevant_moves, depth, expected) {
+                Some((best_move, score)) if !ctx.aborted => {
+                    scores.push(score);
+                    let info = SearchInfo {
+                        best_move,
+                        score,
+                        depth,
+                        nodes: ctx.nodes,
+                        pv: ctx.principal_variation(
+                            game_state,
+                            best_move,
+                            self.stone,
+                            depth as usize,
+                        ),
+                        elapsed: started.elapsed(),
+                    };
+                    report(&info);
+                    best = Some(info);
+                    // 已经找到必胜着法，无需继续加深
+                    if score >= MATE_THRESHOLD {
+                        break;
+                    }
+                }
+                _ => break,
+            }
         }
 
-        let mut best_move = relevant_moves[0];
-        let mut best_score = i32::MIN;
-        let mut alpha = i32::MIN;
-        let beta = i32::MAX;
+        // 连第一层都没搜完时，退回到排序后的第一步
+        let best = best.unwrap_or_else(|| {
+            SearchInfo::unsearched(ctx.order_moves(game_state, relevant_moves, self.stone)[0])
+        });
+        Some(SearchInfo {
+            nodes: ctx.nodes,
+            elapsed: started.elapsed(),
+            ..best
+        })
+    }
 
+    /// 按落子后的静态估值从高到低排列根节点的候选落子
+    fn order_root_moves(
+        &self,
+        game_state: &GameState,
+        relevant_moves: Vec<(usize, usize)>,
+    ) -> Vec<(usize, usize)> {
         let mut moves_with_scores: Vec<((usize, usize), i32)> = relevant_moves
             .into_iter()
             .map(|m| {
                 let mut temp_state = game_state.clone();
-                let mut score = i32::MIN;
+                let mut score = -INFINITY;
                 if temp_state.apply_move(m.0, m.1, self.stone).is_ok() {
                     score = self.evaluate_board(&temp_state);
                 }
@@ -72,89 +558,384 @@ impl SmartAgent {
             .collect();
 
         moves_with_scores.sort_by(|a, b| b.1.cmp(&a.1));
+        moves_with_scores.into_iter().map(|(m, _)| m).collect()
+    }
 
-        for ((r, c), _) in moves_with_scores {
-            if let Some(mut next_state) = game_state.make_move_simulated(r, c, self.stone) {
-                // 如果这一步能直接获胜，就选择它并返回最高分
-                if check_victory(&next_state) == Some(self.stone) {
-                    return Some(((r, c), WIN_SCORE + self.search_depth as i32));
-                }
+    /// 以预计的分数为中心设置渴望窗口搜索根节点，窗口越窄剪枝越多；
+    /// 结果落在窗口之外时逐步放宽窗口重新搜索
+    fn search_aspiration(
+        &self,
+        ctx: &mut SearchContext,
+        game_state: &GameState,
+        moves: &[(usize, usize)],
+        depth: u32,
+        expected: Option<i32>,
+    ) -> Option<((usize, usize), i32)> {
+        let mut delta = ASPIRATION_WINDOW;
+        let (mut alpha, mut beta) = match expected {
+            // 已算出胜负时分数很稳定，不需要窗口
+            Some(score) if score.abs() < MATE_THRESHOLD => (
+                cmp::max(score - delta, -INFINITY),
+                cmp::min(score + delta, INFINITY),
+            ),
+            _ => (-INFINITY, INFINITY),
+        };
 
-                let score = self.minimax(
-                    &mut next_state,
-                    self.search_depth - 1,
-                    false, // 轮到对手
-                    alpha,
-                    beta,
-                );
-
-                if score > best_score {
-                    best_score = score;
-                    best_move = (r, c);
-                }
-                alpha = cmp::max(alpha, score);
-                if beta <= alpha {
-                    break;
-                }
+        loop {
+            let (best_move, score) =
+                self.search_root(ctx, game_state, moves, depth, alpha, beta)?;
+            if score <= alpha && alpha > -INFINITY {
+                alpha = cmp::max(score - delta, -INFINITY);
+            } else if score >= beta && beta < INFINITY {
+                beta = cmp::min(score + delta, INFINITY);
+            } else {
+                return Some((best_move, score));
             }
+            delta = delta.saturating_mul(ASPIRATION_GROWTH);
         }
+    }
+
+    /// 以给定深度和窗口搜索根节点，超时返回 None
+    fn search_root(
+        &self,
+        ctx: &mut SearchContext,
+        game_state: &GameState,
+        moves: &[(usize, usize)],
+        depth: u32,
+        mut alpha: i32,
+        beta: i32,
+    ) -> Option<((usize, usize), i32)> {
+        let ordered_moves = ctx.order_moves(game_state, moves.to_vec(), self.stone);
+        let mut state = game_state.clone();
+        let alpha_orig = alpha;
+        let mut best_move = ordered_moves[0];
+        let mut best_score = -INFINITY;
+
+        for (index, mv) in ordered_moves.into_iter().enumerate() {
+            if !ctx.play(&mut state, mv, self.stone) {
+                continue;
+            }
+            // 如果这一步能直接获胜，就选择它并返回最高分
+            if check_victory(&state) == Some(self.stone) {
+                let score = WIN_SCORE - ctx.ply as i32;
+                ctx.unplay(&mut state, mv, self.stone);
+                return Some((mv, score));
+            }
+
+            let score = self.search_child(ctx, &mut state, depth, self.stone, index, alpha, beta);
+            ctx.unplay(&mut state, mv, self.stone);
+            if ctx.aborted {
+                return None;
+            }
+
+            if score > best_score {
+                best_score = score;
+                best_move = mv;
+            }
+            alpha = cmp::max(alpha, score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        Self::store_node(ctx, best_move, depth, best_score, alpha_orig, beta);
         Some((best_move, best_score))
     }
 
+    /// PVS：第一个子节点 (排序最靠前，最可能是最佳落子) 用完整窗口搜索；
+    /// 其余子节点先用零宽窗口验证它们不比当前最好的更好，失败时才用完整窗口重新搜索。
+    /// 返回从 stone (父节点走棋方) 角度看的分数。
+    #[allow(clippy::too_many_arguments)]
+    fn search_child(
+        &self,
+        ctx: &mut SearchContext,
+        game_state: &mut GameState,
+        depth: u32,
+        stone: Stone,
+        index: usize,
+        alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        let opponent = stone.opponent();
+        if index == 0 {
+            return -self.negamax(ctx, game_state, depth - 1, opponent, -beta, -alpha);
+        }
+        let score = -self.negamax(ctx, game_state, depth - 1, opponent, -alpha - 1, -alpha);
+        if score > alpha && score < beta {
+            -self.negamax(ctx, game_state, depth - 1, opponent, -beta, -alpha)
+        } else {
+            score
+        }
+    }
+
     /// 计算指定落子的 Minimax 分数。
     pub fn get_score_for_move(
         &self,
         game_state: &GameState,
         move_coords: (usize, usize),
     ) -> Option<i32> {
-        // 使用 make_move_simulated 避免修改原始状态
-        if let Some(mut next_state) =
-            game_state.make_move_simulated(move_coords.0, move_coords.1, self.stone)
+        let mut ctx = SearchContext::with_table(None, game_state, TranspositionTable::small());
+        self.score_move_in(&mut ctx, game_state, move_coords)
+    }
+
+    /// 依次计算 moves 中每个落子的分数 (跳过无效的落子)，所有落子共用一个置换表。
+    /// stop 置位时返回 None
+    pub fn get_scores_for_moves(
+        &self,
+        game_state: &GameState,
+        moves: &[(usize, usize)],
+        stop: &AtomicBool,
+    ) -> Option<Vec<((usize, usize), i32)>> {
+        let mut ctx = SearchContext::with_table(None, game_state, TranspositionTable::small());
+        let mut scores = Vec::new();
+        for &mv in moves {
+            if stop.load(Ordering::Relaxed) {
+                return None;
+            }
+            if let Some(score) = self.score_move_in(&mut ctx, game_state, mv) {
+                scores.push((mv, score));
+            }
+        }
+        Some(scores)
+    }
+
+    /// 先算出 move_coords 的分数，再在同一个置换表中搜索最佳落子。
+    /// 评价落子和复盘分析只搜几层，用小置换表即可。返回 (这步棋的分数, 搜索结果)
+    pub fn search_with_move(
+        &self,
+        game_state: &GameState,
+        move_coords: (usize, usize),
+    ) -> Option<(i32, Option<SearchInfo>)> {
+        let mut ctx = SearchContext::with_table(None, game_state, TranspositionTable::small());
+        let played = self.score_move_in(&mut ctx, game_state, move_coords)?;
+        ctx.deadline = self.time_limit.map(|limit| Instant::now() + limit);
+        ctx.hash = zobrist_hash(game_state);
+        ctx.ply = 0;
+        ctx.nodes = 0;
+        Some((played, self.search_in(ctx, game_state, |_| {})))
+    }
+
+    /// 在 ctx 中计算 move_coords 的分数。ctx 的置换表可以在多次调用之间共用
+    fn score_move_in(
+        &self,
+        ctx: &mut SearchContext,
+        game_state: &GameState,
+        move_coords: (usize, usize),
+    ) -> Option<i32> {
+        // 使用 make_move_simulated 避免修改原始状态，无效的落子返回 None
+        let mut next_state =
+            game_state.make_move_simulated(move_coords.0, move_coords.1, self.stone)?;
+        // 检查落子后是否立即获胜
+        if check_victory(&next_state) == Some(self.stone) {
+            return Some(WIN_SCORE - 1);
+        }
+        // 如果没有获胜，则为对手搜索，对手的分数取负即为 AI 的分数
+        ctx.hash = zobrist_hash(&next_state);
+        ctx.ply = 1;
+        let score = -self.negamax(
+            ctx,
+            &mut next_state,
+            self.search_depth - 1, // 深度 - 1
+            self.stone.opponent(), // 轮到对手
+            -INFINITY,
+            INFINITY,
+        );
+        Some(score)
+    }
+
+    /// 评价本 AI 一方在 game_state 下走 move_coords 这步棋，返回 (最佳落子的分数, 这步棋的分数)。
+    /// 两者都按设定深度搜索，差值即这步棋相对最佳落子损失的分数
+    pub fn grade_move(
+        &self,
+        game_state: &GameState,
+        move_coords: (usize, usize),
+    ) -> Option<(i32, i32)> {
+        let (played, search) = self.search_with_move(game_state, move_coords)?;
+        let best = search.map_or(played, |info| info.score);
+        // 这步棋不在候选落子中或搜索不一致时，可能比“最佳”还好
+        Some((best.max(played), played))
+    }
+
+    pub fn make_move(&self, game_state: &GameState) -> Option<(usize, usize)> {
+        self.choose_move(game_state).map(|result| result.best_move)
+    }
+
+    /// 选择本步的落子：按难度等级的失误概率随手乱下，局面在开局库中时走库中的着法，
+    /// 否则正常搜索
+    pub fn choose_move(&self, game_state: &GameState) -> Option<SearchInfo> {
+        self.roll_blunder(game_state)
+            .or_else(|| self.book_move(game_state))
+            .or_else(|| self.search(game_state))
+    }
+
+    /// 与 choose_move 相同，但先利用后台思考：猜中对手的应手时，后台搜索的正是当前局面，
+    /// 采用它的结果；没猜中时丢弃后台搜索并正常搜索。
+    /// 猜中但后台搜索还没有结束时不等待，把 ponder 交还给调用者 (Err)，下一帧再调用。
+    /// 后台搜索本身不超过每步的时间上限，因此猜中后最多再等时间上限的剩余部分
+    pub fn choose_move_after_ponder(
+        &self,
+        game_state: &GameState,
+        ponder: Ponder,
+    ) -> Result<Option<SearchInfo>, Box<Ponder>> {
+        if !ponder.is_hit(game_state) {
+            drop(ponder);
+            return Ok(self.choose_move(game_state));
+        }
+        if !ponder.is_finished() {
+            return Err(Box::new(ponder));
+        }
+        // 失误和开局库在后台搜索结束后才判断，每步只掷一次失误概率
+        if let Some(chosen) = self
+            .roll_blunder(game_state)
+            .or_else(|| self.book_move(game_state))
         {
-            // 检查落子后是否立即获胜
-            if check_victory(&next_state) == Some(self.stone) {
-                return Some(WIN_SCORE + self.search_depth as i32);
+            return Ok(Some(chosen));
+        }
+        Ok(ponder.result().or_else(|| self.search(game_state)))
+    }
+
+    /// 与 choose_move 相同，但搜索在后台线程中进行，不阻塞调用者：需要搜索时开始后台搜索，
+    /// 交给调用者保存 (Err)，下一帧连同它再调用，搜索结束后返回结果。
+    /// thinking 已经过时 (局面或搜索参数改变) 时丢弃它，重新选择落子
+    pub fn choose_move_in_background(
+        &self,
+        game_state: &GameState,
+        thinking: Option<Thinking>,
+    ) -> Result<Option<SearchInfo>, Box<Thinking>> {
+        if let Some(thinking) = thinking
+            && !thinking.is_stale(game_state, self)
+        {
+            if !thinking.is_finished() {
+                return Err(Box::new(thinking));
             }
-            // 如果没有获胜，则为对手运行 minimax
-            let score = self.minimax(
-                &mut next_state,
-                self.search_depth - 1, // 深度 - 1
-                false,                 // 轮到对手 (最小化玩家)
-                i32::MIN,              // Alpha
-                i32::MAX,              // Beta
-            );
-            Some(score)
+            return Ok(thinking.result().or_else(|| self.search(game_state)));
+        }
+        // 失误和开局库不需要搜索，直接给出结果
+        if let Some(chosen) = self
+            .roll_blunder(game_state)
+            .or_else(|| self.book_move(game_state))
+        {
+            return Ok(Some(chosen));
+        }
+        let (stop, handle) = self.spawn_search(game_state);
+        Err(Box::new(Thinking {
+            position: game_state.clone(),
+            agent: self.clone(),
+            stop,
+            handle: Some(handle),
+        }))
+    }
+
+    /// 从开局库中当前局面的后续着法里随机选一个
+    fn book_move(&self, game_state: &GameState) -> Option<SearchInfo> {
+        let moves = self.book.as_ref()?.moves(game_state);
+        let chosen = moves.choose(&mut rand::thread_rng())?;
+        Some(SearchInfo::unsearched((chosen.row, chosen.col)))
+    }
+
+    /// 按难度等级的失误概率决定本步是否随手乱下
+    fn roll_blunder(&self, game_state: &GameState) -> Option<SearchInfo> {
+        if self.blunder_chance > 0.0 && rand::thread_rng().gen_bool(self.blunder_chance) {
+            self.blunder_move(game_state).map(SearchInfo::unsearched)
         } else {
-            None // 提供了无效的移动
+            None
         }
     }
 
-    pub fn make_move(&self, game_state: &GameState) -> Option<(usize, usize)> {
-        self.find_best_move_and_score(game_state).map(|(mv, _)| mv)
+    /// AI 落子后开始后台思考：用刚才搜索的主要变例中的第二步猜测对手的应手，
+    /// 在对手思考期间搜索应手之后的局面，搜到设定深度为止。
+    /// 有每步时间上限时后台搜索也不超过这个时间 (从开始后台思考算起)，
+    /// 因此后台思考不会让 AI 用掉比正常思考更多的时间。
+    /// 没有可猜的应手或应手后已分出胜负时返回 None。
+    pub fn start_ponder(&self, game_state: &GameState, info: &SearchInfo) -> Option<Ponder> {
+        let guess = *info.pv.get(1)?;
+        let mut position = game_state.clone();
+        position
+            .apply_move(guess.0, guess.1, self.stone.opponent())
+            .ok()?;
+        if check_victory(&position).is_some() || position.get_valid_moves().is_empty() {
+            return None;
+        }
+        position.current_turn = self.stone;
+
+        let (stop, handle) = self.spawn_search(&position);
+        Some(Ponder {
+            stone: self.stone,
+            guess,
+            position,
+            agent: self.clone(),
+            stop,
+            handle: Some(handle),
+        })
     }
 
-    /// Minimax 递归函数 (带有 Alpha-Beta 剪枝)
-    /// 参数 game_state 现在是 可变引用 (&mut GameState)
-    fn minimax(
+    /// 在后台线程中搜索 position，有每步时间上限时从现在算起。返回中止搜索的标志和线程
+    fn spawn_search(
         &self,
+        position: &GameState,
+    ) -> (Arc<AtomicBool>, JoinHandle<Option<SearchInfo>>) {
+        let stop = Arc::new(AtomicBool::new(false));
+        let deadline = self.time_limit.map(|limit| Instant::now() + limit);
+        let mut ctx = SearchContext::new(deadline, position);
+        ctx.stop = Some(stop.clone());
+        let (agent, position) = (self.clone(), position.clone());
+        let handle = thread::spawn(move || agent.search_in(ctx, &position, |_| {}));
+        (stop, handle)
+    }
+
+    /// 两个 AI 的搜索参数 (执子、深度、时间上限、候选半径、评估扰动和权重) 是否相同，
+    /// 相同时对同一局面的搜索结果可以互换
+    fn searches_like(&self, other: &SmartAgent) -> bool {
+        self.stone == other.stone
+            && self.search_depth == other.search_depth
+            && self.time_limit == other.time_limit
+            && self.move_radius == other.move_radius
+            && self.eval_noise == other.eval_noise
+            && self.weights == other.weights
+    }
+
+    /// 模拟低水平玩家的失误：不搜索，直接在静态估值靠前的几步中随机选一步。
+    /// 能直接连五时不会失误，返回 None 交给正常搜索。
+    fn blunder_move(&self, game_state: &GameState) -> Option<(usize, usize)> {
+        if game_state.is_game_over {
+            return None;
+        }
+        let relevant_moves = self.get_relevant_moves(game_state);
+        let wins_now = relevant_moves.iter().any(|&(r, c)| {
+            game_state
+                .make_move_simulated(r, c, self.stone)
+                .is_some_and(|next| check_victory(&next) == Some(self.stone))
+        });
+        if wins_now {
+            return None;
+        }
+        let ordered_moves = self.order_root_moves(game_state, relevant_moves);
+        let candidates = &ordered_moves[..ordered_moves.len().min(BLUNDER_CANDIDATES)];
+        candidates.choose(&mut rand::thread_rng()).copied()
+    }
+
+    /// Negamax 递归函数 (带有 Alpha-Beta 剪枝的 PVS)。
+    /// 分数总是从 stone (本节点走棋方) 的角度计算：子节点的分数取负即为本节点的分数，
+    /// 因此双方共用同一段代码。返回值不限于 [alpha, beta] (fail-soft)。
+    fn negamax(
+        &self,
+        ctx: &mut SearchContext,
         game_state: &mut GameState, // 传入可变引用以进行 in-place 修改
         depth: u32,
-        maximizing_player: bool, // true: 当前是 AI (最大化玩家) 的回合, false: 当前是对手 (最小化玩家) 的回合
+        stone: Stone,
         mut alpha: i32,
         mut beta: i32,
     ) -> i32 {
+        // 超时后立即返回，结果会被调用者丢弃
+        if !ctx.visit() {
+            return 0;
+        }
+
         // --- 终止条件 (Base Cases) ---
 
-        // 1. 检查游戏是否在本状态结束 (胜利/失败)
-        // 注意：check_victory 应该检查传入的 game_state
+        // 1. 检查游戏是否在本状态结束 (胜利/失败)，越快分出胜负分数的绝对值越大
         if let Some(winner) = check_victory(game_state) {
-            return if winner == self.stone {
-                // AI 获胜：分数高，且深度越高（越快获胜）分数相对越高
-                WIN_SCORE + depth as i32
-            } else {
-                // 对手获胜：分数低（负分），深度越高（对手越快获胜）负分越绝对
-                -(WIN_SCORE + depth as i32)
-            };
+            let score = WIN_SCORE - ctx.ply as i32;
+            return if winner == stone { score } else { -score };
         }
 
         // 2. 检查是否平局 (没有相关联的有效移动 left)
@@ -166,123 +947,270 @@ impl SmartAgent {
                 return 0; // 和棋分数为 0
             }
             // 如果有有效移动但没有相关移动，评估当前状态
-            return self.evaluate_board(game_state);
+            return self.evaluate_for(game_state, stone);
         }
 
         // 3. 检查是否达到搜索深度限制
         if depth == 0 {
-            // 到达叶节点，评估当前棋盘状态
-            return self.evaluate_board(game_state);
+            // 到达叶节点，先用静态搜索解决悬而未决的冲四，再评估棋盘
+            return self.quiescence(ctx, game_state, stone, alpha, beta, 0);
+        }
+
+        // 4. 置换表：同一局面已经以足够的深度搜索过时，直接使用记录的分数或收窄窗口
+        let (alpha_orig, beta_orig) = (alpha, beta);
+        if let Some(entry) = ctx.tt.probe(ctx.hash)
+            && entry.depth >= depth
+        {
+            let score = score_from_tt(entry.score, ctx.ply);
+            match entry.bound {
+                Bound::Exact => return score,
+                Bound::Lower => alpha = cmp::max(alpha, score),
+                Bound::Upper => beta = cmp::min(beta, score),
+            }
+            if alpha >= beta {
+                return score;
+            }
         }
 
         // --- 递归步骤 ---
 
-        let current_player_stone = if maximizing_player {
-            self.stone
-        } else {
-            self.stone.opponent()
-        };
+        // 落子顺序：置换表最佳落子、杀手着法和历史表，不需要对子节点做静态评估
+        let ordered_moves = ctx.order_moves(game_state, relevant_moves, stone);
+        let mut best_move = ordered_moves[0];
+        let mut best_score = -INFINITY;
 
-        // --- 落子顺序优化：评估当前层的所有相关移动，并排序 ---
-        let mut moves_with_scores: Vec<((usize, usize), i32)> = relevant_moves
-            .into_iter()
-            .map(|m| {
-                // 临时落子并评估即时分数用于排序
-                let mut score = 0; // Default score
-                if game_state
-                    .apply_move(m.0, m.1, current_player_stone)
-                    .is_ok()
-                {
-                    score = self.evaluate_board(game_state);
-                    game_state.undo_move(m.0, m.1); // 立即悔棋
-                } else {
-                    // Should not happen with get_relevant_moves + apply_move
-                    // eprintln!("apply_move for sorting failed for ({}, {})", m.0, m.1); // Error debug
-                }
-                (m, score)
-            })
-            .collect();
+        for (index, mv) in ordered_moves.into_iter().enumerate() {
+            // 在当前状态上“落子”（in-place 修改）
+            if !ctx.play(game_state, mv, stone) {
+                continue;
+            }
+            let score = self.search_child(ctx, game_state, depth, stone, index, alpha, beta);
+            // “悔棋”：恢复到修改前的状态
+            ctx.unplay(game_state, mv, stone);
 
-        // 排序移动：最大化玩家按估值从高到低排，最小化玩家按估值从低到高排
-        if maximizing_player {
-            moves_with_scores.sort_by(|a, b| b.1.cmp(&a.1));
-        } else {
-            moves_with_scores.sort_by(|a, b| a.1.cmp(&b.1));
+            if score > best_score {
+                best_score = score;
+                best_move = mv;
+            }
+            alpha = cmp::max(alpha, score);
+
+            // --- 剪枝 ---
+            // 本节点已经能得到不低于 beta 的分数，对手在上一层不会让局面走到这里
+            if alpha >= beta {
+                ctx.record_cutoff(mv, stone, depth);
+                break;
+            }
         }
-        //println!("Minimax 在深度 {} 的 {} 回合落子排序： {:?}", depth, if maximizing_player {"AI"} else {"对手"}, moves_with_scores); // Debug
 
-        if maximizing_player {
-            // --- AI (最大化玩家) 的回合 ---
-            let mut max_eval = i32::MIN; // 初始化最大评分为负无穷
+        Self::store_node(ctx, best_move, depth, best_score, alpha_orig, beta_orig);
+        best_score
+    }
 
-            // 遍历所有可能的移动 (已排序)
-            for ((r, c), _) in moves_with_scores {
-                // 在当前状态上“落子”（in-place 修改）
-                if game_state.apply_move(r, c, current_player_stone).is_ok() {
-                    // 递归调用 minimax，切换到最小化玩家的回合
-                    let eval = self.minimax(game_state, depth - 1, false, alpha, beta);
+    /// 参考实现：不用置换表、落子排序、PVS 和渴望窗口的 fail-soft Alpha-Beta，
+    /// 候选落子按坐标顺序搜索到设定深度，叶节点的静态搜索和评估与 search 相同。
+    /// 只用于验证 search 的结果不变、节点数更少，对局中不使用
+    pub fn search_alpha_beta(&self, game_state: &GameState) -> Option<SearchInfo> {
+        if game_state.is_game_over {
+            return None;
+        }
+        let mut moves = self.get_relevant_moves(game_state);
+        moves.sort();
+        let started = Instant::now();
+        let mut ctx = SearchContext::new(None, game_state);
+        let mut state = game_state.clone();
+        let mut alpha = -INFINITY;
+        let mut best: Option<((usize, usize), i32)> = None;
+        for mv in moves {
+            if !ctx.play(&mut state, mv, self.stone) {
+                continue;
+            }
+            let score = -self.alpha_beta(
+                &mut ctx,
+                &mut state,
+                self.search_depth - 1,
+                self.stone.opponent(),
+                -INFINITY,
+                -alpha,
+            );
+            ctx.unplay(&mut state, mv, self.stone);
+            if best.is_none_or(|(_, best_score)| score > best_score) {
+                best = Some((mv, score));
+            }
+            alpha = cmp::max(alpha, score);
+        }
+        let (best_move, score) = best?;
+        Some(SearchInfo {
+            best_move,
+            score,
+            depth: self.search_depth,
+            nodes: ctx.nodes,
+            pv: vec![best_move],
+            elapsed: started.elapsed(),
+        })
+    }
 
-                    // “悔棋”：恢复到修改前的状态
-                    game_state.undo_move(r, c);
+    /// 用 search_alpha_beta 的参考搜索计算 move_coords 这步棋的准确分数
+    pub fn alpha_beta_score_for_move(
+        &self,
+        game_state: &GameState,
+        move_coords: (usize, usize),
+    ) -> Option<i32> {
+        let mut state = game_state.clone();
+        let mut ctx = SearchContext::new(None, game_state);
+        if !ctx.play(&mut state, move_coords, self.stone) {
+            return None;
+        }
+        Some(-self.alpha_beta(
+            &mut ctx,
+            &mut state,
+            self.search_depth - 1,
+            self.stone.opponent(),
+            -INFINITY,
+            INFINITY,
+        ))
+    }
 
-                    max_eval = cmp::max(max_eval, eval); // 更新最大评估值
+    // search_alpha_beta 的递归部分，终止条件与 negamax 相同
+    fn alpha_beta(
+        &self,
+        ctx: &mut SearchContext,
+        game_state: &mut GameState,
+        depth: u32,
+        stone: Stone,
+        mut alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        ctx.visit();
+        if let Some(winner) = check_victory(game_state) {
+            let score = WIN_SCORE - ctx.ply as i32;
+            return if winner == stone { score } else { -score };
+        }
+        let mut moves = self.get_relevant_moves(game_state);
+        if moves.is_empty() {
+            if game_state.get_valid_moves().is_empty() {
+                return 0;
+            }
+            return self.evaluate_for(game_state, stone);
+        }
+        if depth == 0 {
+            return self.quiescence(ctx, game_state, stone, alpha, beta, 0);
+        }
 
-                    // --- Alpha 更新 ---
-                    // Alpha 是最大化玩家到目前为止能确保得到的最好分数
-                    alpha = cmp::max(alpha, eval);
+        moves.sort();
+        let mut best_score = -INFINITY;
+        for mv in moves {
+            if !ctx.play(game_state, mv, stone) {
+                continue;
+            }
+            let score =
+                -self.alpha_beta(ctx, game_state, depth - 1, stone.opponent(), -beta, -alpha);
+            ctx.unplay(game_state, mv, stone);
+            best_score = cmp::max(best_score, score);
+            alpha = cmp::max(alpha, score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        best_score
+    }
 
-                    // --- Beta 剪枝 ---
-                    // 如果 Beta <= Alpha，表示最小化玩家在之前已经找到了一条更差的路径
-                    // （对于最大化玩家来说分数更低），所以最大化玩家不会选择当前这条路径。
-                    // 可以停止搜索该分支。
-                    if beta <= alpha {
-                        // println!("Alpha-Beta 剪枝发生，深度 {} (alpha={}, beta={})", depth, alpha, beta); // Debug
-                        break; // 剪枝
-                    }
-                } else {
-                    // 理论上 get_relevant_moves + apply_move 不会失败
-                    // eprintln!("apply_move 在深度 {} 的 ({}, {}) 失败", depth, r, c); // Error debug
-                }
+    /// 静态搜索：叶节点上如果还有冲四没有应对，直接评估会看不到下一步的连五 (水平线效应)。
+    /// 这里继续只走强制性的落子——能连五就连五，对手有成五点就封堵，否则可以冲四——
+    /// 直到局面平静 (没有四) 后再评估。平静时走棋方也可以不冲四，以评估值为下限。
+    fn quiescence(
+        &self,
+        ctx: &mut SearchContext,
+        game_state: &mut GameState,
+        stone: Stone,
+        mut alpha: i32,
+        beta: i32,
+        qply: u32,
+    ) -> i32 {
+        if !ctx.visit() {
+            return 0;
+        }
+
+        // 走棋方有成五点：下一步获胜
+        if !five_points(game_state, stone).is_empty() {
+            return WIN_SCORE - (ctx.ply as i32 + 1);
+        }
+
+        // 对手有两个以上成五点：挡不住，对手在下下步获胜
+        let threats = five_points(game_state, stone.opponent());
+        if threats.len() >= 2 {
+            return -(WIN_SCORE - (ctx.ply as i32 + 2));
+        }
+        if qply >= MAX_QUIESCENCE_PLY {
+            return self.evaluate_for(game_state, stone);
+        }
+
+        // 对手有一个成五点：只能封堵
+        if let [block] = threats[..] {
+            if !ctx.play(game_state, block, stone) {
+                return self.evaluate_for(game_state, stone);
             }
-            max_eval // 返回该节点的最大评估值
-        } else {
-            // --- 对手 (最小化玩家) 的回合 ---
-            let mut min_eval = i32::MAX; // 初始化最小评分为正无穷
-
-            // 遍历所有可能的移动 (已排序)
-            for ((r, c), _) in moves_with_scores {
-                // 在当前状态上“落子”（in-place 修改）
-                if game_state.apply_move(r, c, current_player_stone).is_ok() {
-                    // 递归调用 minimax，切换到最大化玩家的回合
-                    let eval = self.minimax(game_state, depth - 1, true, alpha, beta);
-
-                    // “悔棋”：恢复到修改前的状态
-                    game_state.undo_move(r, c);
-
-                    min_eval = cmp::min(min_eval, eval); // 更新最小评估值
-
-                    // --- Beta 更新 ---
-                    // Beta 是最小化玩家到目前为止能确保得到的最好分数（上限）
-                    beta = cmp::min(beta, eval);
-
-                    // --- Alpha 剪枝 ---
-                    // 如果 Beta <= Alpha，表示最大化玩家在之前已经找到了一条更好的路径
-                    // （对于最小化玩家来说分数更高），所以最小化玩家会避免当前这条路径。
-                    // 可以停止搜索该分支。
-                    if beta <= alpha {
-                        // println!("Alpha-Beta 剪枝发生，深度 {} (alpha={}, beta={})", depth, alpha, beta); // Debug
-                        break; // 剪枝
-                    }
-                } else {
-                    // 理论上 get_relevant_moves + apply_move 不会失败
-                    // eprintln!("apply_move 在深度 {} 的 ({}, {}) 失败", depth, r, c); // Error debug
-                }
+            let score =
+                -self.quiescence(ctx, game_state, stone.opponent(), -beta, -alpha, qply + 1);
+            ctx.unplay(game_state, block, stone);
+            return score;
+        }
+
+        // 局面平静：评估值作为下限，再看冲四能否得到更好的结果
+        let mut best_score = self.evaluate_for(game_state, stone);
+        if best_score >= beta {
+            return best_score;
+        }
+        alpha = cmp::max(alpha, best_score);
+
+        for mv in four_points(game_state, stone) {
+            if !ctx.play(game_state, mv, stone) {
+                continue;
+            }
+            let score =
+                -self.quiescence(ctx, game_state, stone.opponent(), -beta, -alpha, qply + 1);
+            ctx.unplay(game_state, mv, stone);
+
+            best_score = cmp::max(best_score, score);
+            alpha = cmp::max(alpha, score);
+            if alpha >= beta {
+                break;
             }
-            min_eval // 返回该节点的最小评估值
         }
+        best_score
+    }
+
+    /// 将节点的搜索结果记入置换表，alpha_orig/beta_orig 为进入节点时的窗口
+    fn store_node(
+        ctx: &mut SearchContext,
+        best_move: (usize, usize),
+        depth: u32,
+        score: i32,
+        alpha_orig: i32,
+        beta_orig: i32,
+    ) {
+        // 超时中断的结果不可靠
+        if ctx.aborted {
+            return;
+        }
+        let bound = if score <= alpha_orig {
+            Bound::Upper
+        } else if score >= beta_orig {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        ctx.tt.store(
+            ctx.hash,
+            TtEntry {
+                best_move,
+                depth,
+                score: score_to_tt(score, ctx.ply),
+                bound,
+            },
+        );
     }
 
-    /// 生成相关联的落子位置列表：距离现有棋子 MOVE_RADIUS 范围内的空位。
+    /// 生成相关联的落子位置列表：距离现有棋子 move_radius 范围内的空位。
     /// 这可以显著剪枝搜索空间。
     fn get_relevant_moves(&self, game_state: &GameState) -> Vec<(usize, usize)> {
         let mut relevant_moves = HashSet::new(); // 使用 HashSet 避免重复位置
@@ -306,9 +1234,10 @@ impl SmartAgent {
             for c in 0..board_size {
                 // 如果当前位置有棋子
                 if game_state.board[r][c].is_some() {
-                    // 以这个棋子为中心，检查半径 MOVE_RADIUS 范围内的空位
-                    for dr in -(MOVE_RADIUS as isize)..=(MOVE_RADIUS as isize) {
-                        for dc in -(MOVE_RADIUS as isize)..=(MOVE_RADIUS as isize) {
+                    // 以这个棋子为中心，检查半径 move_radius 范围内的空位
+                    let radius = self.move_radius as isize;
+                    for dr in -radius..=radius {
+                        for dc in -radius..=radius {
                             // 跳过棋子本身的位置
                             if dr == 0 && dc == 0 {
                                 continue;
@@ -364,169 +1293,63 @@ impl SmartAgent {
 
     /// 评估整个棋盘状态
     /// 从 AI 的角度计算分数，正分表示 AI 有利，负分表示对手有利。
-    /// 遍历所有可能的 5 子棋型窗口进行评估。
     fn evaluate_board(&self, game_state: &GameState) -> i32 {
-        let mut ai_score = 0;
-        let mut opponent_score = 0;
-        let opponent_stone = self.stone.opponent();
-        let board_size = GRID_SIZE + 1;
-
-        // 定义检查方向：水平、垂直、主对角线、副对角线
-        let directions = [(0, 1), (1, 0), (1, 1), (1, -1)]; // (dr, dc)
-
-        // 遍历棋盘上的每个点，作为潜在 5 子棋型线段的 **起点**
-        // 一个 5 子棋型从 (r, c) 开始，沿 (dr, dc) 方向，结束点是 (r + 4*dr, c + 4*dc)
-        // 因此，起点的范围需要确保结束点在棋盘内。
-        for r in 0..board_size {
-            for c in 0..board_size {
-                for &(dr, dc) in &directions {
-                    // 计算棋型线段的结束点
-                    let end_r = r as isize + 4 * dr;
-                    let end_c = c as isize + 4 * dc;
-
-                    // 如果结束点在棋盘界限内，说明可以形成一个 5 子棋型窗口
-                    if end_r >= 0
-                        && end_r < board_size as isize
-                        && end_c >= 0
-                        && end_c < board_size as isize
-                    {
-                        // 评估从 (r, c) 开始，沿 (dr, dc) 方向的这个 5 子棋型窗口
-                        // evaluate_pattern 函数会查看这 5 个位置以及它们两端的空/阻挡情况
-                        ai_score += self.evaluate_pattern(game_state, r, c, dr, dc, self.stone);
-                        opponent_score +=
-                            self.evaluate_pattern(game_state, r, c, dr, dc, opponent_stone);
-                    }
-                }
-            }
+        // AI 的总棋型分数 - 对手的总棋型分数 (均包含位置分)
+        let report = analyse_position(game_state);
+        let ai_features = Self::features_from(game_state, &report, self.stone);
+        let opponent_features = Self::features_from(game_state, &report, self.stone.opponent());
+        let mut score = self
+            .weights
+            .score(&ai_features)
+            .saturating_sub(self.weights.score(&opponent_features));
+        if self.eval_noise > 0 {
+            // 低难度下的随机扰动，让 AI 每局下法不同且会看错局面
+            score = score
+                .saturating_add(rand::thread_rng().gen_range(-self.eval_noise..=self.eval_noise));
         }
+        score.clamp(-MAX_EVAL, MAX_EVAL)
+    }
 
-        // --- 位置加分  ---
-        // 让 AI 稍微倾向于占据中心位置
-        let mut positional_score = 0;
-        if POSITIONAL_WEIGHT > 0 {
-            let center = (GRID_SIZE as i32) / 2; // 使用 i32 进行计算
-            for r in 0..board_size {
-                for c in 0..board_size {
-                    let r_i32 = r as i32;
-                    let c_i32 = c as i32;
-                    // 计算曼哈顿距离到中心
-                    let dist_from_center = (r_i32 - center).abs() + (c_i32 - center).abs();
-                    // 距离中心越近，加分越多
-                    let bonus =
-                        (POSITIONAL_WEIGHT * (GRID_SIZE as i32 / 2) - dist_from_center).max(0);
-
-                    if game_state.board[r][c] == Some(self.stone) {
-                        positional_score += bonus;
-                    } else if game_state.board[r][c] == Some(opponent_stone) {
-                        positional_score -= bonus;
-                    }
-                }
-            }
-        }
+    /// 从 stone 一方的角度评估棋盘
+    fn evaluate_for(&self, game_state: &GameState, stone: Stone) -> i32 {
+        let score = self.evaluate_board(game_state);
+        if stone == self.stone { score } else { -score }
+    }
 
-        // --- 最终评估分数 ---
-        // AI 的总棋型分数 - 对手的总棋型分数 + 位置分数
-        ai_score - opponent_score + positional_score
+    /// 统计 stone 一方的各类棋型数量和位置分，即评估函数的线性特征。
+    /// 棋型数量来自 patterns 模块，与 BoardEvaluator 使用同一套棋型定义。
+    pub fn board_features(game_state: &GameState, stone: Stone) -> [i32; FEATURE_COUNT] {
+        Self::features_from(game_state, &analyse_position(game_state), stone)
     }
 
-    /// 评估从 (r, c) 开始，沿 (dr, dc) 方向的一个潜在 5 子棋型窗口。
-    /// 查看从 (r, c) 到 (r + 4*dr, c + 4*dc) 的 5 个位置以及它们两端的位置。
-    fn evaluate_pattern(
-        &self,
+    /// 由已经统计好的棋型和 stone 一方的位置分组成特征
+    fn features_from(
         game_state: &GameState,
-        r: usize,
-        c: usize,
-        dr: isize,
-        dc: isize,
-        player_stone: Stone,
-    ) -> i32 {
-        let opponent_stone = player_stone.opponent();
-        let board_size = GRID_SIZE + 1;
-
-        let mut player_stones_in_pattern = 0;
-        let mut opponent_stones_in_pattern = 0;
-        // let mut empty_in_pattern = 0; // 当前评估中未使用
-
-        // 检查潜在棋型内的 5 个位置
-        for i in 0..5 {
-            let curr_r = r as isize + i * dr;
-            let curr_c = c as isize + i * dc;
-            // 在 evaluate_board 中已经检查了结束点在界限内，所以这里不需要再次检查 curr_r/curr_c 的界限
-
-            match game_state.board[curr_r as usize][curr_c as usize] {
-                Some(stone) if stone == player_stone => player_stones_in_pattern += 1,
-                Some(stone) if stone == opponent_stone => opponent_stones_in_pattern += 1,
-                None => { /*empty_in_pattern += 1;*/ }
-                _ => {} // 不应该出现
-            }
-        }
-
-        // 如果这个 5 子窗口内有对手的棋子，则这个棋型被阻挡，对当前玩家没有价值
-        if opponent_stones_in_pattern > 0 {
-            return 0;
-        }
-
-        // 如果窗口内没有当前玩家的棋子，也没有价值
-        if player_stones_in_pattern == 0 {
-            return 0;
-        }
-
-        // 现在检查两端是否开放 (没有被对手的棋子阻挡 或 没有出界)
-        let mut open_ends = 0;
-
-        // 检查后方一格 ((r, c) 前一格)
-        let br = r as isize - dr;
-        let bc = c as isize - dc;
-        // 如果后方位置在界限内且是空的
-        if br >= 0 && br < board_size as isize && bc >= 0 && bc < board_size as isize {
-            if game_state.board[br as usize][bc as usize].is_none() {
-                open_ends += 1;
-            }
-            // 如果是对手棋子，则此端被阻挡，open_ends 不增加
-        } else {
-            // 如果出界，理论上也是被边界“阻挡”了，不算开放端
-            // 我们只计算棋盘内的空位作为开放端
-        }
-
-        // 检查前方一格 (5子棋型结束点后一格)
-        let fr = r as isize + 5 * dr;
-        let fc = c as isize + 5 * dc;
-        // 如果前方位置在界限内且是空的
-        if fr >= 0 && fr < board_size as isize && fc >= 0 && fc < board_size as isize {
-            if game_state.board[fr as usize][fc as usize].is_none() {
-                open_ends += 1;
-            }
-            // 如果是对手棋子，则此端被阻挡
-        } else {
-            // 如果出界，不算开放端
+        report: &PatternReport,
+        stone: Stone,
+    ) -> [i32; FEATURE_COUNT] {
+        let mut features = [0; FEATURE_COUNT];
+        let counts = report.get(stone);
+        // 按棋型所在的窗口数计分：同样是活三，发展空间大的分数高
+        for pattern in Pattern::ALL {
+            features[pattern.feature() as usize] = counts.windows(pattern) as i32;
         }
 
-        // 根据窗口内连续棋子数和开放端数量给分
-        match player_stones_in_pattern {
-            5 => FIVE_SCORE, // 5 连 (理论上会被胜利检查捕获)
-            4 => {
-                match open_ends {
-                    2 => OPEN_FOUR_SCORE, // 活四
-                    1 => HALF_FOUR_SCORE, // 冲四/死四
-                    _ => 0,               // 死四（两端都被封锁）
-                }
-            }
-            3 => {
-                match open_ends {
-                    2 => OPEN_THREE_SCORE, // 活三
-                    1 => HALF_THREE_SCORE, // 眠三/死三
-                    _ => 0,                // 死三
-                }
-            }
-            2 => {
-                match open_ends {
-                    2 => OPEN_TWO_SCORE, // 活二
-                    1 => HALF_TWO_SCORE, // 眠二/死二
-                    _ => 0,              // 死二
+        // --- 位置分 ---
+        // 让 AI 稍微倾向于占据中心位置
+        let board_size = GRID_SIZE + 1;
+        let center = (GRID_SIZE as i32) / 2; // 使用 i32 进行计算
+        for r in 0..board_size {
+            for c in 0..board_size {
+                if game_state.board[r][c] == Some(stone) {
+                    // 计算曼哈顿距离到中心，距离中心越近，加分越多
+                    let dist_from_center = (r as i32 - center).abs() + (c as i32 - center).abs();
+                    features[Feature::Positional as usize] += (center - dist_from_center).max(0);
                 }
             }
-            _ => 0, // 1 个棋子在此棋型评估中无直接价值
         }
+
+        features
     }
 
     // --- Getters 和 Setters ---
@@ -544,9 +1367,49 @@ impl SmartAgent {
     /// 设置 AI 的搜索深度
     pub fn set_depth(&mut self, depth: u32) {
         self.search_depth = depth.max(1);
+        self.skill_level = None;
     }
 
-    pub fn get_difficulty(&self) -> u32 {
+    pub fn get_depth(&self) -> u32 {
         self.search_depth
     }
+
+    /// 按难度等级 (1-10) 设置搜索深度、时间、候选半径、评估扰动和失误概率
+    pub fn set_skill_level(&mut self, level: u32) {
+        let skill = SkillLevel::get(level);
+        self.search_depth = skill.depth.max(1);
+        self.time_limit = skill.time_limit_ms.map(Duration::from_millis);
+        self.move_radius = skill.move_radius;
+        self.eval_noise = skill.eval_noise;
+        self.blunder_chance = skill.blunder_chance;
+        self.skill_level = Some(skill.level);
+    }
+
+    /// 当前的难度等级，直接设置深度时为 None
+    pub fn skill_level(&self) -> Option<u32> {
+        self.skill_level
+    }
+
+    /// 设置评估函数使用的棋型分值
+    pub fn set_weights(&mut self, weights: EvalWeights) {
+        self.weights = weights;
+    }
+
+    pub fn weights(&self) -> &EvalWeights {
+        &self.weights
+    }
+
+    /// 设置开局库，None 表示不使用开局库
+    pub fn set_book(&mut self, book: Option<Arc<OpeningBook>>) {
+        self.book = book;
+    }
+
+    pub fn book(&self) -> Option<&OpeningBook> {
+        self.book.as_deref()
+    }
+
+    /// 设置每步思考时间上限。设置后搜索改为迭代加深，最深不超过搜索深度。
+    pub fn set_time_limit(&mut self, time_limit: Option<Duration>) {
+        self.time_limit = time_limit;
+    }
 }
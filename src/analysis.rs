@@ -0,0 +1,722 @@
+use crate::agent::SmartAgent;
+use crate::game::{
+    GameState, Stone, move_accuracy, move_to_notation, win_probability, win_rate_loss,
+};
+use crate::review::MoveClass;
+use crate::savegame::MoveLog;
+use crate::ui::{AnalyseButton, AnalyseButtonText};
+use crate::weights::EvalWeights;
+use bevy::prelude::*;
+use std::fmt::Write as _;
+use std::fs;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread::{self, JoinHandle};
+
+/// 分析报告导出的文件 (程序工作目录下)
+pub const ANALYSIS_TEXT_FILE: &str = "gobang-analysis.txt";
+pub const ANALYSIS_HTML_FILE: &str = "gobang-analysis.html";
+// 赛后分析每个局面的搜索深度，比对局中评价落子的 GRADING_DEPTH 深一层
+const ANALYSIS_DEPTH: u32 = 4;
+// 报告中列出的转折点数量
+const TURNING_POINTS: usize = 3;
+// 胜率损失达到这个值 (百分点，即 Mistake 及以上) 的落子列出更好的着法
+const MISTAKE_LOSS: f64 = 10.0;
+// 报告窗口中最多列出的失误数，完整列表见导出的文件
+const MISTAKES_SHOWN: usize = 8;
+// 报告窗口中评估曲线的尺寸
+const GRAPH_WIDTH: f32 = 640.0;
+const GRAPH_HEIGHT: f32 = 140.0;
+
+/// 一步棋的分析结果，分数都是落子一方的视角
+#[derive(Debug, Clone)]
+pub struct AnalysedMove {
+    pub number: usize, // 手数，从 1 开始
+    pub stone: Stone,
+    pub played: (usize, usize),
+    pub score: i32,
+    pub best_move: (usize, usize),
+    pub best_score: i32,
+    pub best_line: Vec<(usize, usize)>, // 最佳着法开始的主要变例
+}
+
+impl AnalysedMove {
+    /// 相对最佳着法损失的胜率 (百分点)
+    pub fn loss(&self) -> f64 {
+        win_rate_loss(self.best_score, self.score)
+    }
+
+    pub fn accuracy(&self) -> f64 {
+        move_accuracy(self.best_score, self.score)
+    }
+
+    pub fn class(&self) -> MoveClass {
+        MoveClass::from_loss(self.loss())
+    }
+
+    /// 这步之后黑方的胜率 (0-1)
+    pub fn black_win_rate(&self) -> f64 {
+        let rate = win_probability(self.score);
+        match self.stone {
+            Stone::Black => rate,
+            Stone::White => 1.0 - rate,
+        }
+    }
+
+    /// 如 "12. White h8"
+    fn title(&self) -> String {
+        format!(
+            "{}. {} {}",
+            self.number,
+            stone_name(self.stone),
+            move_to_notation(self.played.0, self.played.1)
+        )
+    }
+
+    /// 更好的着法及其后续，如 "i9 (i9 j10 k11)"
+    fn better_text(&self) -> String {
+        let line = self
+            .best_line
+            .iter()
+            .map(|&(row, col)| move_to_notation(row, col))
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!(
+            "{} ({})",
+            move_to_notation(self.best_move.0, self.best_move.1),
+            line
+        )
+    }
+}
+
+/// 整局的分析报告
+#[derive(Debug, Clone)]
+pub struct AnalysisReport {
+    pub player_stone: Stone, // 玩家执的棋子，另一方是 AI
+    pub depth: u32,
+    pub moves: Vec<AnalysedMove>,
+}
+
+impl AnalysisReport {
+    /// stone 一方所有落子的平均准确度，没有落子时为 None
+    pub fn accuracy(&self, stone: Stone) -> Option<f64> {
+        let accuracies: Vec<f64> = self
+            .moves
+            .iter()
+            .filter(|m| m.stone == stone)
+            .map(AnalysedMove::accuracy)
+            .collect();
+        if accuracies.is_empty() {
+            return None;
+        }
+        Some(accuracies.iter().sum::<f64>() / accuracies.len() as f64)
+    }
+
+    /// 胜率损失最大的几步 (至少是 Inaccuracy)，按手数排序
+    pub fn turning_points(&self) -> Vec<&AnalysedMove> {
+        let mut points: Vec<&AnalysedMove> = self
+            .moves
+            .iter()
+            .filter(|m| m.class() != MoveClass::Best && m.class() != MoveClass::Good)
+            .collect();
+        points.sort_by(|a, b| b.loss().total_cmp(&a.loss()));
+        points.truncate(TURNING_POINTS);
+        points.sort_by_key(|m| m.number);
+        points
+    }
+
+    /// 需要列出更好着法的失误 (Mistake 及以上)
+    pub fn mistakes(&self) -> impl Iterator<Item = &AnalysedMove> {
+        self.moves.iter().filter(|m| m.loss() >= MISTAKE_LOSS)
+    }
+
+    /// 如 "Black (You)"
+    fn side_name(&self, stone: Stone) -> String {
+        let role = if stone == self.player_stone {
+            "You"
+        } else {
+            "AI"
+        };
+        format!("{} ({})", stone_name(stone), role)
+    }
+
+    /// 双方准确度，每方一行
+    fn accuracy_lines(&self) -> Vec<String> {
+        [Stone::Black, Stone::White]
+            .into_iter()
+            .map(|stone| match self.accuracy(stone) {
+                Some(accuracy) => format!("{}: accuracy {:.0}%", self.side_name(stone), accuracy),
+                None => format!("{}: no moves", self.side_name(stone)),
+            })
+            .collect()
+    }
+
+    fn turning_point_lines(&self) -> Vec<String> {
+        self.turning_points()
+            .into_iter()
+            .map(|m| format!("{}  -{:.0}%  {}", m.title(), m.loss(), m.class().name()))
+            .collect()
+    }
+
+    fn mistake_lines(&self) -> Vec<String> {
+        self.mistakes()
+            .map(|m| {
+                format!(
+                    "{}  {}  better: {}",
+                    m.title(),
+                    m.class().name(),
+                    m.better_text()
+                )
+            })
+            .collect()
+    }
+
+    /// 纯文本形式的报告
+    pub fn to_text(&self) -> String {
+        let mut text = format!("Game analysis (search depth {})\n\n", self.depth);
+        for line in self.accuracy_lines() {
+            let _ = writeln!(text, "{}", line);
+        }
+        let _ = writeln!(text, "\nTurning points");
+        for line in self.turning_point_lines() {
+            let _ = writeln!(text, "  {}", line);
+        }
+        let _ = writeln!(text, "\nMistakes and better moves");
+        for line in self.mistake_lines() {
+            let _ = writeln!(text, "  {}", line);
+        }
+        let _ = writeln!(
+            text,
+            "\nMoves\n  {:>4}  {:<6}{:<6}{:>10}  {:<6}{:>10}  {:>9}  Class",
+            "No.", "Side", "Move", "Score", "Best", "Best score", "Black win"
+        );
+        for m in &self.moves {
+            let _ = writeln!(
+                text,
+                "  {:>4}  {:<6}{:<6}{:>10}  {:<6}{:>10}  {:>8.0}%  {}",
+                m.number,
+                stone_name(m.stone),
+                move_to_notation(m.played.0, m.played.1),
+                m.score,
+                move_to_notation(m.best_move.0, m.best_move.1),
+                m.best_score,
+                m.black_win_rate() * 100.0,
+                m.class().name()
+            );
+        }
+        text
+    }
+
+    /// HTML 形式的报告，评估曲线画成 SVG
+    pub fn to_html(&self) -> String {
+        let mut html = String::from(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Game analysis</title>\n\
+             <style>body{font-family:sans-serif;margin:2em}table{border-collapse:collapse}\
+             td,th{padding:2px 10px;text-align:right}th{border-bottom:1px solid #888}</style>\n\
+             </head>\n<body>\n",
+        );
+        let _ = writeln!(
+            html,
+            "<h1>Game analysis</h1>\n<p>Search depth {}</p>",
+            self.depth
+        );
+        for line in self.accuracy_lines() {
+            let _ = writeln!(html, "<p>{}</p>", line);
+        }
+
+        // 评估曲线：横轴为手数，纵轴为黑方胜率，中线为 50%
+        let (width, height) = (GRAPH_WIDTH as f64, GRAPH_HEIGHT as f64);
+        let step = width / self.moves.len().max(1) as f64;
+        let points = self
+            .moves
+            .iter()
+            .enumerate()
+            .map(|(i, m)| {
+                format!(
+                    "{:.1},{:.1}",
+                    (i as f64 + 0.5) * step,
+                    (1.0 - m.black_win_rate()) * height
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        let _ = writeln!(
+            html,
+            "<h2>Black win chance</h2>\n<svg width=\"{w}\" height=\"{h}\" style=\"background:#ddd\">\n\
+             <line x1=\"0\" y1=\"{m}\" x2=\"{w}\" y2=\"{m}\" stroke=\"#888\"/>\n\
+             <polyline points=\"{p}\" fill=\"none\" stroke=\"#000\" stroke-width=\"2\"/>\n</svg>",
+            w = width,
+            h = height,
+            m = height / 2.0,
+            p = points
+        );
+
+        let _ = writeln!(html, "<h2>Turning points</h2>\n<ul>");
+        for line in self.turning_point_lines() {
+            let _ = writeln!(html, "<li>{}</li>", line);
+        }
+        let _ = writeln!(html, "</ul>\n<h2>Mistakes and better moves</h2>\n<ul>");
+        for line in self.mistake_lines() {
+            let _ = writeln!(html, "<li>{}</li>", line);
+        }
+        let _ = writeln!(
+            html,
+            "</ul>\n<h2>Moves</h2>\n<table>\n<tr><th>No.</th><th>Side</th><th>Move</th><th>Score</th>\
+             <th>Best</th><th>Best score</th><th>Black win</th><th>Class</th></tr>"
+        );
+        for m in &self.moves {
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td>\
+                 <td>{:.0}%</td><td>{}</td></tr>",
+                m.number,
+                stone_name(m.stone),
+                move_to_notation(m.played.0, m.played.1),
+                m.score,
+                move_to_notation(m.best_move.0, m.best_move.1),
+                m.best_score,
+                m.black_win_rate() * 100.0,
+                m.class().name()
+            );
+        }
+        html.push_str("</table>\n</body>\n</html>\n");
+        html
+    }
+
+    /// 把报告写入 ANALYSIS_TEXT_FILE 和 ANALYSIS_HTML_FILE
+    pub fn export(&self) -> Result<(), String> {
+        fs::write(ANALYSIS_TEXT_FILE, self.to_text())
+            .map_err(|e| format!("cannot write {}: {}", ANALYSIS_TEXT_FILE, e))?;
+        fs::write(ANALYSIS_HTML_FILE, self.to_html())
+            .map_err(|e| format!("cannot write {}: {}", ANALYSIS_HTML_FILE, e))
+    }
+}
+
+fn stone_name(stone: Stone) -> &'static str {
+    match stone {
+        Stone::Black => "Black",
+        Stone::White => "White",
+    }
+}
+
+/// 从空棋盘起按顺序重放 moves，逐个局面搜索最佳着法并评价实际的落子。
+/// 每分析完一步 progress 加一；stop 置位时提前结束，返回已分析的部分
+pub fn analyse_game(
+    weights: &EvalWeights,
+    moves: &[(usize, usize, Stone)],
+    player_stone: Stone,
+    progress: &AtomicUsize,
+    stop: &AtomicBool,
+) -> AnalysisReport {
+    let mut state = GameState::new();
+    let mut analysed = Vec::new();
+    for (index, &(row, col, stone)) in moves.iter().enumerate() {
+        if stop.load(Ordering::Relaxed) {
+            break;
+        }
+        let mut agent = SmartAgent::new(stone, ANALYSIS_DEPTH);
+        agent.set_weights(weights.clone());
+        state.current_turn = stone;
+        let Some((score, search)) = agent.search_with_move(&state, (row, col)) else {
+            break;
+        };
+        let (best_move, best_score, best_line) = match search {
+            Some(info) if info.best_move == (row, col) => ((row, col), score, info.pv),
+            Some(info) if info.score > score => (info.best_move, info.score, info.pv),
+            _ => ((row, col), score, vec![(row, col)]),
+        };
+        analysed.push(AnalysedMove {
+            number: index + 1,
+            stone,
+            played: (row, col),
+            score,
+            best_move,
+            best_score,
+            best_line,
+        });
+        state.board[row][col] = Some(stone);
+        progress.fetch_add(1, Ordering::Relaxed);
+    }
+    AnalysisReport {
+        player_stone,
+        depth: ANALYSIS_DEPTH,
+        moves: analysed,
+    }
+}
+
+/// 后台线程中进行的分析。丢弃时通知后台线程在当前局面分析完后停止
+pub struct AnalysisJob {
+    total: usize,
+    progress: Arc<AtomicUsize>,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<AnalysisReport>>,
+}
+
+impl AnalysisJob {
+    /// 在后台线程中分析本局的全部落子 (按落子记录，包括读档恢复的落子)，使用 AI 的评估权重
+    pub fn start(ai: &SmartAgent, log: &MoveLog) -> Self {
+        let moves: Vec<(usize, usize, Stone)> =
+            log.moves.iter().map(|m| (m.row, m.col, m.stone)).collect();
+        let weights = ai.weights().clone();
+        let player_stone = ai.get_stone().opponent();
+        let progress = Arc::new(AtomicUsize::new(0));
+        let stop = Arc::new(AtomicBool::new(false));
+        let total = moves.len();
+        let (thread_progress, thread_stop) = (progress.clone(), stop.clone());
+        let handle = thread::spawn(move || {
+            analyse_game(
+                &weights,
+                &moves,
+                player_stone,
+                &thread_progress,
+                &thread_stop,
+            )
+        });
+        AnalysisJob {
+            total,
+            progress,
+            stop,
+            handle: Some(handle),
+        }
+    }
+
+    /// (已分析的步数, 总步数)
+    pub fn progress(&self) -> (usize, usize) {
+        (self.progress.load(Ordering::Relaxed), self.total)
+    }
+
+    /// 分析完成时取出报告，还在进行中时返回 None
+    pub fn try_finish(&mut self) -> Option<AnalysisReport> {
+        if !self.handle.as_ref()?.is_finished() {
+            return None;
+        }
+        self.handle.take()?.join().ok()
+    }
+}
+
+impl Drop for AnalysisJob {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// 本局的赛后分析：进行中的后台分析或已完成的报告
+#[derive(Resource, Default)]
+pub struct Analysis {
+    pub job: Option<AnalysisJob>,
+    pub report: Option<AnalysisReport>,
+}
+
+/// 分析报告窗口
+#[derive(Component)]
+pub struct AnalysisWindow;
+
+/// 报告窗口中的按钮
+#[derive(Component, Clone, Copy, PartialEq)]
+pub enum AnalysisWindowButton {
+    Export,
+    Close,
+}
+
+/// 报告窗口中显示导出结果的文字
+#[derive(Component)]
+pub struct AnalysisStatusText;
+
+type AnalyseButtonChanged = (Changed<Interaction>, With<AnalyseButton>);
+
+/// 系统：点击胜利窗口中的 "Analyse" 按钮开始后台分析，已有报告时重新打开报告窗口
+pub fn handle_analyse_button(
+    mut commands: Commands,
+    mut button_query: Query<(&Interaction, &mut BackgroundColor), AnalyseButtonChanged>,
+    ai: Res<SmartAgent>,
+    game_state: Res<GameState>,
+    log: Res<MoveLog>,
+    mut analysis: ResMut<Analysis>,
+    window_query: Query<Entity, With<AnalysisWindow>>,
+) {
+    for (interaction, mut bg_color) in &mut button_query {
+        match *interaction {
+            Interaction::Pressed => {
+                *bg_color = Color::rgb(0.1, 0.1, 0.1).into();
+                if let Some(report) = &analysis.report {
+                    if window_query.is_empty() {
+                        spawn_report_window(&mut commands, report);
+                    }
+                } else if analysis.job.is_none()
+                    && !log.moves.is_empty()
+                    && !log.is_stale(&game_state)
+                {
+                    analysis.job = Some(AnalysisJob::start(&ai, &log));
+                }
+            }
+            Interaction::Hovered => {
+                *bg_color = Color::rgb(0.25, 0.25, 0.25).into();
+            }
+            Interaction::None => {
+                *bg_color = Color::rgb(0.15, 0.15, 0.15).into();
+            }
+        }
+    }
+}
+
+/// 系统：更新分析进度，完成后显示报告窗口；重新开局时丢弃分析和报告
+pub fn update_analysis(
+    mut commands: Commands,
+    game_state: Res<GameState>,
+    mut analysis: ResMut<Analysis>,
+    mut text_query: Query<&mut Text, With<AnalyseButtonText>>,
+    window_query: Query<Entity, With<AnalysisWindow>>,
+) {
+    if !game_state.is_game_over {
+        if analysis.job.is_some() || analysis.report.is_some() {
+            analysis.job = None;
+            analysis.report = None;
+        }
+        for entity in window_query.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+
+    let label = if let Some(job) = analysis.job.as_mut() {
+        match job.try_finish() {
+            Some(report) => {
+                spawn_report_window(&mut commands, &report);
+                analysis.job = None;
+                analysis.report = Some(report);
+                "Show Analysis".to_string()
+            }
+            None => {
+                let (done, total) = job.progress();
+                format!("Analysing {}/{}", done, total)
+            }
+        }
+    } else if analysis.report.is_some() {
+        "Show Analysis".to_string()
+    } else {
+        "Analyse".to_string()
+    };
+    for mut text in text_query.iter_mut() {
+        if text.sections[0].value != label {
+            text.sections[0].value = label.clone();
+        }
+    }
+}
+
+/// 系统：报告窗口中的导出和关闭按钮
+pub fn handle_analysis_window_buttons(
+    mut commands: Commands,
+    button_query: Query<(&Interaction, &AnalysisWindowButton), Changed<Interaction>>,
+    analysis: Res<Analysis>,
+    window_query: Query<Entity, With<AnalysisWindow>>,
+    mut status_query: Query<&mut Text, With<AnalysisStatusText>>,
+) {
+    for (interaction, button) in button_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            AnalysisWindowButton::Export => {
+                let Some(report) = &analysis.report else {
+                    continue;
+                };
+                let status = match report.export() {
+                    Ok(()) => format!("Saved {} and {}", ANALYSIS_TEXT_FILE, ANALYSIS_HTML_FILE),
+                    Err(e) => format!("Export failed: {}", e),
+                };
+                println!("{}", status);
+                for mut text in status_query.iter_mut() {
+                    text.sections[0].value = status.clone();
+                }
+            }
+            AnalysisWindowButton::Close => {
+                for entity in window_query.iter() {
+                    commands.entity(entity).despawn_recursive();
+                }
+            }
+        }
+    }
+}
+
+/// 生成报告窗口：双方准确度、评估曲线、转折点和失误的更好着法，底部是导出和关闭按钮
+fn spawn_report_window(commands: &mut Commands, report: &AnalysisReport) {
+    let text_style = |font_size: f32| TextStyle {
+        font_size,
+        color: Color::rgb(0.2, 0.2, 0.2),
+        ..default()
+    };
+    let section = |title: &str, lines: Vec<String>| {
+        let body = if lines.is_empty() {
+            "  (none)".to_string()
+        } else {
+            lines
+                .iter()
+                .map(|line| format!("  {}", line))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        format!("{}\n{}", title, body)
+    };
+    let mut mistakes = report.mistake_lines();
+    if mistakes.len() > MISTAKES_SHOWN {
+        let hidden = mistakes.len() - MISTAKES_SHOWN;
+        mistakes.truncate(MISTAKES_SHOWN);
+        mistakes.push(format!("... {} more in the exported report", hidden));
+    }
+    let turning: Vec<usize> = report.turning_points().iter().map(|m| m.number).collect();
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(20.0),
+                    top: Val::Px(20.0),
+                    width: Val::Px(GRAPH_WIDTH + 40.0),
+                    height: Val::Px(760.0),
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(20.0)),
+                    ..default()
+                },
+                background_color: Color::rgb(0.95, 0.95, 0.95).into(),
+                z_index: ZIndex::Global(20),
+                ..default()
+            },
+            AnalysisWindow,
+        ))
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_section("Game Analysis", text_style(28.0)).with_style(Style {
+                    margin: UiRect::bottom(Val::Px(10.0)),
+                    ..default()
+                }),
+            );
+            parent.spawn(
+                TextBundle::from_section(report.accuracy_lines().join("\n"), text_style(18.0))
+                    .with_style(Style {
+                        margin: UiRect::bottom(Val::Px(10.0)),
+                        ..default()
+                    }),
+            );
+
+            // 评估曲线：每步一根柱，黑方占优时在中线以上画黑柱，白方占优时在中线以下画白柱，
+            // 转折点画成红色
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(GRAPH_WIDTH),
+                        height: Val::Px(GRAPH_HEIGHT),
+                        margin: UiRect::bottom(Val::Px(10.0)),
+                        ..default()
+                    },
+                    background_color: Color::rgb(0.75, 0.75, 0.75).into(),
+                    ..default()
+                })
+                .with_children(|graph| {
+                    let middle = GRAPH_HEIGHT / 2.0;
+                    let step = GRAPH_WIDTH / report.moves.len().max(1) as f32;
+                    for (i, m) in report.moves.iter().enumerate() {
+                        let advantage = (m.black_win_rate() as f32 - 0.5) * GRAPH_HEIGHT;
+                        let color = if turning.contains(&m.number) {
+                            Color::rgb(0.8, 0.1, 0.1)
+                        } else if advantage >= 0.0 {
+                            Color::BLACK
+                        } else {
+                            Color::WHITE
+                        };
+                        graph.spawn(NodeBundle {
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                left: Val::Px(i as f32 * step),
+                                top: Val::Px(if advantage >= 0.0 {
+                                    middle - advantage
+                                } else {
+                                    middle
+                                }),
+                                width: Val::Px((step - 1.0).max(1.0)),
+                                height: Val::Px(advantage.abs().max(1.0)),
+                                ..default()
+                            },
+                            background_color: color.into(),
+                            ..default()
+                        });
+                    }
+                    graph.spawn(NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            left: Val::Px(0.0),
+                            top: Val::Px(middle),
+                            width: Val::Px(GRAPH_WIDTH),
+                            height: Val::Px(1.0),
+                            ..default()
+                        },
+                        background_color: Color::rgb(0.4, 0.4, 0.4).into(),
+                        ..default()
+                    });
+                });
+
+            parent.spawn(
+                TextBundle::from_section(
+                    format!(
+                        "{}\n\n{}",
+                        section("Turning points", report.turning_point_lines()),
+                        section("Mistakes and better moves", mistakes)
+                    ),
+                    text_style(16.0),
+                )
+                .with_style(Style {
+                    flex_grow: 1.0,
+                    ..default()
+                }),
+            );
+
+            parent.spawn((
+                TextBundle::from_section("", text_style(14.0)).with_style(Style {
+                    margin: UiRect::bottom(Val::Px(10.0)),
+                    ..default()
+                }),
+                AnalysisStatusText,
+            ));
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        column_gap: Val::Px(20.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|row| {
+                    for (button, label) in [
+                        (AnalysisWindowButton::Export, "Export"),
+                        (AnalysisWindowButton::Close, "Close"),
+                    ] {
+                        row.spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    width: Val::Px(120.0),
+                                    height: Val::Px(40.0),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                background_color: Color::rgb(0.15, 0.15, 0.15).into(),
+                                ..default()
+                            },
+                            button,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                label,
+                                TextStyle {
+                                    font_size: 20.0,
+                                    color: Color::WHITE,
+                                    ..default()
+                                },
+                            ));
+                        });
+                    }
+                });
+        });
+}
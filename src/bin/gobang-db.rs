@@ -0,0 +1,131 @@
+use gobang::database::{DB_FILE, GameDatabase, GameFilter};
+use gobang::game::{GameState, Stone, move_to_notation, parse_notation};
+use gobang::rif::parse_rif;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+const USAGE: &str = "\
+Usage: gobang-db import <rif.xml> [--db <file>]
+       gobang-db search [moves...] [--db <file>]
+
+Maintains the local game database browsed from the main menu (Master Games).
+
+import  adds the games of a RIF / renju.net XML database export. Games that
+        are already in the database (same RIF game id) are skipped.
+search  lists how often each next move was played after the given moves
+        (e.g. `h8 h9 i9`), counting symmetric positions as the same.
+
+Options:
+  --db <file>    the game database (default: gobang-db.toml)";
+
+enum Command {
+    Import(PathBuf),
+    Search(Vec<String>),
+}
+
+fn parse_args() -> Result<(Command, PathBuf), String> {
+    let mut args = env::args().skip(1);
+    let command = args.next().ok_or("missing command")?;
+    let mut db = PathBuf::from(DB_FILE);
+    let mut rest = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--db" => db = args.next().ok_or("--db needs a value")?.into(),
+            "-h" | "--help" => return Err(String::new()),
+            _ if arg.starts_with('-') => return Err(format!("unexpected argument {}", arg)),
+            _ => rest.push(arg),
+        }
+    }
+
+    let command = match command.as_str() {
+        "import" => match rest.as_slice() {
+            [file] => Command::Import(PathBuf::from(file)),
+            [] => return Err("missing RIF file".to_string()),
+            _ => return Err("import takes one RIF file".to_string()),
+        },
+        "search" => Command::Search(rest),
+        "-h" | "--help" => return Err(String::new()),
+        _ => return Err(format!("unknown command {}", command)),
+    };
+    Ok((command, db))
+}
+
+fn main() {
+    let (command, db_path) = parse_args().unwrap_or_else(|message| {
+        if !message.is_empty() {
+            eprintln!("error: {}\n", message);
+        }
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    });
+    let mut database = GameDatabase::load(&db_path).unwrap_or_else(|e| {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    });
+
+    match command {
+        Command::Import(file) => {
+            let text = fs::read_to_string(&file).unwrap_or_else(|e| {
+                eprintln!("error: {}: {}", file.display(), e);
+                process::exit(1);
+            });
+            let import = parse_rif(&text).unwrap_or_else(|e| {
+                eprintln!("error: {}: {}", file.display(), e);
+                process::exit(1);
+            });
+            let read = import.games.len();
+            let added = database.merge(import.games);
+            if let Err(e) = database.save(&db_path) {
+                eprintln!("error: {}", e);
+                process::exit(1);
+            }
+            println!(
+                "{} games read ({} skipped), {} added, {} already present; {} games in {}",
+                read,
+                import.skipped,
+                added,
+                read - added,
+                database.games.len(),
+                db_path.display()
+            );
+        }
+        Command::Search(moves) => {
+            let mut game_state = GameState::new();
+            let mut stone = Stone::Black;
+            for text in &moves {
+                let applied = parse_notation(text)
+                    .ok_or("not a move")
+                    .and_then(|(row, col)| game_state.apply_move(row, col, stone));
+                if applied.is_err() {
+                    eprintln!("error: invalid move {}", text);
+                    process::exit(2);
+                }
+                stone = stone.opponent();
+            }
+
+            let stats = database.search_position(&game_state, &GameFilter::default());
+            println!(
+                "{} of {} games reach this position",
+                stats.games,
+                database.games.len()
+            );
+            for m in &stats.moves {
+                let score = m
+                    .score(stone)
+                    .map_or("-".to_string(), |score| format!("{:.1}%", score * 100.0));
+                println!(
+                    "{:>4} {:6} games  {:4} black wins  {:4} draws  {:4} white wins  {} for {:?}",
+                    move_to_notation(m.row, m.col),
+                    m.games,
+                    m.black_wins,
+                    m.draws,
+                    m.white_wins,
+                    score,
+                    stone
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use gobang::elo::{Sprt, SprtVerdict};
+use gobang::match_runner::{self, EngineConfig, MatchConfig};
+use gobang::position::Position;
+use gobang::tuner;
+use std::env;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::process;
+use std::thread;
+
+const USAGE: &str = "\
+Usage: gobang-match [options]
+
+Plays the SmartAgent against itself with two configurations (A and B) and
+reports the result from A's point of view. Every opening is played twice
+with colours swapped.
+
+Options:
+  --a <spec>           engine A, e.g. name=new,depth=4,time=200,weights=tuned.toml
+  --b <spec>           engine B (same keys; default: depth=3 with built-in weights)
+                       skill=<1-10> uses a difficulty level instead of depth/time
+  --games <n>          number of games (default: two per opening)
+                       deterministic engines (no time limit, no eval noise)
+                       play at most two games per opening
+  --openings <file>    one opening per line, e.g. `h8 h9 i10` (default: built-in set)
+  --position <text>    start every game from this position instead of the openings,
+                       written as `15 <rows> <b|w> freestyle` (Ctrl+C in the game)
+  --concurrency <n>    games played in parallel (default: number of CPUs)
+  --sprt <spec>        stop early once decided, e.g. elo0=0,elo1=10,alpha=0.05,beta=0.05
+  --out <file>         write the games in the gobang-tune format";
+
+struct Options {
+    engine_a: EngineConfig,
+    engine_b: EngineConfig,
+    games: Option<usize>,
+    openings: Option<PathBuf>,
+    position: Option<Position>,
+    concurrency: usize,
+    sprt: Option<Sprt>,
+    out: Option<PathBuf>,
+}
+
+fn parse_sprt(spec: &str) -> Result<Sprt, String> {
+    let mut sprt = Sprt::default();
+    for item in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let (key, value) = item
+            .split_once('=')
+            .ok_or_else(|| format!("expected key=value, got {}", item))?;
+        let value: f64 = value
+            .trim()
+            .parse()
+            .map_err(|_| format!("bad value for {}", key))?;
+        match key.trim() {
+            "elo0" => sprt.elo0 = value,
+            "elo1" => sprt.elo1 = value,
+            "alpha" => sprt.alpha = value,
+            "beta" => sprt.beta = value,
+            other => return Err(format!("unknown SPRT option {}", other)),
+        }
+    }
+    Ok(sprt)
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut args = env::args().skip(1);
+    let mut options = Options {
+        engine_a: EngineConfig::from_spec("", "A")?,
+        engine_b: EngineConfig::from_spec("", "B")?,
+        games: None,
+        openings: None,
+        position: None,
+        concurrency: thread::available_parallelism().map_or(1, |n| n.get()),
+        sprt: None,
+        out: None,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{} needs a value", name));
+        match arg.as_str() {
+            "--a" => options.engine_a = EngineConfig::from_spec(&value("--a")?, "A")?,
+            "--b" => options.engine_b = EngineConfig::from_spec(&value("--b")?, "B")?,
+            "--games" => {
+                options.games = Some(
+                    value("--games")?
+                        .parse()
+                        .map_err(|_| "--games needs a number")?,
+                )
+            }
+            "--openings" => options.openings = Some(value("--openings")?.into()),
+            "--position" => {
+                options.position = Some(
+                    value("--position")?
+                        .parse()
+                        .map_err(|e| format!("--position: {}", e))?,
+                )
+            }
+            "--concurrency" => {
+                options.concurrency = value("--concurrency")?
+                    .parse()
+                    .map_err(|_| "--concurrency needs a number")?
+            }
+            "--sprt" => options.sprt = Some(parse_sprt(&value("--sprt")?)?),
+            "--out" => options.out = Some(value("--out")?.into()),
+            "-h" | "--help" => return Err(String::new()),
+            _ => return Err(format!("unexpected argument {}", arg)),
+        }
+    }
+    if options.position.is_some() && options.openings.is_some() {
+        return Err("--position and --openings cannot be used together".to_string());
+    }
+    if let Some(position) = &options.position
+        && position.to_game_state().is_game_over
+    {
+        return Err("--position: the game is already over".to_string());
+    }
+    Ok(options)
+}
+
+fn main() {
+    let options = parse_args().unwrap_or_else(|message| {
+        if !message.is_empty() {
+            eprintln!("error: {}\n", message);
+        }
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    });
+
+    let openings = match (&options.position, &options.openings) {
+        // 局面换成一个开局：黑白交替摆出局面中的棋子
+        (Some(position), _) => vec![
+            position
+                .saved_game()
+                .moves
+                .iter()
+                .map(|m| (m.row, m.col))
+                .collect(),
+        ],
+        (None, Some(path)) => fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| match_runner::parse_openings(&text))
+            .unwrap_or_else(|e| {
+                eprintln!("error: {}: {}", path.display(), e);
+                process::exit(1);
+            }),
+        (None, None) => match_runner::default_openings(),
+    };
+    if openings.is_empty() {
+        eprintln!("error: no openings");
+        process::exit(1);
+    }
+
+    let mut out = options.out.as_ref().map(|path| {
+        let file = File::create(path).unwrap_or_else(|e| {
+            eprintln!("error: {}: {}", path.display(), e);
+            process::exit(1);
+        });
+        BufWriter::new(file)
+    });
+
+    let config = MatchConfig {
+        games: options.games.unwrap_or(openings.len() * 2),
+        openings,
+        engine_a: options.engine_a,
+        engine_b: options.engine_b,
+        concurrency: options.concurrency,
+        sprt: options.sprt,
+    };
+    if let Some(distinct) = config.distinct_games()
+        && config.games > distinct
+    {
+        eprintln!(
+            "error: --games {} would repeat games: without a time limit or a noisy skill level \
+             both engines play the same game again, so {} openings give at most {} distinct games",
+            config.games,
+            config.openings.len(),
+            distinct
+        );
+        process::exit(2);
+    }
+    println!(
+        "{} vs {}: {} games, {} threads",
+        config.engine_a.name, config.engine_b.name, config.games, config.concurrency
+    );
+
+    let stats = match_runner::run_match(&config, |result, stats| {
+        let (black, white) = if result.a_is_black {
+            (&config.engine_a.name, &config.engine_b.name)
+        } else {
+            (&config.engine_b.name, &config.engine_a.name)
+        };
+        println!(
+            "game {:4}: {} (black) vs {} (white) {} in {} moves  [+{} ={} -{}]",
+            result.index + 1,
+            black,
+            white,
+            tuner::result_notation(result.record.black_score),
+            result.record.moves.len(),
+            stats.wins,
+            stats.draws,
+            stats.losses
+        );
+        if let Some(out) = out.as_mut()
+            && let Err(e) = writeln!(out, "{}", tuner::format_game(&result.record))
+        {
+            eprintln!("error: writing games: {}", e);
+        }
+    });
+    if let Some(mut out) = out {
+        let _ = out.flush();
+    }
+
+    let (elo, margin) = stats.elo();
+    println!();
+    println!(
+        "{} vs {}: {} games, +{} ={} -{}, score {:.1}%",
+        config.engine_a.name,
+        config.engine_b.name,
+        stats.games(),
+        stats.wins,
+        stats.draws,
+        stats.losses,
+        stats.score() * 100.0
+    );
+    println!("Elo difference: {:+.1} +/- {:.1} (95%)", elo, margin);
+
+    if let Some(sprt) = &config.sprt {
+        let (lower, upper) = sprt.bounds();
+        let verdict = match sprt.verdict(&stats) {
+            SprtVerdict::AcceptH1 => "H1 accepted",
+            SprtVerdict::AcceptH0 => "H0 accepted",
+            SprtVerdict::Continue => "inconclusive",
+        };
+        println!(
+            "SPRT [{}, {}]: LLR {:.2} ({:.2}, {:.2}) {}",
+            sprt.elo0,
+            sprt.elo1,
+            sprt.llr(&stats),
+            lower,
+            upper,
+            verdict
+        );
+    }
+}
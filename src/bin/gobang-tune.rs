@@ -0,0 +1,153 @@
+use gobang::game::SCORE_SCALE;
+use gobang::tuner::{self, GameRecord, parse_game_list};
+use gobang::weights::{EvalWeights, WEIGHTS_FILE};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+const USAGE: &str = "\
+Usage: gobang-tune <games.txt> [options]
+
+Fits the SmartAgent pattern weights to recorded games (Texel-style tuning).
+Each line of <games.txt> is one game: a result (1-0, 0-1 or 1/2-1/2, from
+Black's point of view) followed by the moves, e.g. `1-0 h8 i9 h9 h10`.
+
+With --calibrate the weights are left alone; instead every position is
+searched and the scale that maps search scores to a win probability
+(SCORE_SCALE in src/game.rs) is fitted to the game results.
+
+Options:
+  --init <file>    starting weights (default: weights.toml, or built-in values)
+  --out <file>     where to write the tuned weights (default: weights.toml)
+  --passes <n>     maximum number of tuning passes (default: 100)
+  --skip <n>       ignore the first n moves of every game (default: 4)
+  --calibrate      fit the score-to-win-probability scale instead
+  --depth <n>      search depth used by --calibrate (default: 3)";
+
+struct Options {
+    games: PathBuf,
+    init: PathBuf,
+    out: PathBuf,
+    passes: usize,
+    skip: usize,
+    calibrate: bool,
+    depth: u32,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut args = env::args().skip(1);
+    let mut games = None;
+    let mut options = Options {
+        games: PathBuf::new(),
+        init: PathBuf::from(WEIGHTS_FILE),
+        out: PathBuf::from(WEIGHTS_FILE),
+        passes: 100,
+        skip: 4,
+        calibrate: false,
+        depth: 3,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{} needs a value", name));
+        match arg.as_str() {
+            "--init" => options.init = value("--init")?.into(),
+            "--out" => options.out = value("--out")?.into(),
+            "--passes" => {
+                options.passes = value("--passes")?
+                    .parse()
+                    .map_err(|_| "--passes needs a number")?
+            }
+            "--skip" => {
+                options.skip = value("--skip")?
+                    .parse()
+                    .map_err(|_| "--skip needs a number")?
+            }
+            "--calibrate" => options.calibrate = true,
+            "--depth" => {
+                options.depth = value("--depth")?
+                    .parse()
+                    .map_err(|_| "--depth needs a number")?
+            }
+            "-h" | "--help" => return Err(String::new()),
+            _ if games.is_none() && !arg.starts_with('-') => games = Some(PathBuf::from(arg)),
+            _ => return Err(format!("unexpected argument {}", arg)),
+        }
+    }
+
+    options.games = games.ok_or("missing games file")?;
+    Ok(options)
+}
+
+fn main() {
+    let options = parse_args().unwrap_or_else(|message| {
+        if !message.is_empty() {
+            eprintln!("error: {}\n", message);
+        }
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    });
+
+    let text = fs::read_to_string(&options.games).unwrap_or_else(|e| {
+        eprintln!("error: {}: {}", options.games.display(), e);
+        process::exit(1);
+    });
+    let games = parse_game_list(&text).unwrap_or_else(|e| {
+        eprintln!("error: {}: {}", options.games.display(), e);
+        process::exit(1);
+    });
+    let initial = EvalWeights::load_or_default(&options.init);
+    if options.calibrate {
+        calibrate(&options, &games, &initial);
+        return;
+    }
+
+    let samples = tuner::extract_samples(&games, options.skip);
+    if samples.is_empty() {
+        eprintln!("error: no positions to tune on");
+        process::exit(1);
+    }
+    println!("{} games, {} positions", games.len(), samples.len());
+
+    let tuned = tuner::tune(&samples, &initial, options.passes, |pass, error| {
+        println!("pass {:3}: error {:.6}", pass, error);
+    });
+
+    println!("\n{}", tuned.to_toml());
+    if let Err(e) = tuned.save(&options.out) {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+    println!("tuned weights written to {}", options.out.display());
+}
+
+/// 拟合搜索分数换算为胜率的尺度并打印，不修改权重
+fn calibrate(options: &Options, games: &[GameRecord], weights: &EvalWeights) {
+    let samples =
+        tuner::extract_score_samples(games, options.skip, weights, options.depth, |done| {
+            eprint!("\rsearching positions: {}/{} games", done, games.len());
+        });
+    eprintln!();
+    if samples.is_empty() {
+        eprintln!("error: no positions to calibrate on");
+        process::exit(1);
+    }
+
+    let scale = tuner::fit_score_scale(&samples);
+    println!(
+        "{} games, {} positions searched at depth {}",
+        games.len(),
+        samples.len(),
+        options.depth
+    );
+    println!(
+        "current scale {:.0}: error {:.6}",
+        SCORE_SCALE,
+        tuner::score_error(&samples, SCORE_SCALE)
+    );
+    println!(
+        "fitted scale  {:.0}: error {:.6}",
+        scale,
+        tuner::score_error(&samples, scale)
+    );
+}
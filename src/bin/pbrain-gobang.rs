@@ -0,0 +1,282 @@
+use gobang::agent::SmartAgent;
+use gobang::game::{GameState, Stone, move_to_notation};
+use gobang::piskvork::{self, BOARD_SIZE, BoardField, Command};
+use gobang::position::Position;
+use gobang::weights::{EvalWeights, WEIGHTS_FILE};
+use std::env;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use std::process;
+use std::time::Duration;
+
+const USAGE: &str = "\
+Usage: pbrain-gobang
+       pbrain-gobang --position <position> [--time <ms>]
+
+Without arguments, plays as a Piskvork protocol engine on standard input and
+output. With --position, searches the given position once, prints the search
+progress and the best move, and exits. The position is written as
+`15 <rows> <b|w> freestyle`, as copied with Ctrl+C in the game.
+
+Options:
+  --position <text>    the position to analyse
+  --time <ms>          thinking time for --position (default: 5000)";
+
+// 有时间限制时迭代加深的最大深度
+const MAX_DEPTH: u32 = 8;
+// 管理器没有发送时间信息时，每步使用的时间 (毫秒)
+const DEFAULT_TURN_TIME_MS: u64 = 5_000;
+// timeout_turn 为 0 表示尽快落子
+const FAST_TURN_TIME_MS: u64 = 100;
+// 用剩余对局时间估算每步时间时，假设还要再走的步数
+const MOVES_TO_GO: u64 = 20;
+// 留给通信和收尾的余量 (百分比)
+const TIME_USAGE_PERCENT: u64 = 80;
+
+/// Piskvork 协议引擎：从标准输入读取命令，在标准输出回复落子
+struct Brain {
+    game_state: GameState,
+    weights: EvalWeights,
+    timeout_turn: Option<u64>, // 每步时间上限 (毫秒)
+    time_left: Option<u64>,    // 整局剩余时间 (毫秒)
+}
+
+impl Brain {
+    fn new() -> Self {
+        Brain {
+            game_state: GameState::new(),
+            weights: EvalWeights::load_or_default(&weights_path()),
+            timeout_turn: None,
+            time_left: None,
+        }
+    }
+
+    /// 处理一条命令，返回 false 表示应退出
+    fn handle(&mut self, command: Command, input: &mut impl Iterator<Item = String>) -> bool {
+        match command {
+            Command::Start(size) => {
+                if size == BOARD_SIZE {
+                    self.game_state = GameState::new();
+                    respond("OK");
+                } else {
+                    respond(&format!("ERROR unsupported board size {}", size));
+                }
+            }
+            Command::RectStart(w, h) => {
+                respond(&format!("ERROR unsupported board size {},{}", w, h));
+            }
+            Command::Restart => {
+                self.game_state = GameState::new();
+                respond("OK");
+            }
+            Command::Begin => self.think(),
+            Command::Turn(row, col) => {
+                let stone = self.game_state.current_turn;
+                if self.game_state.apply_move(row, col, stone).is_err() {
+                    respond(&format!(
+                        "ERROR invalid move {}",
+                        piskvork::format_coords(row, col)
+                    ));
+                    return true;
+                }
+                self.game_state.current_turn = stone.opponent();
+                self.think();
+            }
+            Command::Board => self.read_board(input),
+            Command::Info(key, value) => self.set_info(&key, &value),
+            Command::Takeback(row, col) => {
+                if let Some(stone) = self.game_state.board[row][col] {
+                    self.game_state.undo_move(row, col);
+                    self.game_state.current_turn = stone;
+                    respond("OK");
+                } else {
+                    respond(&format!(
+                        "ERROR no stone at {}",
+                        piskvork::format_coords(row, col)
+                    ));
+                }
+            }
+            Command::End => return false,
+            Command::About => respond(&format!(
+                "name=\"{}\", version=\"{}\"",
+                env!("CARGO_PKG_NAME"),
+                env!("CARGO_PKG_VERSION")
+            )),
+            Command::Unknown(line) => respond(&format!("UNKNOWN {}", line)),
+        }
+        true
+    }
+
+    /// 读取 BOARD 命令之后直到 DONE 的所有行，然后落子
+    fn read_board(&mut self, input: &mut impl Iterator<Item = String>) {
+        let mut own = Vec::new();
+        let mut opponent = Vec::new();
+        for line in input.by_ref() {
+            if line.trim().eq_ignore_ascii_case("DONE") {
+                break;
+            }
+            match piskvork::parse_board_line(&line) {
+                Some((row, col, BoardField::Own)) => own.push((row, col)),
+                Some((row, col, BoardField::Opponent)) => opponent.push((row, col)),
+                None => respond(&format!("ERROR bad board line {}", line.trim())),
+            }
+        }
+
+        // 双方棋子数相同时轮到黑棋，因此本方执黑
+        let own_stone = if own.len() == opponent.len() {
+            Stone::Black
+        } else {
+            Stone::White
+        };
+
+        self.game_state = GameState::new();
+        for (row, col) in own {
+            let _ = self.game_state.apply_move(row, col, own_stone);
+        }
+        for (row, col) in opponent {
+            let _ = self.game_state.apply_move(row, col, own_stone.opponent());
+        }
+        self.game_state.current_turn = own_stone;
+        self.think();
+    }
+
+    fn set_info(&mut self, key: &str, value: &str) {
+        let Ok(value) = value.parse::<u64>() else {
+            return;
+        };
+        match key {
+            "timeout_turn" => self.timeout_turn = Some(value),
+            "time_left" => self.time_left = Some(value),
+            _ => {} // 其余信息 (内存、规则等) 暂不使用
+        }
+    }
+
+    /// 本步可用的思考时间
+    fn turn_budget(&self) -> Duration {
+        let mut ms = match self.timeout_turn {
+            Some(0) => FAST_TURN_TIME_MS,
+            Some(timeout) => timeout,
+            None => DEFAULT_TURN_TIME_MS,
+        };
+        if let Some(left) = self.time_left {
+            ms = ms.min(left / MOVES_TO_GO);
+        }
+        Duration::from_millis(ms * TIME_USAGE_PERCENT / 100)
+    }
+
+    /// 为当前行棋方搜索并落子
+    fn think(&mut self) {
+        let stone = self.game_state.current_turn;
+        let mut agent = SmartAgent::new(stone, MAX_DEPTH);
+        agent.set_time_limit(Some(self.turn_budget()));
+        agent.set_weights(self.weights.clone());
+
+        // 每完成一轮迭代加深就用 MESSAGE 报告一次搜索信息，管理器会显示在日志中
+        let info = agent.search_with(&self.game_state, |info| {
+            respond(&format!("MESSAGE {}", info));
+        });
+        match info.map(|info| info.best_move) {
+            Some((row, col)) => {
+                let _ = self.game_state.apply_move(row, col, stone);
+                self.game_state.current_turn = stone.opponent();
+                respond(&piskvork::format_coords(row, col));
+            }
+            None => respond("ERROR no move available"),
+        }
+    }
+}
+
+/// 命令行给出局面时，搜索一次后输出最佳着法
+fn analyse(position: &Position, time_ms: u64) {
+    let game_state = position.to_game_state();
+    if let Some(winner) = game_state.winner {
+        println!("game over: {:?} has five in a row", winner);
+        return;
+    }
+    let mut agent = SmartAgent::new(game_state.current_turn, MAX_DEPTH);
+    agent.set_time_limit(Some(Duration::from_millis(time_ms)));
+    agent.set_weights(EvalWeights::load_or_default(&weights_path()));
+    let info = agent.search_with(&game_state, |info| println!("info {}", info));
+    match info {
+        Some(info) => println!(
+            "bestmove {}",
+            move_to_notation(info.best_move.0, info.best_move.1)
+        ),
+        None => println!("bestmove none"),
+    }
+}
+
+/// 解析命令行，返回要分析的局面和思考时间；没有参数时返回 None
+fn parse_args() -> Result<Option<(Position, u64)>, String> {
+    let mut args = env::args().skip(1);
+    let mut position = None;
+    let mut time_ms = None;
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{} needs a value", name));
+        match arg.as_str() {
+            "--position" => position = Some(value("--position")?.parse::<Position>()?),
+            "--time" => {
+                let ms = value("--time")?
+                    .parse()
+                    .map_err(|_| "--time needs a number of milliseconds")?;
+                time_ms = Some(ms);
+            }
+            "-h" | "--help" => return Err(String::new()),
+            _ => return Err(format!("unexpected argument {}", arg)),
+        }
+    }
+    match (position, time_ms) {
+        (Some(position), time_ms) => Ok(Some((position, time_ms.unwrap_or(DEFAULT_TURN_TIME_MS)))),
+        (None, Some(_)) => Err("--time needs --position".to_string()),
+        (None, None) => Ok(None),
+    }
+}
+
+/// 管理器启动引擎时的工作目录不确定，因此权重文件放在可执行文件旁边
+fn weights_path() -> PathBuf {
+    env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(WEIGHTS_FILE)))
+        .unwrap_or_else(|| PathBuf::from(WEIGHTS_FILE))
+}
+
+fn respond(line: &str) {
+    let mut stdout = io::stdout().lock();
+    let _ = writeln!(stdout, "{}", line);
+    let _ = stdout.flush();
+}
+
+fn main() {
+    match parse_args() {
+        Ok(Some((position, time_ms))) => {
+            analyse(&position, time_ms);
+            return;
+        }
+        Ok(None) => {}
+        Err(message) => {
+            if !message.is_empty() {
+                eprintln!("error: {}\n", message);
+            }
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    }
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines().map_while(Result::ok);
+    let mut brain = Brain::new();
+
+    while let Some(line) = lines.next() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match line.parse::<Command>() {
+            Ok(command) => {
+                if !brain.handle(command, &mut lines) {
+                    break;
+                }
+            }
+            Err(message) => respond(&format!("ERROR {}", message)),
+        }
+    }
+}
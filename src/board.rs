@@ -1,5 +1,8 @@
-use crate::game::{CELL_SIZE, GRID_SIZE, PlayerScore};
+use crate::game::{CELL_SIZE, GRID_SIZE, PlayerScore, Stone, StoneComponent};
 use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+const BOARD_OFFSET: f32 = -200.0; // 棋盘向左偏移的距离
 
 pub fn setup_board(mut commands: Commands, player_score: Res<PlayerScore>) {
     commands.spawn(Camera2dBundle::default()); // 确保摄像机存在
@@ -40,6 +43,7 @@ pub fn setup_board(mut commands: Commands, player_score: Res<PlayerScore>) {
     let score_y = 350.0; // 得分显示位置
     let reset_button_y = 250.0;
     let switch_button_y = 150.0;
+    let hint_button_y = 50.0;
     
     // 添加玩家得分显示
     commands.spawn((Text2dBundle {
@@ -117,6 +121,79 @@ pub fn setup_board(mut commands: Commands, player_score: Res<PlayerScore>) {
         },
         SwitchButtonText,
     ));
+
+    // 添加提示按钮
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgb(0.8, 0.8, 0.8),
+                custom_size: Some(Vec2::new(200.0, 60.0)),
+                ..default()
+            },
+            transform: Transform::from_xyz(button_x, hint_button_y, 1.0),
+            ..default()
+        },
+        HintButton,
+    ));
+
+    // 添加提示按钮文字 (剩余次数由 update_hint_button_text 更新)
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                "Hint",
+                TextStyle {
+                    font_size: 24.0,
+                    color: Color::BLACK,
+                    ..default()
+                },
+            )
+            .with_alignment(TextAlignment::Center),
+            transform: Transform::from_xyz(button_x, hint_button_y, 2.0),
+            ..default()
+        },
+        HintButtonText,
+    ));
+}
+
+/// 棋盘 (row, col) 交叉点的世界坐标
+pub fn cell_position(row: usize, col: usize) -> Vec2 {
+    Vec2::new(
+        (col as f32 - (GRID_SIZE as f32) / 2.0) * CELL_SIZE + BOARD_OFFSET,
+        (row as f32 - (GRID_SIZE as f32) / 2.0) * CELL_SIZE,
+    )
+}
+
+/// 放在 translation 处的形状。ShapeBundle 的 mesh 和 material 由 lyon 插件填充，
+/// 这里统一用默认值补上
+#[allow(clippy::needless_update)]
+pub fn shape_bundle(path: Path, translation: Vec3) -> ShapeBundle {
+    ShapeBundle {
+        path,
+        spatial: SpatialBundle::from_transform(Transform::from_translation(translation)),
+        ..default()
+    }
+}
+
+/// 在棋盘 (row, col) 处生成一个棋子实体
+pub fn spawn_stone(commands: &mut Commands, row: usize, col: usize, stone: Stone) {
+    let position = cell_position(row, col);
+
+    let color = match stone {
+        Stone::Black => Color::BLACK,
+        Stone::White => Color::WHITE,
+    };
+
+    commands.spawn((
+        shape_bundle(
+            GeometryBuilder::build_as(&shapes::Circle {
+                radius: CELL_SIZE * 0.4,
+                center: Vec2::ZERO,
+            }),
+            position.extend(2.0),
+        ),
+        Fill::color(color),
+        StoneComponent,
+    ));
 }
 
 #[derive(Component)]
@@ -130,3 +207,9 @@ pub struct SwitchButtonText;
 
 #[derive(Component)]
 pub struct ScoreText;
+
+#[derive(Component)]
+pub struct HintButton;
+
+#[derive(Component)]
+pub struct HintButtonText;
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+/// 简单的 TOML 子集：`[section]` 表头、`key = value` 和 `#` 注释。
+/// 值支持带引号的字符串、整数、浮点数和布尔值，读取时键名为 "section.key"。
+#[derive(Debug, Default, Clone)]
+pub struct ConfigFile {
+    values: HashMap<String, String>,
+}
+
+impl ConfigFile {
+    /// 从文件读取配置；文件不存在时返回空配置
+    pub fn load(path: &Path) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut values = HashMap::new();
+        let mut section = String::new();
+
+        for (line_no, raw_line) in text.lines().enumerate() {
+            let line = strip_comment(raw_line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = name.trim().to_string();
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected `key = value`", line_no + 1))?;
+            let key = key.trim();
+            let full_key = if section.is_empty() {
+                key.to_string()
+            } else {
+                format!("{}.{}", section, key)
+            };
+            values.insert(full_key, unquote(value.trim()));
+        }
+
+        Ok(ConfigFile { values })
+    }
+
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+
+    pub fn get_i32(&self, key: &str) -> Option<i32> {
+        self.get_str(key)?.replace('_', "").parse().ok()
+    }
+
+    pub fn get_u64(&self, key: &str) -> Option<u64> {
+        self.get_str(key)?.replace('_', "").parse().ok()
+    }
+
+    pub fn get_f32(&self, key: &str) -> Option<f32> {
+        self.get_str(key)?.replace('_', "").parse().ok()
+    }
+
+    pub fn get_f64(&self, key: &str) -> Option<f64> {
+        self.get_str(key)?.replace('_', "").parse().ok()
+    }
+
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        self.get_str(key)?.parse().ok()
+    }
+}
+
+/// 去掉行尾注释 (忽略字符串中的 `#`，字符串中的 `\"` 不结束字符串)
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, ch) in line.char_indices() {
+        match ch {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// 去掉字符串的引号并还原转义 (`\"`、`\\`、`\n`、`\t`、`\r`，与 `{:?}` 的输出对应)
+fn unquote(value: &str) -> String {
+    let Some(inner) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) else {
+        return value.to_string();
+    };
+    let mut result = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            result.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some(ch @ ('"' | '\\')) => result.push(ch),
+            // 其他反斜杠 (如 Windows 路径 C:\engines) 原样保留
+            Some(other) => {
+                result.push('\\');
+                result.push(other);
+            }
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+/// 生成 TOML 文本的辅助工具，与 ConfigFile 的读取格式对应
+#[derive(Default)]
+pub struct ConfigWriter {
+    text: String,
+}
+
+impl ConfigWriter {
+    pub fn section(&mut self, name: &str) -> &mut Self {
+        if !self.text.is_empty() {
+            self.text.push('\n');
+        }
+        let _ = writeln!(self.text, "[{}]", name);
+        self
+    }
+
+    pub fn value(&mut self, key: &str, value: impl ToString) -> &mut Self {
+        let _ = writeln!(self.text, "{} = {}", key, value.to_string());
+        self
+    }
+
+    pub fn finish(&self) -> &str {
+        &self.text
+    }
+}
@@ -0,0 +1,876 @@
+use crate::board::{cell_position, shape_bundle};
+use crate::config::{ConfigFile, ConfigWriter};
+use crate::game::{CELL_SIZE, GRID_SIZE, GameState, Stone, move_to_notation, parse_notation};
+use crate::psq::{PsqGame, PsqReplay};
+use crate::savegame::{GameResult, SaveStatusText, TimedMove, set_status};
+use crate::transposition::{
+    SYMMETRIES, inverse_transform_point, symmetric_hash, transform_point, zobrist_key,
+};
+use crate::ui::{AppState, GamesButton};
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::thread::{self, JoinHandle};
+
+/// 本地对局库的默认位置 (程序工作目录下)，用 `gobang-db import` 导入
+pub const DB_FILE: &str = "gobang-db.toml";
+/// 对局库 `[database] format` 的值，用来识别对局库文件
+pub const DB_FORMAT: &str = "gobang-db";
+/// 当前的对局库格式版本
+pub const DB_VERSION: u32 = 1;
+// 对局浏览窗口每页列出的对局数
+const GAMES_PER_PAGE: usize = 12;
+// 局面搜索在提示文字中列出的后续着法数
+const MOVES_LISTED: usize = 6;
+// 过滤条件的最大长度
+const MAX_FILTER_LEN: usize = 24;
+// 局面搜索的标记在棋子之上、提示 (z = 3) 之下
+const SEARCH_Z: f32 = 2.6;
+
+/// 对局库中的一局
+#[derive(Debug, Clone, PartialEq)]
+pub struct DbGame {
+    pub source: String, // 来源和原编号 (如 "rif:1234")，重复导入时用来去重
+    pub black: String,
+    pub white: String,
+    pub result: GameResult,
+    pub opening: String,
+    pub event: String, // 赛事名称
+    pub year: Option<u32>,
+    pub rule: String,
+    pub moves: Vec<(usize, usize)>, // 黑先交替的着法 (行, 列)
+}
+
+impl DbGame {
+    /// 对局列表中的一行
+    pub fn summary(&self) -> String {
+        let mut text = format!(
+            "{} - {}  {}",
+            name_or_unknown(&self.black),
+            name_or_unknown(&self.white),
+            self.result.notation()
+        );
+        if !self.opening.is_empty() {
+            text.push_str(&format!("  {}", self.opening));
+        }
+        if !self.event.is_empty() {
+            text.push_str(&format!("  {}", self.event));
+        }
+        if let Some(year) = self.year {
+            text.push_str(&format!(" {}", year));
+        }
+        text.push_str(&format!("  ({} moves)", self.moves.len()));
+        text
+    }
+
+    /// 转为 Piskvork 棋谱，用来在棋盘上回放
+    pub fn psq_game(&self) -> PsqGame {
+        let mut stone = Stone::Black;
+        let moves = self
+            .moves
+            .iter()
+            .map(|&(row, col)| {
+                let m = TimedMove {
+                    row,
+                    col,
+                    stone,
+                    time: 0,
+                };
+                stone = stone.opponent();
+                m
+            })
+            .collect();
+        PsqGame {
+            black: self.black.clone(),
+            white: self.white.clone(),
+            moves,
+        }
+    }
+}
+
+fn name_or_unknown(name: &str) -> &str {
+    if name.is_empty() { "?" } else { name }
+}
+
+/// 对局浏览的过滤条件。棋手和开局按名字的一部分匹配，不区分大小写
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GameFilter {
+    pub player: String, // 黑方或白方
+    pub opening: String,
+    pub result: Option<GameResult>,
+}
+
+impl GameFilter {
+    pub fn matches(&self, game: &DbGame) -> bool {
+        let contains = |text: &str, part: &str| text.to_lowercase().contains(&part.to_lowercase());
+        (self.player.is_empty()
+            || contains(&game.black, &self.player)
+            || contains(&game.white, &self.player))
+            && (self.opening.is_empty() || contains(&game.opening, &self.opening))
+            && self.result.is_none_or(|result| result == game.result)
+    }
+}
+
+/// 局面搜索中的一个后续着法：走过的对局数和这些对局的结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct NextMove {
+    pub row: usize,
+    pub col: usize,
+    pub games: u32,
+    pub black_wins: u32,
+    pub draws: u32,
+    pub white_wins: u32,
+}
+
+impl NextMove {
+    /// stone 一方在有结果的对局中的得分率 (和棋算半局)，没有有结果的对局时为 None
+    pub fn score(&self, stone: Stone) -> Option<f64> {
+        let decided = self.black_wins + self.draws + self.white_wins;
+        if decided == 0 {
+            return None;
+        }
+        let wins = match stone {
+            Stone::Black => self.black_wins,
+            Stone::White => self.white_wins,
+        };
+        Some((wins as f64 + self.draws as f64 / 2.0) / decided as f64)
+    }
+}
+
+/// 局面搜索的结果：到达这个局面的对局数，以及按对局数从多到少排列的后续着法
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PositionStats {
+    pub games: usize,
+    pub moves: Vec<NextMove>,
+}
+
+/// 本地对局库 (如导入的 RIF 数据库)。文件较大，第一次用到时才在后台线程中读取
+#[derive(Resource, Debug, Default)]
+pub struct GameDatabase {
+    pub games: Vec<DbGame>,
+    loaded: bool,
+    loading: Option<JoinHandle<GameDatabase>>, // 正在后台读取 DB_FILE 的线程
+}
+
+impl GameDatabase {
+    /// 读取对局库，文件不存在时返回空的对局库
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let config = ConfigFile::load(path)?;
+        let mut database = GameDatabase {
+            loaded: true,
+            ..GameDatabase::default()
+        };
+        let Some(format) = config.get_str("database.format") else {
+            return Ok(database);
+        };
+        if format != DB_FORMAT {
+            return Err(format!("{}: not a gobang game database", path.display()));
+        }
+        let version = config.get_u64("database.version").unwrap_or(0);
+        if version == 0 || version > DB_VERSION as u64 {
+            return Err(format!(
+                "{}: unsupported database version {}",
+                path.display(),
+                version
+            ));
+        }
+
+        let count = config.get_u64("database.games").unwrap_or(0);
+        for index in 1..=count {
+            let key = |field: &str| format!("game.{}.{}", index, field);
+            let text = |field: &str| config.get_str(&key(field)).unwrap_or_default().to_string();
+            let moves = config
+                .get_str(&key("moves"))
+                .unwrap_or_default()
+                .split_whitespace()
+                .map(|m| {
+                    parse_notation(m).ok_or_else(|| {
+                        format!("{}: game {}: invalid move {}", path.display(), index, m)
+                    })
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+            database.games.push(DbGame {
+                source: text("source"),
+                black: text("black"),
+                white: text("white"),
+                result: GameResult::from_notation(&text("result")).unwrap_or(GameResult::Ongoing),
+                opening: text("opening"),
+                event: text("event"),
+                year: config.get_u64(&key("year")).map(|year| year as u32),
+                rule: text("rule"),
+                moves,
+            });
+        }
+        Ok(database)
+    }
+
+    pub fn to_toml(&self) -> String {
+        let mut writer = ConfigWriter::default();
+        writer
+            .section("database")
+            .value("format", format!("{:?}", DB_FORMAT))
+            .value("version", DB_VERSION)
+            .value("games", self.games.len());
+        for (index, game) in self.games.iter().enumerate() {
+            let moves: Vec<String> = game
+                .moves
+                .iter()
+                .map(|&(row, col)| move_to_notation(row, col))
+                .collect();
+            writer
+                .section(&format!("game.{}", index + 1))
+                .value("source", format!("{:?}", game.source))
+                .value("black", format!("{:?}", game.black))
+                .value("white", format!("{:?}", game.white))
+                .value("result", format!("{:?}", game.result.notation()))
+                .value("opening", format!("{:?}", game.opening))
+                .value("event", format!("{:?}", game.event))
+                .value("rule", format!("{:?}", game.rule));
+            if let Some(year) = game.year {
+                writer.value("year", year);
+            }
+            writer.value("moves", format!("{:?}", moves.join(" ")));
+        }
+        writer.finish().to_string()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.to_toml()).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// 加入对局，跳过来源已经在库中的对局 (重复导入同一数据库)。返回加入的对局数
+    pub fn merge(&mut self, games: Vec<DbGame>) -> usize {
+        let mut sources: HashSet<String> = self
+            .games
+            .iter()
+            .filter(|g| !g.source.is_empty())
+            .map(|g| g.source.clone())
+            .collect();
+        let before = self.games.len();
+        for game in games {
+            if game.source.is_empty() || sources.insert(game.source.clone()) {
+                self.games.push(game);
+            }
+        }
+        self.games.len() - before
+    }
+
+    /// 第一次用到时在后台线程中读取 DB_FILE，读完由 finish_database_loading 换上。
+    /// 出错时打印原因并使用空的对局库
+    pub fn start_loading(&mut self) {
+        if self.loaded || self.loading.is_some() {
+            return;
+        }
+        self.loading = Some(thread::spawn(|| {
+            let database = Self::load(Path::new(DB_FILE)).unwrap_or_else(|e| {
+                eprintln!("读取对局库失败: {}", e);
+                GameDatabase {
+                    loaded: true,
+                    ..GameDatabase::default()
+                }
+            });
+            println!("对局库 {}: {} 局", DB_FILE, database.games.len());
+            database
+        }));
+    }
+
+    /// 是否正在后台读取
+    pub fn is_loading(&self) -> bool {
+        self.loading.is_some()
+    }
+
+    /// 符合过滤条件的对局的下标
+    pub fn filtered(&self, filter: &GameFilter) -> Vec<usize> {
+        (0..self.games.len())
+            .filter(|&index| filter.matches(&self.games[index]))
+            .collect()
+    }
+
+    /// 查找符合过滤条件、走到过当前局面 (包括它的 8 种对称局面) 的对局，
+    /// 统计每个后续着法的次数和结果。着法已换回当前棋盘的方向，当前局面本身对称时
+    /// 等价的着法算作一个
+    pub fn search_position(&self, game_state: &GameState, filter: &GameFilter) -> PositionStats {
+        let stones = game_state.board.iter().flatten().flatten().count();
+        let targets: Vec<u64> = (0..SYMMETRIES)
+            .map(|symmetry| symmetric_hash(game_state, symmetry))
+            .collect();
+        // 当前局面自身的对称：在这些变换下等价的后续着法合并为一个
+        let invariant: Vec<u8> = (0..SYMMETRIES)
+            .filter(|&symmetry| targets[symmetry as usize] == targets[0])
+            .collect();
+
+        let mut stats = PositionStats::default();
+        for game in &self.games {
+            if game.moves.len() < stones || !filter.matches(game) {
+                continue;
+            }
+            let mut hash = 0;
+            let mut stone = Stone::Black;
+            for &(row, col) in &game.moves[..stones] {
+                hash ^= zobrist_key(row, col, stone);
+                stone = stone.opponent();
+            }
+            // 对局中的局面是当前局面的第 symmetry 种对称变换
+            let Some(symmetry) = targets.iter().position(|&target| target == hash) else {
+                continue;
+            };
+            stats.games += 1;
+            let Some(&next) = game.moves.get(stones) else {
+                continue;
+            };
+            let point = inverse_transform_point(next, symmetry as u8);
+            let (row, col) = invariant
+                .iter()
+                .map(|&symmetry| transform_point(point, symmetry))
+                .min()
+                .unwrap_or(point);
+            let index = match stats
+                .moves
+                .iter()
+                .position(|m| (m.row, m.col) == (row, col))
+            {
+                Some(index) => index,
+                None => {
+                    stats.moves.push(NextMove {
+                        row,
+                        col,
+                        games: 0,
+                        black_wins: 0,
+                        draws: 0,
+                        white_wins: 0,
+                    });
+                    stats.moves.len() - 1
+                }
+            };
+            let entry = &mut stats.moves[index];
+            entry.games += 1;
+            match game.result {
+                GameResult::Win(Stone::Black) => entry.black_wins += 1,
+                GameResult::Win(Stone::White) => entry.white_wins += 1,
+                GameResult::Draw => entry.draws += 1,
+                GameResult::Ongoing => {}
+            }
+        }
+        stats.moves.sort_by_key(|m| Reverse(m.games));
+        stats
+    }
+}
+
+/// 当前局面轮到哪一方 (按棋子数判断，黑先)
+fn side_to_move(game_state: &GameState) -> Stone {
+    let black = game_state
+        .board
+        .iter()
+        .flatten()
+        .filter(|&&cell| cell == Some(Stone::Black))
+        .count();
+    let white = game_state
+        .board
+        .iter()
+        .flatten()
+        .filter(|&&cell| cell == Some(Stone::White))
+        .count();
+    if black > white {
+        Stone::White
+    } else {
+        Stone::Black
+    }
+}
+
+/// 正在编辑的过滤条件
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum FilterField {
+    #[default]
+    Player,
+    Opening,
+}
+
+/// 对局浏览的状态：过滤条件、页码，以及棋盘上的局面搜索。
+/// 局面搜索按 D 键切换，使用浏览窗口中设置的过滤条件
+#[derive(Resource, Default)]
+pub struct GameBrowser {
+    pub filter: GameFilter,
+    page: usize,
+    focus: FilterField,
+    pub show_position: bool,
+    // 局面搜索标记当前对应的局面，局面变化后重新搜索
+    board: Option<[[Option<Stone>; GRID_SIZE + 1]; GRID_SIZE + 1]>,
+}
+
+/// 主菜单中的对局浏览窗口
+#[derive(Component)]
+pub struct GameBrowserWindow;
+
+/// 对局浏览窗口中的按钮
+#[derive(Component, Clone, PartialEq)]
+pub enum GameBrowserButton {
+    Focus(FilterField),         // 切换正在编辑的过滤条件
+    Result(Option<GameResult>), // 按结果过滤，None 为全部
+    Previous,
+    Next,
+    Open(usize), // 在棋盘上回放对局库中的第几局
+    Close,
+}
+
+/// 局面搜索在棋盘上画出的后续着法
+#[derive(Component)]
+pub struct PositionSearchMarker;
+
+/// 系统：点击主菜单的 "Master Games" 按钮打开对局浏览窗口
+pub fn handle_games_button(
+    mut commands: Commands,
+    button_query: Query<&Interaction, (Changed<Interaction>, With<GamesButton>)>,
+    mut database: ResMut<GameDatabase>,
+    browser: Res<GameBrowser>,
+    window_query: Query<Entity, With<GameBrowserWindow>>,
+) {
+    if button_query.iter().any(|i| *i == Interaction::Pressed) && window_query.is_empty() {
+        database.start_loading();
+        spawn_browser_window(&mut commands, &database, &browser);
+    }
+}
+
+/// 系统：对局浏览窗口中的按钮，以及输入过滤条件 (Tab 切换棋手、开局)。
+/// 点击对局后进入游戏，在棋盘上回放这局 (左、右方向键逐步后退、前进)
+#[allow(clippy::too_many_arguments)]
+pub fn handle_game_browser(
+    mut commands: Commands,
+    button_query: Query<(&Interaction, &GameBrowserButton), Changed<Interaction>>,
+    mut characters: EventReader<ReceivedCharacter>,
+    keys: Res<Input<KeyCode>>,
+    database: Res<GameDatabase>,
+    mut browser: ResMut<GameBrowser>,
+    mut replay: ResMut<PsqReplay>,
+    mut next_state: ResMut<NextState<AppState>>,
+    window_query: Query<Entity, With<GameBrowserWindow>>,
+) {
+    if window_query.is_empty() {
+        characters.clear();
+        return;
+    }
+
+    let mut changed = false;
+    let field = match browser.focus {
+        FilterField::Player => &mut browser.filter.player,
+        FilterField::Opening => &mut browser.filter.opening,
+    };
+    for event in characters.read() {
+        if !event.char.is_control() && field.chars().count() < MAX_FILTER_LEN {
+            field.push(event.char);
+            changed = true;
+        }
+    }
+    if keys.just_pressed(KeyCode::Back) {
+        changed |= field.pop().is_some();
+    }
+    if keys.just_pressed(KeyCode::Tab) {
+        browser.focus = match browser.focus {
+            FilterField::Player => FilterField::Opening,
+            FilterField::Opening => FilterField::Player,
+        };
+        changed = true;
+    }
+    if changed {
+        browser.page = 0;
+    }
+
+    let action = button_query
+        .iter()
+        .find(|(interaction, _)| **interaction == Interaction::Pressed)
+        .map(|(_, button)| button.clone());
+    match action {
+        Some(GameBrowserButton::Focus(focus)) => browser.focus = focus,
+        Some(GameBrowserButton::Result(result)) => {
+            browser.filter.result = result;
+            browser.page = 0;
+        }
+        Some(GameBrowserButton::Previous) => browser.page = browser.page.saturating_sub(1),
+        Some(GameBrowserButton::Next) => {
+            let pages = database
+                .filtered(&browser.filter)
+                .len()
+                .div_ceil(GAMES_PER_PAGE);
+            browser.page = (browser.page + 1).min(pages.saturating_sub(1));
+        }
+        Some(GameBrowserButton::Open(index)) => {
+            replay.open(database.games[index].psq_game());
+            next_state.set(AppState::InGame);
+            return;
+        }
+        Some(GameBrowserButton::Close) => {
+            for entity in window_query.iter() {
+                commands.entity(entity).despawn_recursive();
+            }
+            return;
+        }
+        None if !changed => return,
+        None => {}
+    }
+
+    for entity in window_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    spawn_browser_window(&mut commands, &database, &browser);
+}
+
+/// 生成对局浏览窗口：过滤条件、符合条件的对局 (分页) 和翻页按钮
+fn spawn_browser_window(commands: &mut Commands, database: &GameDatabase, browser: &GameBrowser) {
+    let text_style = |font_size: f32| TextStyle {
+        font_size,
+        color: Color::rgb(0.2, 0.2, 0.2),
+        ..default()
+    };
+    let matching = database.filtered(&browser.filter);
+    let pages = matching.len().div_ceil(GAMES_PER_PAGE).max(1);
+    let page = browser.page.min(pages - 1);
+    let title = if database.is_loading() {
+        format!("Loading {} ...", DB_FILE)
+    } else if database.games.is_empty() {
+        format!(
+            "No games in {} (import with: gobang-db import <rif.xml>)",
+            DB_FILE
+        )
+    } else {
+        format!(
+            "Master games: {} of {} match",
+            matching.len(),
+            database.games.len()
+        )
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(20.0),
+                    top: Val::Px(20.0),
+                    width: Val::Px(760.0),
+                    height: Val::Px(760.0),
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(20.0)),
+                    ..default()
+                },
+                background_color: Color::rgb(0.9, 0.9, 0.9).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            GameBrowserWindow,
+        ))
+        .with_children(|parent| {
+            let margin = Style {
+                margin: UiRect::bottom(Val::Px(10.0)),
+                ..default()
+            };
+            parent.spawn(
+                TextBundle::from_section(title, text_style(24.0)).with_style(margin.clone()),
+            );
+
+            // 过滤条件：点击 (或按 Tab) 选择要输入的一项
+            let row_style = Style {
+                flex_direction: FlexDirection::Row,
+                flex_wrap: FlexWrap::Wrap,
+                column_gap: Val::Px(10.0),
+                row_gap: Val::Px(10.0),
+                margin: UiRect::bottom(Val::Px(10.0)),
+                ..default()
+            };
+            parent
+                .spawn(NodeBundle {
+                    style: row_style.clone(),
+                    ..default()
+                })
+                .with_children(|row| {
+                    for (field, label, value) in [
+                        (FilterField::Player, "Player", &browser.filter.player),
+                        (FilterField::Opening, "Opening", &browser.filter.opening),
+                    ] {
+                        let cursor = if browser.focus == field { "_" } else { "" };
+                        spawn_browser_button(
+                            row,
+                            &format!("{}: {}{}", label, value, cursor),
+                            browser.focus == field,
+                            GameBrowserButton::Focus(field),
+                        );
+                    }
+                });
+            parent
+                .spawn(NodeBundle {
+                    style: row_style.clone(),
+                    ..default()
+                })
+                .with_children(|row| {
+                    for (label, result) in [
+                        ("All results", None),
+                        ("1-0", Some(GameResult::Win(Stone::Black))),
+                        ("0-1", Some(GameResult::Win(Stone::White))),
+                        ("Draw", Some(GameResult::Draw)),
+                    ] {
+                        spawn_browser_button(
+                            row,
+                            label,
+                            browser.filter.result == result,
+                            GameBrowserButton::Result(result),
+                        );
+                    }
+                });
+
+            // 这一页的对局，点击回放
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(4.0),
+                        flex_grow: 1.0,
+                        margin: UiRect::bottom(Val::Px(10.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|list| {
+                    for &index in matching
+                        .iter()
+                        .skip(page * GAMES_PER_PAGE)
+                        .take(GAMES_PER_PAGE)
+                    {
+                        spawn_game_row(list, &database.games[index].summary(), index);
+                    }
+                });
+
+            parent.spawn(
+                TextBundle::from_section(
+                    "Type to edit the selected filter, Tab to switch. Click a game to replay it; \
+                     press D in the game to search the database for the board position.",
+                    text_style(14.0),
+                )
+                .with_style(margin.clone()),
+            );
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(20.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|row| {
+                    spawn_browser_button(row, "Previous", false, GameBrowserButton::Previous);
+                    row.spawn(TextBundle::from_section(
+                        format!("Page {}/{}", page + 1, pages),
+                        text_style(18.0),
+                    ));
+                    spawn_browser_button(row, "Next", false, GameBrowserButton::Next);
+                    spawn_browser_button(row, "Close", false, GameBrowserButton::Close);
+                });
+        });
+}
+
+fn spawn_browser_button(
+    parent: &mut ChildBuilder,
+    label: &str,
+    selected: bool,
+    button: GameBrowserButton,
+) {
+    let background = if selected {
+        Color::rgb(0.2, 0.4, 0.6)
+    } else {
+        Color::rgb(0.15, 0.15, 0.15)
+    };
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    min_width: Val::Px(100.0),
+                    height: Val::Px(36.0),
+                    padding: UiRect::horizontal(Val::Px(10.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: background.into(),
+                ..default()
+            },
+            button,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                label,
+                TextStyle {
+                    font_size: 18.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn spawn_game_row(parent: &mut ChildBuilder, summary: &str, index: usize) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    height: Val::Px(30.0),
+                    padding: UiRect::horizontal(Val::Px(8.0)),
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::rgb(0.97, 0.97, 0.97).into(),
+                ..default()
+            },
+            GameBrowserButton::Open(index),
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                summary,
+                TextStyle {
+                    font_size: 16.0,
+                    color: Color::rgb(0.2, 0.2, 0.2),
+                    ..default()
+                },
+            ));
+        });
+}
+
+/// 系统：后台读取完对局库后换上读到的对局，刷新打开的浏览窗口，并重新搜索当前局面
+pub fn finish_database_loading(
+    mut commands: Commands,
+    mut database: ResMut<GameDatabase>,
+    mut browser: ResMut<GameBrowser>,
+    window_query: Query<Entity, With<GameBrowserWindow>>,
+) {
+    // 读完之前不可变借用，避免每帧都触发对局库的变更检测
+    if !database
+        .loading
+        .as_ref()
+        .is_some_and(JoinHandle::is_finished)
+    {
+        return;
+    }
+    let Some(handle) = database.loading.take() else {
+        return;
+    };
+    *database = handle.join().unwrap_or_else(|_| GameDatabase {
+        loaded: true,
+        ..GameDatabase::default()
+    });
+    if !window_query.is_empty() {
+        for entity in window_query.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        spawn_browser_window(&mut commands, &database, &browser);
+    }
+    browser.board = None;
+}
+
+/// 系统：按 D 键开始或停止在对局库中搜索当前局面
+pub fn toggle_position_search(
+    keys: Res<Input<KeyCode>>,
+    mut browser: ResMut<GameBrowser>,
+    mut database: ResMut<GameDatabase>,
+) {
+    if keys.just_pressed(KeyCode::D) {
+        browser.show_position = !browser.show_position;
+        if browser.show_position {
+            database.start_loading();
+        }
+    }
+}
+
+/// 系统：局面搜索开启时，局面一变化就在对局库中搜索，在棋盘上标出每个后续着法的对局数
+/// (按轮到的一方的得分率着色：绿色高于 55%，红色低于 45%)，并在提示文字中列出最常见的几个
+pub fn update_position_search(
+    mut commands: Commands,
+    game_state: Res<GameState>,
+    database: Res<GameDatabase>,
+    mut browser: ResMut<GameBrowser>,
+    marker_query: Query<Entity, With<PositionSearchMarker>>,
+    mut status_query: Query<&mut Text, With<SaveStatusText>>,
+) {
+    let wanted = browser.show_position.then_some(game_state.board);
+    if browser.board == wanted {
+        return;
+    }
+    for entity in marker_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    browser.board = wanted;
+    if !browser.show_position {
+        return;
+    }
+    if database.is_loading() {
+        set_status(&mut status_query, format!("Loading {} ...", DB_FILE));
+        return;
+    }
+
+    let stats = database.search_position(&game_state, &browser.filter);
+    let stone = side_to_move(&game_state);
+    for m in &stats.moves {
+        let position = cell_position(m.row, m.col);
+        let color = match m.score(stone) {
+            Some(score) if score > 0.55 => Color::rgb(0.1, 0.5, 0.1),
+            Some(score) if score < 0.45 => Color::rgb(0.75, 0.1, 0.1),
+            _ => Color::rgb(0.35, 0.35, 0.35),
+        };
+        commands.spawn((
+            shape_bundle(
+                GeometryBuilder::build_as(&shapes::Circle {
+                    radius: 0.4 * CELL_SIZE,
+                    center: Vec2::ZERO,
+                }),
+                position.extend(SEARCH_Z),
+            ),
+            Fill::color(Color::rgba(1.0, 1.0, 1.0, 0.6)),
+            Stroke::new(color, 2.0),
+            PositionSearchMarker,
+        ));
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    m.games.to_string(),
+                    TextStyle {
+                        font_size: 14.0,
+                        color,
+                        ..default()
+                    },
+                )
+                .with_alignment(TextAlignment::Center),
+                transform: Transform::from_xyz(position.x, position.y, SEARCH_Z + 0.1),
+                ..default()
+            },
+            PositionSearchMarker,
+        ));
+    }
+
+    let mut message = format!(
+        "Database: {} of {} games reach this position",
+        stats.games,
+        database.games.len()
+    );
+    for m in stats.moves.iter().take(MOVES_LISTED) {
+        let score = m
+            .score(stone)
+            .map_or("-".to_string(), |score| format!("{:.0}%", score * 100.0));
+        message.push_str(&format!(
+            "\n{}: {} games, +{} ={} -{}, {} for {:?}",
+            move_to_notation(m.row, m.col),
+            m.games,
+            match stone {
+                Stone::Black => m.black_wins,
+                Stone::White => m.white_wins,
+            },
+            m.draws,
+            match stone {
+                Stone::Black => m.white_wins,
+                Stone::White => m.black_wins,
+            },
+            score,
+            stone
+        ));
+    }
+    set_status(&mut status_query, message);
+}
@@ -0,0 +1,251 @@
+// 95% 置信区间对应的正态分布分位数
+const Z_95: f64 = 1.959_964;
+
+/// 将期望得分 (0-1) 换算为 Elo 差
+pub fn elo_from_score(score: f64) -> f64 {
+    let score = score.clamp(1e-6, 1.0 - 1e-6);
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+/// 将 Elo 差换算为期望得分
+pub fn score_from_elo(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+/// 一方 (引擎 A) 视角的胜/和/负统计
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MatchStats {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl MatchStats {
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// 记录一局，score 为 A 的得分 (1、0.5 或 0)
+    pub fn add(&mut self, score: f64) {
+        if score > 0.75 {
+            self.wins += 1;
+        } else if score < 0.25 {
+            self.losses += 1;
+        } else {
+            self.draws += 1;
+        }
+    }
+
+    /// 平均得分
+    pub fn score(&self) -> f64 {
+        let games = self.games();
+        if games == 0 {
+            return 0.5;
+        }
+        (self.wins as f64 + 0.5 * self.draws as f64) / games as f64
+    }
+
+    /// 单局得分的方差
+    fn variance(&self) -> f64 {
+        let games = self.games() as f64;
+        if games == 0.0 {
+            return 0.0;
+        }
+        let s = self.score();
+        (self.wins as f64 * (1.0 - s).powi(2)
+            + self.draws as f64 * (0.5 - s).powi(2)
+            + self.losses as f64 * s.powi(2))
+            / games
+    }
+
+    /// Elo 差及其 95% 置信区间的半宽
+    pub fn elo(&self) -> (f64, f64) {
+        let games = self.games() as f64;
+        let s = self.score();
+        let elo = elo_from_score(s);
+        if games == 0.0 {
+            return (elo, f64::INFINITY);
+        }
+        let margin = Z_95 * (self.variance() / games).sqrt();
+        let low = elo_from_score(s - margin);
+        let high = elo_from_score(s + margin);
+        (elo, (high - low) / 2.0)
+    }
+}
+
+/// SPRT 的结论
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SprtVerdict {
+    AcceptH0, // 没有达到 elo1 的提升
+    AcceptH1, // 至少有 elo1 的提升
+    Continue, // 证据不足，需要更多对局
+}
+
+/// 序贯概率比检验：H0 为 Elo 差等于 elo0，H1 为 Elo 差等于 elo1
+#[derive(Debug, Clone, Copy)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+impl Default for Sprt {
+    fn default() -> Self {
+        Sprt {
+            elo0: 0.0,
+            elo1: 10.0,
+            alpha: 0.05,
+            beta: 0.05,
+        }
+    }
+}
+
+impl Sprt {
+    /// 接受 H0 和 H1 的对数似然比界限
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    /// 对数似然比 (按正态近似计算)
+    pub fn llr(&self, stats: &MatchStats) -> f64 {
+        let variance = stats.variance();
+        if stats.games() == 0 || variance == 0.0 {
+            return 0.0;
+        }
+        let s0 = score_from_elo(self.elo0);
+        let s1 = score_from_elo(self.elo1);
+        let s = stats.score();
+        stats.games() as f64 * (s1 - s0) * (2.0 * s - s0 - s1) / (2.0 * variance)
+    }
+
+    pub fn verdict(&self, stats: &MatchStats) -> SprtVerdict {
+        let (lower, upper) = self.bounds();
+        let llr = self.llr(stats);
+        if llr >= upper {
+            SprtVerdict::AcceptH1
+        } else if llr <= lower {
+            SprtVerdict::AcceptH0
+        } else {
+            SprtVerdict::Continue
+        }
+    }
+}
+
+// Glicko-2 的标度换算系数 (Glicko 分数 = 173.7178 × μ + 1500)
+const GLICKO_SCALE: f64 = 173.7178;
+// 波动率的变化约束，越小评分越稳定
+const GLICKO_TAU: f64 = 0.5;
+// 计算新波动率时迭代的收敛精度
+const GLICKO_EPSILON: f64 = 1e-6;
+
+/// Glicko-2 评分，以 Glicko 标度表示 (新玩家为 1500 ± 350)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Glicko2 {
+    pub rating: f64,
+    pub deviation: f64, // 评分偏差 (RD)，越小表示评分越可靠
+    pub volatility: f64,
+}
+
+impl Default for Glicko2 {
+    fn default() -> Self {
+        Glicko2 {
+            rating: 1500.0,
+            deviation: 350.0,
+            volatility: 0.06,
+        }
+    }
+}
+
+impl Glicko2 {
+    /// 评分的 95% 置信区间半宽
+    pub fn margin(&self) -> f64 {
+        Z_95 * self.deviation
+    }
+
+    /// 与 opponent 下完一局后的新评分，每局作为一个评分周期。score 为本方得分 (1、0.5 或 0)
+    pub fn update(&self, opponent: &Glicko2, score: f64) -> Glicko2 {
+        self.update_period(&[(*opponent, score)])
+    }
+
+    /// 一个评分周期内与多个对手对局后的新评分 (Glickman 的 Glicko-2 算法)，
+    /// results 为 (对手, 本方得分)。周期内没有对局时只增大评分偏差
+    pub fn update_period(&self, results: &[(Glicko2, f64)]) -> Glicko2 {
+        let mu = (self.rating - 1500.0) / GLICKO_SCALE;
+        let phi = self.deviation / GLICKO_SCALE;
+        if results.is_empty() {
+            let phi_star = (phi.powi(2) + self.volatility.powi(2)).sqrt();
+            return Glicko2 {
+                deviation: GLICKO_SCALE * phi_star,
+                ..*self
+            };
+        }
+
+        // (g(φj), E(μ, μj, φj), sj)
+        let games: Vec<(f64, f64, f64)> = results
+            .iter()
+            .map(|(opponent, score)| {
+                let opponent_mu = (opponent.rating - 1500.0) / GLICKO_SCALE;
+                let opponent_phi = opponent.deviation / GLICKO_SCALE;
+                let g =
+                    1.0 / (1.0 + 3.0 * opponent_phi.powi(2) / std::f64::consts::PI.powi(2)).sqrt();
+                let expected = 1.0 / (1.0 + (-g * (mu - opponent_mu)).exp());
+                (g, expected, *score)
+            })
+            .collect();
+        let v = 1.0
+            / games
+                .iter()
+                .map(|(g, expected, _)| g.powi(2) * expected * (1.0 - expected))
+                .sum::<f64>();
+        let improvement: f64 = games
+            .iter()
+            .map(|(g, expected, score)| g * (score - expected))
+            .sum();
+        let delta = v * improvement;
+
+        // 用 Illinois 算法求新的波动率
+        let a = self.volatility.powi(2).ln();
+        let f = |x: f64| {
+            let ex = x.exp();
+            ex * (delta.powi(2) - phi.powi(2) - v - ex) / (2.0 * (phi.powi(2) + v + ex).powi(2))
+                - (x - a) / GLICKO_TAU.powi(2)
+        };
+        let mut low = a;
+        let mut high = if delta.powi(2) > phi.powi(2) + v {
+            (delta.powi(2) - phi.powi(2) - v).ln()
+        } else {
+            let mut k = 1.0;
+            while f(a - k * GLICKO_TAU) < 0.0 {
+                k += 1.0;
+            }
+            a - k * GLICKO_TAU
+        };
+        let (mut f_low, mut f_high) = (f(low), f(high));
+        while (high - low).abs() > GLICKO_EPSILON {
+            let c = low + (low - high) * f_low / (f_high - f_low);
+            let f_c = f(c);
+            if f_c * f_high <= 0.0 {
+                low = high;
+                f_low = f_high;
+            } else {
+                f_low /= 2.0;
+            }
+            high = c;
+            f_high = f_c;
+        }
+        let volatility = (low / 2.0).exp();
+
+        let phi_star = (phi.powi(2) + volatility.powi(2)).sqrt();
+        let new_phi = 1.0 / (1.0 / phi_star.powi(2) + 1.0 / v).sqrt();
+        let new_mu = mu + new_phi.powi(2) * improvement;
+        Glicko2 {
+            rating: GLICKO_SCALE * new_mu + 1500.0,
+            deviation: GLICKO_SCALE * new_phi,
+            volatility,
+        }
+    }
+}
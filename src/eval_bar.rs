@@ -0,0 +1,135 @@
+use crate::board::cell_position;
+use crate::game::{CELL_SIZE, GRID_SIZE, GameState, Stone, win_probability};
+use crate::review::MoveReview;
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+
+const BAR_WIDTH: f32 = 16.0;
+const BAR_GAP: f32 = 30.0; // 评估条与棋盘左边缘的距离
+const BAR_HEIGHT: f32 = GRID_SIZE as f32 * CELL_SIZE; // 与棋盘等高
+const FILL_COLOR: Color = Color::rgb(0.1, 0.1, 0.1);
+const PENDING_COLOR: Color = Color::rgb(0.6, 0.6, 0.6); // 最后一步还没评价完时的填充色
+
+/// 评估条的白方部分 (底色)
+#[derive(Component)]
+pub struct EvalBarBackground;
+
+/// 评估条的黑方部分，从底部向上填充
+#[derive(Component)]
+pub struct EvalBarFill;
+
+/// 评估条上方的黑方胜率文字
+#[derive(Component)]
+pub struct EvalBarText;
+
+// 棋盘左边缘 (a 列) 再向左 BAR_GAP
+fn bar_x() -> f32 {
+    cell_position(0, 0).x - BAR_GAP
+}
+
+/// 当前局面下黑方的胜率：取最后一步棋的评价分数 (走棋一方的视角) 换算。
+/// 还没有落子或已经重新开局时为 0.5；最后一步还在后台评价时为 None
+pub fn black_win_probability(game_state: &GameState, review: &MoveReview) -> Option<f64> {
+    if review.is_grading() {
+        return None;
+    }
+    if review.is_stale(game_state) {
+        return Some(0.5);
+    }
+    Some(match review.moves.last() {
+        Some(last) => match last.stone {
+            Stone::Black => win_probability(last.score),
+            Stone::White => 1.0 - win_probability(last.score),
+        },
+        None => 0.5,
+    })
+}
+
+// 创建棋盘左侧的竖直评估条：黑色部分的高度为黑方胜率，白色部分为白方胜率
+pub fn setup_eval_bar(mut commands: Commands) {
+    let x = bar_x();
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgb(0.95, 0.95, 0.95),
+                custom_size: Some(Vec2::new(BAR_WIDTH, BAR_HEIGHT)),
+                ..default()
+            },
+            transform: Transform::from_xyz(x, 0.0, 1.0),
+            ..default()
+        },
+        EvalBarBackground,
+    ));
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: FILL_COLOR,
+                custom_size: Some(Vec2::new(BAR_WIDTH, BAR_HEIGHT / 2.0)),
+                anchor: Anchor::BottomCenter,
+                ..default()
+            },
+            transform: Transform::from_xyz(x, -BAR_HEIGHT / 2.0, 1.1),
+            ..default()
+        },
+        EvalBarFill,
+    ));
+    // 中线：双方机会均等的位置
+    commands.spawn(SpriteBundle {
+        sprite: Sprite {
+            color: Color::rgb(0.8, 0.2, 0.2),
+            custom_size: Some(Vec2::new(BAR_WIDTH + 6.0, 2.0)),
+            ..default()
+        },
+        transform: Transform::from_xyz(x, 0.0, 1.2),
+        ..default()
+    });
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                eval_bar_text(Some(0.5)),
+                TextStyle {
+                    font_size: 16.0,
+                    color: Color::rgb(0.2, 0.2, 0.2),
+                    ..default()
+                },
+            )
+            .with_alignment(TextAlignment::Center),
+            transform: Transform::from_xyz(x, BAR_HEIGHT / 2.0 + 24.0, 2.0),
+            ..default()
+        },
+        EvalBarText,
+    ));
+}
+
+fn eval_bar_text(black: Option<f64>) -> String {
+    match black {
+        Some(black) => format!("B {:.0}%\nW {:.0}%", black * 100.0, (1.0 - black) * 100.0),
+        None => "B --\nW --".to_string(),
+    }
+}
+
+/// 系统：每走一步 (或重新开局) 后按最后一步的评价分数更新评估条。
+/// 评价完成前显示灰色的中间状态，不沿用上一步的胜率
+pub fn update_eval_bar(
+    game_state: Res<GameState>,
+    review: Res<MoveReview>,
+    mut fill_query: Query<&mut Sprite, With<EvalBarFill>>,
+    mut text_query: Query<&mut Text, With<EvalBarText>>,
+) {
+    if !game_state.is_changed() && !review.is_changed() {
+        return;
+    }
+    let black = black_win_probability(&game_state, &review);
+    for mut sprite in fill_query.iter_mut() {
+        let height = BAR_HEIGHT * black.unwrap_or(0.5) as f32;
+        sprite.custom_size = Some(Vec2::new(BAR_WIDTH, height));
+        sprite.color = if black.is_some() {
+            FILL_COLOR
+        } else {
+            PENDING_COLOR
+        };
+    }
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = eval_bar_text(black);
+    }
+}
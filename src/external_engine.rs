@@ -0,0 +1,245 @@
+use crate::game::{GRID_SIZE, GameState, Stone};
+use crate::piskvork::{self, BOARD_SIZE, Command};
+use crate::settings::EngineSettings;
+use bevy::prelude::*;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{self, Child, ChildStdin, Stdio};
+use std::sync::Mutex;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// 关闭引擎时等待其自行退出的时间
+const SHUTDOWN_WAIT: Duration = Duration::from_millis(500);
+
+/// 外部引擎当前所处的阶段
+enum EngineState {
+    Starting { deadline: Instant },
+    Ready,
+    Thinking { started: Instant, deadline: Instant },
+    Failed,
+}
+
+/// 每帧轮询引擎的结果
+pub enum EnginePoll {
+    Pending,
+    Move(usize, usize),
+    Failed(String),
+}
+
+/// 作为子进程运行、通过 Piskvork 协议通信的第三方引擎
+pub struct ExternalEngine {
+    child: Option<Child>, // 关闭时交给后台线程等待退出
+    stdin: ChildStdin,
+    lines: Mutex<Receiver<String>>, // 读取线程转发的引擎输出
+    state: EngineState,
+    settings: EngineSettings,
+    time_used: Duration, // 本局已用时间，用于计算 time_left
+}
+
+impl ExternalEngine {
+    /// 启动引擎进程并发送 START。不等待回复，回复在 poll 中处理。
+    pub fn launch(settings: &EngineSettings) -> Result<Self, String> {
+        let path = settings.path.as_ref().ok_or("engine path is not set")?;
+
+        let mut command = process::Command::new(path);
+        // 很多引擎会在自身目录下读写数据文件
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            command.current_dir(dir);
+        }
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("cannot start engine {}: {}", path.display(), e))?;
+
+        let stdin = child.stdin.take().ok_or("cannot open engine stdin")?;
+        let stdout = child.stdout.take().ok_or("cannot open engine stdout")?;
+
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                match line {
+                    Ok(line) => {
+                        if sender.send(line).is_err() {
+                            break;
+                        }
+                    }
+                    Err(_) => break,
+                }
+            }
+            // 读取线程结束时 sender 被丢弃，接收端据此得知引擎已退出
+        });
+
+        let mut engine = ExternalEngine {
+            child: Some(child),
+            stdin,
+            lines: Mutex::new(receiver),
+            state: EngineState::Starting {
+                deadline: Instant::now() + Duration::from_millis(settings.start_timeout_ms),
+            },
+            settings: settings.clone(),
+            time_used: Duration::ZERO,
+        };
+
+        engine.send(&Command::Start(BOARD_SIZE))?;
+        engine.send(&Command::Info(
+            "timeout_turn".into(),
+            settings.timeout_turn_ms.to_string(),
+        ))?;
+        engine.send(&Command::Info(
+            "timeout_match".into(),
+            settings.timeout_match_ms.to_string(),
+        ))?;
+        Ok(engine)
+    }
+
+    fn send(&mut self, command: &Command) -> Result<(), String> {
+        self.send_line(&command.to_string())?;
+        self.flush()
+    }
+
+    fn send_line(&mut self, line: &str) -> Result<(), String> {
+        writeln!(self.stdin, "{}", line).map_err(|e| format!("cannot write to engine: {}", e))
+    }
+
+    fn flush(&mut self) -> Result<(), String> {
+        self.stdin
+            .flush()
+            .map_err(|e| format!("cannot write to engine: {}", e))
+    }
+
+    /// 引擎已启动且没有在思考
+    pub fn is_ready(&self) -> bool {
+        matches!(self.state, EngineState::Ready)
+    }
+
+    /// 请求引擎为 stone 方在当前局面落子。空棋盘发送 BEGIN，否则用 BOARD 发送完整局面。
+    pub fn request_move(&mut self, game_state: &GameState, stone: Stone) -> Result<(), String> {
+        let stone_count = game_state.board.iter().flatten().flatten().count();
+        // 引擎的第一步 (执黑的空棋盘或执白的第二手) 说明开始了新的一局，整局计时清零
+        if stone_count <= 1 {
+            self.time_used = Duration::ZERO;
+        }
+
+        let mut turn_limit = Duration::from_millis(self.settings.timeout_turn_ms);
+        if self.settings.timeout_match_ms > 0 {
+            let match_limit = Duration::from_millis(self.settings.timeout_match_ms);
+            let time_left = match_limit.saturating_sub(self.time_used);
+            self.send(&Command::Info(
+                "time_left".into(),
+                time_left.as_millis().to_string(),
+            ))?;
+            turn_limit = turn_limit.min(time_left);
+        }
+
+        if stone_count == 0 {
+            self.send(&Command::Begin)?;
+        } else {
+            self.send_line(&Command::Board.to_string())?;
+            for row in 0..=GRID_SIZE {
+                for col in 0..=GRID_SIZE {
+                    if let Some(owner) = game_state.board[row][col] {
+                        let field = if owner == stone { 1 } else { 2 };
+                        let line = format!("{},{}", piskvork::format_coords(row, col), field);
+                        self.send_line(&line)?;
+                    }
+                }
+            }
+            self.send_line("DONE")?;
+            self.flush()?;
+        }
+
+        let started = Instant::now();
+        self.state = EngineState::Thinking {
+            started,
+            deadline: started + turn_limit + Duration::from_millis(self.settings.grace_ms),
+        };
+        Ok(())
+    }
+
+    /// 处理引擎的输出并检查超时，不会阻塞
+    pub fn poll(&mut self) -> EnginePoll {
+        loop {
+            let received = self.lines.lock().unwrap().try_recv();
+            let line = match received {
+                Ok(line) => line,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return self.fail("engine process exited".into()),
+            };
+            let line = line.trim();
+            let keyword = line.split_whitespace().next().unwrap_or("").to_ascii_uppercase();
+
+            match keyword.as_str() {
+                "MESSAGE" => {
+                    println!("[engine] {}", line[keyword.len()..].trim());
+                    continue;
+                }
+                "DEBUG" | "" => continue,
+                "ERROR" | "UNKNOWN" => return self.fail(format!("engine error: {}", line)),
+                _ => {}
+            }
+
+            match self.state {
+                EngineState::Starting { .. } if keyword == "OK" => self.state = EngineState::Ready,
+                EngineState::Thinking { started, .. } => {
+                    if let Some((row, col)) = piskvork::parse_coords(line) {
+                        self.time_used += started.elapsed();
+                        self.state = EngineState::Ready;
+                        return EnginePoll::Move(row, col);
+                    }
+                }
+                _ => {} // 忽略不期望的输出
+            }
+        }
+
+        match self.state {
+            EngineState::Starting { deadline } if Instant::now() >= deadline => {
+                self.fail("engine did not answer START in time".into())
+            }
+            EngineState::Thinking { deadline, .. } if Instant::now() >= deadline => {
+                self.fail("engine ran out of time".into())
+            }
+            EngineState::Failed => EnginePoll::Failed("engine has already failed".into()),
+            _ => EnginePoll::Pending,
+        }
+    }
+
+    fn fail(&mut self, message: String) -> EnginePoll {
+        self.state = EngineState::Failed;
+        if let Some(child) = &mut self.child {
+            let _ = child.kill();
+        }
+        EnginePoll::Failed(message)
+    }
+}
+
+impl Drop for ExternalEngine {
+    /// 发送 END 后在后台线程中等待引擎退出，超时则强制结束，不阻塞界面
+    fn drop(&mut self) {
+        let _ = self.send(&Command::End);
+        let Some(mut child) = self.child.take() else {
+            return;
+        };
+        thread::spawn(move || {
+            let deadline = Instant::now() + SHUTDOWN_WAIT;
+            while Instant::now() < deadline {
+                if let Ok(Some(_)) = child.try_wait() {
+                    return;
+                }
+                thread::sleep(Duration::from_millis(20));
+            }
+            let _ = child.kill();
+            let _ = child.wait();
+        });
+    }
+}
+
+/// 外部引擎对手。引擎在轮到它落子时按需启动。
+#[derive(Resource, Default)]
+pub struct ExternalOpponent {
+    pub engine: Option<ExternalEngine>,
+    pub requested: Option<GameState>, // 正在等待引擎回复的局面
+    pub failure: Option<String>,      // 引擎崩溃或超时的原因，用于结束界面显示
+}
@@ -2,14 +2,19 @@ use bevy::prelude::*;
 
 pub const GRID_SIZE: usize = 14; // 棋盘大小
 pub const CELL_SIZE: f32 = 40.0; // 每个单元格的大小
+pub const HINT_PENALTY: u32 = 5; // 每使用一次提示从评分中扣除的分数
+/// 把搜索分数换算为胜率时的尺度：分数为 SCORE_SCALE 时胜率约 73%。
+/// 由 `gobang-tune --calibrate` 在 240 局自对弈 (2 层对 3 层) 的 6830 个局面上拟合得出
+pub const SCORE_SCALE: f64 = 2750.0;
 
-#[derive(Resource)]
+#[derive(Resource, Debug, Clone)]
 pub struct PlayerScore {
     pub move_count: u32,
     pub current_rating: u32, // 0-100 scale
     pub current_move_score: i32, // 当前步得分
     pub best_move_score: i32,    // 最优步得分
     pub move_scores: Vec<u32>,   // 存储每一步的得分百分比
+    pub hints_used: u32,         // 本局使用提示的次数
 }
 
 impl PlayerScore {
@@ -20,26 +25,42 @@ impl PlayerScore {
             current_move_score: 0,
             best_move_score: 0,
             move_scores: Vec::new(),
+            hints_used: 0,
         }
     }
 
-    /// 更新玩家评分，计算规则：存储每一步的得分百分比，并计算加权平均分作为最终得分（权重为步数）
+    /// 更新玩家评分。current_score 和 best_score 是引擎搜索给出的这步棋和最佳落子的分数。
+    /// 计算规则：按 move_accuracy 计算这步棋的准确度；
+    /// 存储每一步的得分，并计算加权平均分作为最终得分（权重为步数）
     pub fn add_move(&mut self, current_score: i32, best_score: i32) {
         self.move_count += 1;
         self.current_move_score = current_score;
         self.best_move_score = best_score;
         
-        // 计算当前步的得分百分比：当前步得分 / 最优步得分 * 100
-        let score_percentage = if best_score > 0 {
-            ((current_score as f32 / best_score as f32) * 100.0).min(100.0) as u32
-        } else {
-            100 // 如果最优步得分为0，则评分为100（满分）
-        };
+        let score_percentage = move_accuracy(best_score, current_score).round() as u32;
         
         // 存储当前步的得分百分比
         self.move_scores.push(score_percentage);
-        
-        // 计算加权平均分作为最终得分，权重为步数
+        self.update_rating();
+
+        println!(
+            "Move #{}: 当前步得分 = {}, 最优步得分 = {}, 当前步评分 = {}, 加权平均评分 = {}",
+            self.move_count, current_score, best_score, score_percentage, self.current_rating
+        );
+    }
+
+    /// 记录一次提示，每次提示从评分中扣除 HINT_PENALTY 分
+    pub fn use_hint(&mut self) {
+        self.hints_used += 1;
+        self.update_rating();
+        println!(
+            "使用提示 #{}, 加权平均评分 = {}",
+            self.hints_used, self.current_rating
+        );
+    }
+
+    /// 计算加权平均分作为最终得分，权重为步数，再扣除使用提示的分数
+    fn update_rating(&mut self) {
         let mut weighted_sum: u64 = 0;
         let mut weight_sum: u64 = 0;
         
@@ -49,16 +70,12 @@ impl PlayerScore {
             weight_sum += weight*weight;
         }
         
-        self.current_rating = if weight_sum > 0 {
+        let average = if weight_sum > 0 {
             (weighted_sum / weight_sum) as u32
         } else {
             100 // 默认值
         };
-
-        println!(
-            "Move #{}: 当前步得分 = {}, 最优步得分 = {}, 当前步评分 = {}, 加权平均评分 = {}",
-            self.move_count, current_score, best_score, score_percentage, self.current_rating
-        );
+        self.current_rating = average.saturating_sub(self.hints_used * HINT_PENALTY);
     }
 
     /// Resets the score for a new game.
@@ -68,10 +85,34 @@ impl PlayerScore {
         self.current_move_score = 0;
         self.best_move_score = 0;
         self.move_scores.clear();
+        self.hints_used = 0;
         println!("Player score reset.");
     }
 }
 
+impl Default for PlayerScore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 搜索分数对应的胜率估计 (0-1)，连五的分数对应 0 或 1
+pub fn win_probability(score: i32) -> f64 {
+    1.0 / (1.0 + (-(score as f64) / SCORE_SCALE).exp())
+}
+
+/// 走 played_score 的一步而不是 best_score 的最佳落子损失的胜率，单位为百分点 (0-100)
+pub fn win_rate_loss(best_score: i32, played_score: i32) -> f64 {
+    (win_probability(best_score) - win_probability(played_score)).max(0.0) * 100.0
+}
+
+/// 一步棋的准确度 (0-100)：按损失的胜率计算，不损失为 100，损失 5 个百分点约 80，
+/// 损失 25 个百分点约 30 (与 Lichess 的准确度公式相同)
+pub fn move_accuracy(best_score: i32, played_score: i32) -> f64 {
+    let loss = win_rate_loss(best_score, played_score);
+    (103.1668 * (-0.04354 * loss).exp() - 3.1669).clamp(0.0, 100.0)
+}
+
 #[derive(Resource, Clone)]
 pub struct GameState {
     pub board: [[Option<Stone>; GRID_SIZE + 1]; GRID_SIZE + 1], // 棋盘
@@ -96,6 +137,13 @@ impl GameState {
         self.is_game_over = false;
     }
 
+    /// 记录的棋子是否都还在棋盘上。有不在的说明记录之后已经重新开局
+    pub fn has_stones(&self, stones: impl IntoIterator<Item = (usize, usize, Stone)>) -> bool {
+        stones
+            .into_iter()
+            .all(|(row, col, stone)| self.board[row][col] == Some(stone))
+    }
+
     /// 在棋盘上应用一个落子 (in-place 修改)。
     /// 如果位置越界或已被占据，返回 Err。
     pub fn apply_move(&mut self, r: usize, c: usize, stone: Stone) -> Result<(), &'static str> {
@@ -153,6 +201,27 @@ impl GameState {
     }
 }
 
+/// 将 (行, 列) 转换为棋谱记法，例如 (7, 7) -> "h8"。列用字母 a-o，行用数字 1-15。
+pub fn move_to_notation(row: usize, col: usize) -> String {
+    format!("{}{}", (b'a' + col as u8) as char, row + 1)
+}
+
+/// 解析棋谱记法 (如 "h8")，返回 (行, 列)
+pub fn parse_notation(text: &str) -> Option<(usize, usize)> {
+    let text = text.trim();
+    let mut chars = text.chars();
+    let letter = chars.next()?.to_ascii_lowercase();
+    if !letter.is_ascii_lowercase() {
+        return None;
+    }
+    let col = (letter as u8 - b'a') as usize;
+    let row = chars.as_str().parse::<usize>().ok()?.checked_sub(1)?;
+    if row > GRID_SIZE || col > GRID_SIZE {
+        return None;
+    }
+    Some((row, col))
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Stone {
     Black,
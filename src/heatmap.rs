@@ -0,0 +1,379 @@
+use crate::agent::SmartAgent;
+use crate::board::{cell_position, shape_bundle};
+use crate::evaluator::BoardEvaluator;
+use crate::game::{CELL_SIZE, GRID_SIZE, GameState, Stone, win_probability};
+use crate::weights::EvalWeights;
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+
+// 热力图用搜索打分时的搜索深度，保持较浅以便几秒内算完整个棋盘
+const HEATMAP_SEARCH_DEPTH: u32 = 2;
+// 色块在网格线 (z = 0) 之上、棋子 (z = 2) 之下
+const HEATMAP_Z: f32 = 0.5;
+// 图例中色带的格数
+const LEGEND_STEPS: usize = 10;
+
+type Board = [[Option<Stone>; GRID_SIZE + 1]; GRID_SIZE + 1];
+
+/// 热力图的打分方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeatmapSource {
+    Evaluator, // BoardEvaluator::evaluate_position 的棋型分 (只算视角一方的棋型)
+    Search,    // SmartAgent::get_score_for_move 的浅层搜索分数
+}
+
+/// 从哪一方的角度给空位打分
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeatmapView {
+    Mine,     // 玩家自己落在这里的价值
+    Opponent, // 对手 (AI) 落在这里的价值
+}
+
+impl HeatmapSource {
+    fn name(self) -> &'static str {
+        match self {
+            HeatmapSource::Evaluator => "evaluator",
+            HeatmapSource::Search => "search",
+        }
+    }
+}
+
+impl HeatmapView {
+    fn name(self) -> &'static str {
+        match self {
+            HeatmapView::Mine => "my view",
+            HeatmapView::Opponent => "opponent view",
+        }
+    }
+}
+
+/// 一次热力图计算的参数，参数变化后需要重新计算
+#[derive(Clone, Copy, PartialEq)]
+struct HeatmapKey {
+    board: Board,
+    source: HeatmapSource,
+    stone: Stone,
+}
+
+/// 每个空位的分数，已换算为 0-1 的热度
+pub struct HeatmapValues {
+    key: HeatmapKey,
+    cells: Vec<((usize, usize), f32)>,
+}
+
+/// 后台线程中进行的热力图计算
+pub struct HeatmapJob {
+    key: HeatmapKey,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<Option<HeatmapValues>>>,
+}
+
+impl HeatmapJob {
+    fn start(key: HeatmapKey, weights: EvalWeights) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let handle = thread::spawn(move || compute_heatmap(key, &weights, &thread_stop));
+        HeatmapJob {
+            key,
+            stop,
+            handle: Some(handle),
+        }
+    }
+
+    /// 计算完成时取出结果，还在进行中时返回 None
+    fn try_finish(&mut self) -> Option<HeatmapValues> {
+        if !self.handle.as_ref()?.is_finished() {
+            return None;
+        }
+        self.handle.take()?.join().ok().flatten()
+    }
+}
+
+impl Drop for HeatmapJob {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// 学习用的热力图：按分数给每个空的交叉点上色。M 键显示或隐藏，
+/// E 键在棋型分和浅层搜索之间切换，V 键在己方视角和对手视角之间切换。
+/// 每次落子后在后台线程中重新计算，棋盘不会卡顿
+#[derive(Resource)]
+pub struct Heatmap {
+    pub visible: bool,
+    pub source: HeatmapSource,
+    pub view: HeatmapView,
+    job: Option<HeatmapJob>,
+    // 当前画在棋盘上的热力图对应的参数
+    shown: Option<HeatmapKey>,
+}
+
+impl Default for Heatmap {
+    fn default() -> Self {
+        Heatmap {
+            visible: false,
+            source: HeatmapSource::Evaluator,
+            view: HeatmapView::Mine,
+            job: None,
+            shown: None,
+        }
+    }
+}
+
+/// 热力图的色块
+#[derive(Component)]
+pub struct HeatmapCell;
+
+/// 热力图的图例
+#[derive(Component)]
+pub struct HeatmapLegend;
+
+/// 图例中的说明文字
+#[derive(Component)]
+pub struct HeatmapLegendText;
+
+/// 对 key 中局面的每个空位打分，并换算为 0-1 的热度。stop 被置位时提前结束并返回 None
+fn compute_heatmap(
+    key: HeatmapKey,
+    weights: &EvalWeights,
+    stop: &AtomicBool,
+) -> Option<HeatmapValues> {
+    let mut game_state = GameState::new();
+    game_state.board = key.board;
+    game_state.current_turn = key.stone;
+    let mut agent = SmartAgent::new(key.stone, HEATMAP_SEARCH_DEPTH);
+    agent.set_weights(weights.clone());
+
+    let moves = game_state.get_valid_moves();
+    let scores = match key.source {
+        // 进攻加防守的 evaluate_move 与视角无关，这里只算视角一方落在这里的棋型分
+        HeatmapSource::Evaluator => {
+            let mut scores = Vec::new();
+            for (row, col) in moves {
+                if stop.load(Ordering::Relaxed) {
+                    return None;
+                }
+                let score =
+                    BoardEvaluator::evaluate_position(&game_state, row, col, key.stone, weights);
+                scores.push(((row, col), score));
+            }
+            scores
+        }
+        // 各空位的浅层搜索共用一个置换表
+        HeatmapSource::Search => agent.get_scores_for_moves(&game_state, &moves, stop)?,
+    };
+
+    let cells = match key.source {
+        // 搜索分数换算为胜率，不同局面之间颜色的含义一致
+        HeatmapSource::Search => scores
+            .into_iter()
+            .map(|(cell, score)| (cell, win_probability(score) as f32))
+            .collect(),
+        // 棋型分从几十到上百万，按对数缩放到本局面的最低分和最高分之间
+        HeatmapSource::Evaluator => {
+            let min = scores.iter().map(|&(_, s)| s).min().unwrap_or(0);
+            let max = scores.iter().map(|&(_, s)| s).max().unwrap_or(0);
+            let range = ((max - min) as f32).ln_1p().max(f32::EPSILON);
+            scores
+                .into_iter()
+                .map(|(cell, score)| (cell, ((score - min) as f32).ln_1p() / range))
+                .collect()
+        }
+    };
+    Some(HeatmapValues { key, cells })
+}
+
+/// 热度 0-1 对应的颜色：由冷到热为蓝、黄、红
+fn heat_color(heat: f32, alpha: f32) -> Color {
+    let heat = heat.clamp(0.0, 1.0);
+    let (r, g, b) = if heat < 0.5 {
+        let t = heat * 2.0;
+        (0.2 + 0.8 * t, 0.4 + 0.5 * t, 0.9 - 0.7 * t)
+    } else {
+        let t = (heat - 0.5) * 2.0;
+        (1.0, 0.9 - 0.8 * t, 0.2 - 0.1 * t)
+    };
+    Color::rgba(r, g, b, alpha)
+}
+
+/// 系统：M 键显示或隐藏热力图，E 键切换打分方式，V 键切换视角
+pub fn handle_heatmap_keys(keys: Res<Input<KeyCode>>, mut heatmap: ResMut<Heatmap>) {
+    // Ctrl 组合键 (如 Ctrl+E 导出棋谱) 留给其他操作
+    if keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return;
+    }
+    if keys.just_pressed(KeyCode::M) {
+        heatmap.visible = !heatmap.visible;
+    }
+    if keys.just_pressed(KeyCode::E) {
+        heatmap.source = match heatmap.source {
+            HeatmapSource::Evaluator => HeatmapSource::Search,
+            HeatmapSource::Search => HeatmapSource::Evaluator,
+        };
+    }
+    if keys.just_pressed(KeyCode::V) {
+        heatmap.view = match heatmap.view {
+            HeatmapView::Mine => HeatmapView::Opponent,
+            HeatmapView::Opponent => HeatmapView::Mine,
+        };
+    }
+}
+
+/// 系统：局面或热力图设置变化后在后台重新计算，算完后换上新的色块。
+/// 计算期间不显示旧的热力图，以免和当前局面对不上
+pub fn update_heatmap(
+    mut commands: Commands,
+    game_state: Res<GameState>,
+    ai: Res<SmartAgent>,
+    mut heatmap: ResMut<Heatmap>,
+    cell_query: Query<Entity, With<HeatmapCell>>,
+    mut legend_query: Query<&mut Visibility, With<HeatmapLegend>>,
+    mut text_query: Query<&mut Text, With<HeatmapLegendText>>,
+) {
+    let player_stone = ai.get_stone().opponent();
+    let wanted = heatmap.visible.then(|| HeatmapKey {
+        board: game_state.board,
+        source: heatmap.source,
+        stone: match heatmap.view {
+            HeatmapView::Mine => player_stone,
+            HeatmapView::Opponent => player_stone.opponent(),
+        },
+    });
+
+    if heatmap.shown != wanted && heatmap.shown.is_some() {
+        for entity in cell_query.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        heatmap.shown = None;
+    }
+    match wanted {
+        Some(key) if heatmap.shown.is_none() => {
+            if heatmap.job.as_ref().is_none_or(|job| job.key != key) {
+                heatmap.job = Some(HeatmapJob::start(key, ai.weights().clone()));
+            }
+            if let Some(values) = heatmap.job.as_mut().and_then(HeatmapJob::try_finish) {
+                heatmap.job = None;
+                spawn_heatmap_cells(&mut commands, &values);
+                heatmap.shown = Some(values.key);
+            }
+        }
+        Some(_) => {}
+        None => heatmap.job = None,
+    }
+
+    for mut visibility in legend_query.iter_mut() {
+        let target = if heatmap.visible {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+        if *visibility != target {
+            *visibility = target;
+        }
+    }
+    let label = legend_text(&heatmap);
+    for mut text in text_query.iter_mut() {
+        if text.sections[0].value != label {
+            text.sections[0].value = label.clone();
+        }
+    }
+}
+
+fn legend_text(heatmap: &Heatmap) -> String {
+    let status = if heatmap.job.is_some() {
+        " (computing...)"
+    } else {
+        ""
+    };
+    let range = match heatmap.source {
+        HeatmapSource::Evaluator => "low score ... high score",
+        HeatmapSource::Search => "0% ... 100% win chance",
+    };
+    format!(
+        "Heatmap (M): {} (E), {} (V){}\n{}",
+        heatmap.source.name(),
+        heatmap.view.name(),
+        status,
+        range
+    )
+}
+
+fn spawn_heatmap_cells(commands: &mut Commands, values: &HeatmapValues) {
+    for &((row, col), heat) in &values.cells {
+        let position = cell_position(row, col);
+        commands.spawn((
+            shape_bundle(
+                GeometryBuilder::build_as(&shapes::Rectangle {
+                    extents: Vec2::splat(CELL_SIZE * 0.8),
+                    origin: shapes::RectangleOrigin::Center,
+                }),
+                position.extend(HEATMAP_Z),
+            ),
+            Fill::color(heat_color(heat, 0.55)),
+            HeatmapCell,
+        ));
+    }
+}
+
+// 创建热力图的图例 (说明文字和由冷到热的色带)，位于右侧引擎信息面板下方
+pub fn setup_heatmap_legend(mut commands: Commands, heatmap: Res<Heatmap>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(780.0),
+                    top: Val::Px(630.0),
+                    width: Val::Px(400.0),
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                visibility: if heatmap.visible {
+                    Visibility::Visible
+                } else {
+                    Visibility::Hidden
+                },
+                ..default()
+            },
+            HeatmapLegend,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    legend_text(&heatmap),
+                    TextStyle {
+                        font_size: 16.0,
+                        color: Color::rgb(0.2, 0.2, 0.2),
+                        ..default()
+                    },
+                ),
+                HeatmapLegendText,
+            ));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        margin: UiRect::top(Val::Px(4.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|bar| {
+                    for step in 0..LEGEND_STEPS {
+                        let heat = step as f32 / (LEGEND_STEPS - 1) as f32;
+                        bar.spawn(NodeBundle {
+                            style: Style {
+                                width: Val::Px(24.0),
+                                height: Val::Px(12.0),
+                                ..default()
+                            },
+                            background_color: heat_color(heat, 1.0).into(),
+                            ..default()
+                        });
+                    }
+                });
+        });
+}
@@ -0,0 +1,168 @@
+use crate::agent::{SearchInfo, SmartAgent};
+use crate::board::{HintButton, HintButtonText, cell_position, shape_bundle};
+use crate::game::{CELL_SIZE, GRID_SIZE, GameState, PlayerScore, Stone};
+use crate::settings::Settings;
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+// 提示至少使用这个难度等级搜索 (比默认的 AI 更强，每步最多思考 1 秒)
+const HINT_SKILL_LEVEL: u32 = 8;
+// 建议落子之后在棋盘上显示的后续着法数
+const HINT_CONTINUATION: usize = 4;
+
+/// 当前显示的提示
+#[derive(Resource, Default)]
+pub struct Hint {
+    // 提示对应的局面，局面变化 (落子、重新开局) 后提示失效
+    pub board: Option<[[Option<Stone>; GRID_SIZE + 1]; GRID_SIZE + 1]>,
+    pub info: Option<SearchInfo>,
+}
+
+/// 提示在棋盘上画出的半透明棋子和序号
+#[derive(Component)]
+pub struct HintMarker;
+
+/// 为 stone 一方搜索建议落子：使用与 AI 相同的评估权重，难度不低于 HINT_SKILL_LEVEL
+pub fn find_hint(ai: &SmartAgent, game_state: &GameState, stone: Stone) -> Option<SearchInfo> {
+    let level = ai.skill_level().unwrap_or(0).max(HINT_SKILL_LEVEL);
+    let mut agent = SmartAgent::with_skill_level(stone, level);
+    agent.set_weights(ai.weights().clone());
+    agent.search(game_state)
+}
+
+/// 系统：点击提示按钮 (或按 H 键) 时为玩家搜索建议落子并显示在棋盘上。
+/// 每次提示消耗一次本局的提示次数，并计入玩家评分
+#[allow(clippy::too_many_arguments)]
+pub fn handle_hint_button(
+    mut commands: Commands,
+    windows: Query<&Window>,
+    buttons: Res<Input<MouseButton>>,
+    keys: Res<Input<KeyCode>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    hint_button_query: Query<&GlobalTransform, With<HintButton>>,
+    game_state: Res<GameState>,
+    ai: Res<SmartAgent>,
+    settings: Res<Settings>,
+    mut player_score: ResMut<PlayerScore>,
+    mut hint: ResMut<Hint>,
+) {
+    let clicked = buttons.just_pressed(MouseButton::Left)
+        && windows
+            .single()
+            .cursor_position()
+            .zip(camera_query.get_single().ok())
+            .and_then(|(cursor, (camera, camera_transform))| {
+                camera.viewport_to_world_2d(camera_transform, cursor)
+            })
+            .is_some_and(|world_position| {
+                hint_button_query.iter().any(|transform| {
+                    let button_pos = transform.translation();
+                    Rect::new(
+                        button_pos.x - 100.0,
+                        button_pos.y - 30.0,
+                        button_pos.x + 100.0,
+                        button_pos.y + 30.0,
+                    )
+                    .contains(world_position)
+                })
+            });
+    if !clicked && !keys.just_pressed(KeyCode::H) {
+        return;
+    }
+
+    // 只在玩家的回合提示，同一局面不重复计次
+    let player_stone = ai.get_stone().opponent();
+    if game_state.is_game_over
+        || game_state.current_turn != player_stone
+        || hint.board == Some(game_state.board)
+    {
+        return;
+    }
+    if player_score.hints_used >= settings.hints_per_game {
+        println!("本局提示次数已用完");
+        return;
+    }
+
+    let Some(info) = find_hint(&ai, &game_state, player_stone) else {
+        return;
+    };
+    println!("提示: {}", info);
+    spawn_hint_markers(&mut commands, &info, player_stone);
+    player_score.use_hint();
+    hint.board = Some(game_state.board);
+    hint.info = Some(info);
+}
+
+/// 画出建议落子 (半透明棋子) 和之后的预计着法 (较小的半透明棋子，标有序号)
+fn spawn_hint_markers(commands: &mut Commands, info: &SearchInfo, player_stone: Stone) {
+    let mut stone = player_stone;
+    for (index, &(row, col)) in info.pv.iter().take(HINT_CONTINUATION + 1).enumerate() {
+        let position = cell_position(row, col);
+        let (fill, label) = match stone {
+            Stone::Black => (Color::rgba(0.0, 0.0, 0.0, 0.45), Color::WHITE),
+            Stone::White => (Color::rgba(1.0, 1.0, 1.0, 0.6), Color::BLACK),
+        };
+        let radius = if index == 0 { 0.4 } else { 0.3 } * CELL_SIZE;
+
+        commands.spawn((
+            shape_bundle(
+                GeometryBuilder::build_as(&shapes::Circle {
+                    radius,
+                    center: Vec2::ZERO,
+                }),
+                position.extend(3.0),
+            ),
+            Fill::color(fill),
+            Stroke::new(Color::rgb(0.1, 0.6, 0.1), 2.0),
+            HintMarker,
+        ));
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    (index + 1).to_string(),
+                    TextStyle {
+                        font_size: 16.0,
+                        color: label,
+                        ..default()
+                    },
+                )
+                .with_alignment(TextAlignment::Center),
+                transform: Transform::from_xyz(position.x, position.y, 4.0),
+                ..default()
+            },
+            HintMarker,
+        ));
+        stone = stone.opponent();
+    }
+}
+
+/// 系统：局面变化后清除提示
+pub fn clear_stale_hint(
+    mut commands: Commands,
+    game_state: Res<GameState>,
+    mut hint: ResMut<Hint>,
+    marker_query: Query<Entity, With<HintMarker>>,
+) {
+    if hint.board.is_none_or(|board| board == game_state.board) {
+        return;
+    }
+    for entity in marker_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    hint.board = None;
+    hint.info = None;
+}
+
+/// 系统：提示按钮上显示本局剩余的提示次数
+pub fn update_hint_button_text(
+    mut text_query: Query<&mut Text, With<HintButtonText>>,
+    player_score: Res<PlayerScore>,
+    settings: Res<Settings>,
+) {
+    let remaining = settings
+        .hints_per_game
+        .saturating_sub(player_score.hints_used);
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = format!("Hint (H)\n{} left", remaining);
+    }
+}
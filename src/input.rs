@@ -1,7 +1,8 @@
 use crate::agent::SmartAgent;
-use crate::evaluator::BoardEvaluator;
-use crate::game::{CELL_SIZE, GRID_SIZE, GameState, PlayerScore, Stone, StoneComponent};
+use crate::game::{CELL_SIZE, GRID_SIZE, GameState, Stone, StoneComponent};
 use crate::game_manager::check_victory;
+use crate::review::MoveReview;
+use crate::savegame::MoveLog;
 use bevy::prelude::*;
 use bevy_prototype_lyon::prelude::*;
 
@@ -14,7 +15,8 @@ pub fn place_stone(
     mut game_state: ResMut<GameState>,
     camera_query: Query<(&Camera, &GlobalTransform)>,
     ui_interaction_query: Query<&Interaction, (Changed<Interaction>, With<Button>)>,
-    mut player_score: ResMut<PlayerScore>,
+    mut review: ResMut<MoveReview>,
+    mut move_log: ResMut<MoveLog>,
     ai: ResMut<SmartAgent>,
 ) {
     let window = windows.single();
@@ -56,25 +58,9 @@ pub fn place_stone(
                         let player_stone = game_state.current_turn;
 
                         // --- 玩家评分计算 (在应用落子之前) ---
-                        let game_state_before = game_state.clone();
-                        
-                        // 使用新的评分系统
-                        if let Some((best_move, best_score)) = 
-                            BoardEvaluator::find_best_move(&game_state_before, player_stone)
-                        {
-                            // 计算玩家实际落子的得分
-                            let player_move_score = 
-                                BoardEvaluator::evaluate_move(&game_state_before, row, col, player_stone);
-                            
-                            // 更新玩家评分，直接传入当前步得分和最优步得分
-                            player_score.add_move(player_move_score, best_score);
-                            
-                            println!("最佳落子: {:?}, 得分: {}", best_move, best_score);
-                            println!("玩家落子: ({}, {}), 得分: {}", row, col, player_move_score);
-                        } else {
-                            println!("警告: 找不到最佳落子/分数。也许没有可落子的地方了？");
-                            // 如果没有可行的移动，则不更新评分
-                        }
+                        // 用搜索引擎以固定深度为玩家一方评价最佳落子和玩家的落子，
+                        // 在后台进行，完成后更新玩家评分
+                        review.grade(&ai, &game_state, (row, col), player_stone, true);
 
                         println!("row: {}, col: {}", row, col);
                         let stone_x =
@@ -100,6 +86,7 @@ pub fn place_stone(
                             Fill::color(color),
                             StoneComponent,
                         ));
+                        move_log.record(&game_state, row, col, player_stone);
                         game_state.board[row][col] = Some(player_stone); // 使用 player_stone
                         game_state.current_turn = player_stone.opponent(); // 切换回合
                     }
@@ -0,0 +1,34 @@
+pub mod adaptive;
+pub mod analysis;
+pub mod agent;
+pub mod board;
+pub mod config;
+pub mod database;
+pub mod elo;
+pub mod eval_bar;
+pub mod evaluator;
+pub mod external_engine;
+pub mod game;
+pub mod game_manager;
+pub mod heatmap;
+pub mod hint;
+pub mod input;
+pub mod match_runner;
+pub mod overlay;
+pub mod patterns;
+pub mod piskvork;
+pub mod position;
+pub mod profile;
+pub mod psq;
+pub mod renlib;
+pub mod review;
+pub mod rif;
+pub mod savegame;
+pub mod settings;
+pub mod sgf;
+pub mod skill;
+pub mod threats;
+pub mod transposition;
+pub mod tuner;
+pub mod ui;
+pub mod weights;
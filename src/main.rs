@@ -1,37 +1,97 @@
-mod agent;
-mod board;
-mod evaluator;
-mod game;
-mod game_manager;
-mod input;
-mod ui;
-
-use agent::SmartAgent;
 use bevy::prelude::*;
 use bevy_prototype_lyon::prelude::*;
-use board::{ResetButton, ScoreText, SwitchButton, SwitchButtonText, setup_board};
-use game::{CELL_SIZE, GRID_SIZE, GameState, PlayerScore, Stone, StoneComponent};
-use game_manager::check_victory;
-use input::place_stone;
-use ui::{
-    AppState, CloseButton, DifficultyDropdown, DifficultyOption, DifficultySelector,
+use gobang::adaptive::{AdaptiveDifficulty, adapt_difficulty};
+use gobang::agent::{Pondering, SmartAgent};
+use gobang::analysis::{
+    Analysis, handle_analyse_button, handle_analysis_window_buttons, update_analysis,
+};
+use gobang::board::{
+    ResetButton, ScoreText, SwitchButton, SwitchButtonText, setup_board, spawn_stone,
+};
+use gobang::database::{
+    GameBrowser, GameDatabase, finish_database_loading, handle_game_browser, handle_games_button,
+    toggle_position_search, update_position_search,
+};
+use gobang::eval_bar::{setup_eval_bar, update_eval_bar};
+use gobang::external_engine::{EnginePoll, ExternalEngine, ExternalOpponent};
+use gobang::game::{GameState, PlayerScore, Stone, StoneComponent};
+use gobang::game_manager::check_victory;
+use gobang::heatmap::{Heatmap, handle_heatmap_keys, setup_heatmap_legend, update_heatmap};
+use gobang::hint::{Hint, clear_stale_hint, handle_hint_button, update_hint_button_text};
+use gobang::input::place_stone;
+use gobang::overlay::{
+    ThreatOverlay, setup_threat_legend, toggle_threat_overlay, update_threat_overlay,
+};
+use gobang::profile::{
+    PlayerProfile, handle_profile_button, handle_profile_window, record_profile_game,
+};
+use gobang::position::{copy_position, paste_position};
+use gobang::psq::{PsqReplay, export_psq, replay_psq};
+use gobang::renlib::{BookBrowser, OpeningBook, toggle_book_browser, update_book_browser};
+use gobang::review::{MoveReview, record_graded_moves, update_move_badges};
+use gobang::savegame::{MoveLog, load_game, save_game, setup_save_status};
+use gobang::settings::Settings;
+use gobang::sgf::{SgfImport, export_sgf, import_sgf};
+use gobang::weights::EvalWeights;
+use gobang::ui::{
+    AppState, CloseButton, DifficultyDropdown, DifficultyOption, DifficultySelector, EnginePanel,
     PlayAgainButton, StartButton, UsageButton, UsageWindow, VictoryWindow, cleanup_main_menu,
     handle_close_button, handle_difficulty_dropdown, handle_difficulty_options,
     handle_play_again_button, handle_start_button, handle_usage_button,
-    handle_victory_close_button, setup_difficulty_selector, setup_main_menu, show_victory_window,
+    handle_victory_close_button, setup_difficulty_selector, setup_engine_panel, setup_main_menu,
+    show_victory_window, toggle_engine_panel, update_difficulty_label, update_engine_panel,
 }; // 导入UI组件和系统
-
-const BOARD_OFFSET: f32 = -200.0;
-const AI_DIFFICULTY: u32 = 3; // 最高支持=4但有明显卡顿
+use std::sync::Arc;
 
 // 修改导入部分
 // 在 main 函数中添加系统
 fn main() {
+    let settings = Settings::load_or_default();
+    let mut ai = SmartAgent::with_skill_level(Stone::White, settings.skill_level); // 默认AI使用白子
+    ai.set_weights(EvalWeights::load_or_default(&settings.weights_path));
+    if let Some(path) = &settings.book {
+        match OpeningBook::load(path) {
+            Ok(book) => {
+                println!("开局库 {}: {} 个局面", path.display(), book.len());
+                ai.set_book(Some(Arc::new(book)));
+            }
+            Err(e) => eprintln!("读取开局库失败: {}", e),
+        }
+    }
+    let adaptive = AdaptiveDifficulty::new(
+        settings.adaptive,
+        settings.target_win_rate as f64,
+        settings.skill_level,
+    );
+    let profile = PlayerProfile::load_or_new(&settings.profile);
+    let engine_panel = EnginePanel {
+        visible: settings.show_engine_panel,
+        info: None,
+    };
+    let threat_overlay = ThreatOverlay::new(settings.show_threats);
+
     App::new()
         .insert_resource(ClearColor(Color::rgb(0.9, 0.8, 0.6)))
         .insert_resource(GameState::new())
         .insert_resource(PlayerScore::new()) // 添加玩家评分资源
-        .insert_resource(SmartAgent::new(Stone::White, AI_DIFFICULTY)) // 默认AI使用白子
+        .insert_resource(ai)
+        .insert_resource(adaptive) // 自适应难度，未开启时不起作用
+        .insert_resource(profile) // 当前玩家档案，对局结果保存到 profiles 目录
+        .insert_resource(settings)
+        .insert_resource(engine_panel) // 引擎信息面板，F2 切换显示
+        .insert_resource(threat_overlay) // 威胁叠加层，T 切换显示
+        .init_resource::<Pondering>() // 内置 AI 的后台搜索
+        .init_resource::<Hint>() // 当前显示的落子提示
+        .init_resource::<Heatmap>() // 学习用的热力图，M 切换显示
+        .init_resource::<MoveReview>() // 本局双方落子的分类
+        .init_resource::<MoveLog>() // 本局带时间的落子记录，Ctrl+S 存档
+        .init_resource::<SgfImport>() // Ctrl+I 导入的 SGF 棋谱
+        .init_resource::<PsqReplay>() // Ctrl+O 导入回放的 Piskvork 棋谱
+        .init_resource::<BookBrowser>() // 开局库浏览，B 切换显示
+        .init_resource::<GameDatabase>() // 本地对局库，第一次浏览或搜索时在后台读取
+        .init_resource::<GameBrowser>() // 对局库浏览的过滤条件和局面搜索，D 切换
+        .init_resource::<Analysis>() // 对局结束后的赛后分析
+        .init_resource::<ExternalOpponent>() // 设置了外部引擎时由它代替内置AI
         .add_state::<AppState>() // 添加应用状态
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
@@ -56,10 +116,38 @@ fn main() {
             Update,
             handle_close_button.run_if(in_state(AppState::MainMenu)),
         )
+        .add_systems(
+            Update,
+            handle_profile_button.run_if(in_state(AppState::MainMenu)),
+        )
+        .add_systems(
+            Update,
+            handle_profile_window.run_if(in_state(AppState::MainMenu)),
+        )
+        .add_systems(
+            Update,
+            handle_games_button.run_if(in_state(AppState::MainMenu)),
+        )
+        .add_systems(
+            Update,
+            handle_game_browser.run_if(in_state(AppState::MainMenu)),
+        )
+        // 对局库在主菜单 (浏览) 和对局中 (局面搜索) 都可能在后台读取
+        .add_systems(
+            Update,
+            finish_database_loading
+                .after(handle_games_button)
+                .before(update_position_search),
+        )
         .add_systems(OnExit(AppState::MainMenu), cleanup_main_menu)
         // 游戏系统
         .add_systems(OnEnter(AppState::InGame), setup_board)
         .add_systems(OnEnter(AppState::InGame), setup_difficulty_selector) // 添加难度选择器
+        .add_systems(OnEnter(AppState::InGame), setup_engine_panel)
+        .add_systems(OnEnter(AppState::InGame), setup_threat_legend)
+        .add_systems(OnEnter(AppState::InGame), setup_heatmap_legend)
+        .add_systems(OnEnter(AppState::InGame), setup_eval_bar)
+        .add_systems(OnEnter(AppState::InGame), setup_save_status)
         .add_systems(
             Update,
             handle_buttons
@@ -67,6 +155,22 @@ fn main() {
                 .run_if(in_state(AppState::InGame)),
         )
         .add_systems(Update, place_stone.run_if(in_state(AppState::InGame)))
+        .add_systems(
+            Update,
+            handle_hint_button
+                .before(place_stone)
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            Update,
+            clear_stale_hint
+                .after(ai_move)
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            Update,
+            update_hint_button_text.run_if(in_state(AppState::InGame)),
+        )
         .add_systems(
             Update,
             check_victory_system
@@ -79,12 +183,48 @@ fn main() {
                 .after(check_victory_system)
                 .run_if(in_state(AppState::InGame)),
         )
+        .add_systems(
+            Update,
+            adapt_difficulty
+                .after(check_victory_system)
+                .before(ai_move)
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            Update,
+            record_profile_game
+                .after(check_victory_system)
+                .before(adapt_difficulty)
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            Update,
+            external_engine_move
+                .after(check_victory_system)
+                .run_if(in_state(AppState::InGame)),
+        )
         .add_systems(
             Update,
             update_switch_button_text
                 .after(check_victory_system)
                 .run_if(in_state(AppState::InGame)),
         )
+        .add_systems(
+            Update,
+            record_graded_moves
+                .after(place_stone)
+                .after(ai_move)
+                .after(external_engine_move)
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            Update,
+            update_move_badges
+                .after(record_graded_moves)
+                .after(ai_move)
+                .after(external_engine_move)
+                .run_if(in_state(AppState::InGame)),
+        )
         // 游戏系统部分添加胜利窗口相关系统
         .add_systems(
             Update,
@@ -100,6 +240,20 @@ fn main() {
             Update,
             handle_play_again_button.run_if(in_state(AppState::InGame)),
         )
+        .add_systems(
+            Update,
+            handle_analyse_button.run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            Update,
+            update_analysis
+                .after(handle_analyse_button)
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            Update,
+            handle_analysis_window_buttons.run_if(in_state(AppState::InGame)),
+        )
         .add_systems(
             Update,
             handle_difficulty_dropdown.run_if(in_state(AppState::InGame)),
@@ -108,10 +262,153 @@ fn main() {
             Update,
             handle_difficulty_options.run_if(in_state(AppState::InGame)),
         ) // 处理难度选项
+        .add_systems(
+            Update,
+            update_difficulty_label
+                .after(adapt_difficulty)
+                .run_if(in_state(AppState::InGame)),
+        ) // 自适应难度调整等级后刷新难度按钮
         .add_systems(
             Update,
             update_score_text.run_if(in_state(AppState::InGame)),
         ) // 更新得分显示
+        .add_systems(
+            Update,
+            toggle_engine_panel.run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            Update,
+            update_engine_panel
+                .after(ai_move)
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            Update,
+            toggle_threat_overlay.run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            Update,
+            update_threat_overlay
+                .after(toggle_threat_overlay)
+                .after(place_stone)
+                .after(ai_move)
+                .after(external_engine_move)
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            Update,
+            update_eval_bar
+                .after(record_graded_moves)
+                .after(place_stone)
+                .after(ai_move)
+                .after(external_engine_move)
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            Update,
+            handle_heatmap_keys.run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            Update,
+            save_game
+                .after(place_stone)
+                .after(ai_move)
+                .after(external_engine_move)
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            Update,
+            load_game
+                .before(place_stone)
+                .before(check_victory_system)
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            Update,
+            export_sgf
+                .after(place_stone)
+                .after(ai_move)
+                .after(external_engine_move)
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            Update,
+            import_sgf
+                .before(place_stone)
+                .before(check_victory_system)
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            Update,
+            export_psq
+                .after(place_stone)
+                .after(ai_move)
+                .after(external_engine_move)
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            Update,
+            replay_psq
+                .before(place_stone)
+                .before(ai_move)
+                .before(external_engine_move)
+                .before(check_victory_system)
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            Update,
+            copy_position
+                .after(place_stone)
+                .after(ai_move)
+                .after(external_engine_move)
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            Update,
+            paste_position
+                .before(place_stone)
+                .before(ai_move)
+                .before(external_engine_move)
+                .before(check_victory_system)
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            Update,
+            toggle_book_browser.run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            Update,
+            update_book_browser
+                .after(toggle_book_browser)
+                .after(place_stone)
+                .after(ai_move)
+                .after(external_engine_move)
+                .after(replay_psq)
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            Update,
+            toggle_position_search.run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            Update,
+            update_position_search
+                .after(toggle_position_search)
+                .after(place_stone)
+                .after(ai_move)
+                .after(external_engine_move)
+                .after(replay_psq)
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            Update,
+            update_heatmap
+                .after(handle_heatmap_keys)
+                .after(place_stone)
+                .after(ai_move)
+                .after(external_engine_move)
+                .run_if(in_state(AppState::InGame)),
+        )
         .run();
 }
 
@@ -137,46 +434,183 @@ fn check_victory_system(mut game_state: ResMut<GameState>) {
 }
 
 /// 系统：AI落子
-fn ai_move(mut commands: Commands, mut game_state: ResMut<GameState>, ai: Res<SmartAgent>) {
-    if game_state.is_game_over {
+#[allow(clippy::too_many_arguments)]
+fn ai_move(
+    mut commands: Commands,
+    mut game_state: ResMut<GameState>,
+    ai: Res<SmartAgent>,
+    settings: Res<Settings>,
+    mut engine_panel: ResMut<EnginePanel>,
+    mut pondering: ResMut<Pondering>,
+    mut review: ResMut<MoveReview>,
+    mut move_log: ResMut<MoveLog>,
+    replay: Res<PsqReplay>,
+) {
+    // 使用外部引擎时由 external_engine_move 负责落子；回放棋谱时不落子
+    if game_state.is_game_over
+        || settings.engine.path.is_some()
+        || replay.is_replaying(&game_state)
+    {
+        pondering.search = None;
+        pondering.thinking = None;
         return;
     }
 
+    // AI 的搜索参数改变 (换边、调整难度) 或局面被重置后，后台思考的结果不再可用；
+    // 自适应难度只改了失误概率等设置时继续使用
+    if pondering.search.as_ref().is_some_and(|ponder| {
+        (ai.is_changed() && !ponder.matches(&ai)) || ponder.is_stale(&game_state)
+    }) {
+        pondering.search = None;
+    }
+    if pondering
+        .thinking
+        .as_ref()
+        .is_some_and(|thinking| thinking.is_stale(&game_state, &ai))
+    {
+        pondering.thinking = None;
+    }
+
     // sleep(Duration::from_secs(1));
 
     // 只在AI回合且游戏未结束时执行
     if game_state.current_turn == ai.get_stone() {
-        if let Some((row, col)) = ai.make_move(&game_state) {
-            let stone_x = (col as f32 - (GRID_SIZE as f32) / 2.0) * CELL_SIZE + BOARD_OFFSET;
-            let stone_y = (row as f32 - (GRID_SIZE as f32) / 2.0) * CELL_SIZE;
-
-            let color = match ai.get_stone() {
-                Stone::Black => Color::BLACK,
-                Stone::White => Color::WHITE,
-            };
-
-            commands.spawn((
-                ShapeBundle {
-                    path: GeometryBuilder::build_as(&shapes::Circle {
-                        radius: CELL_SIZE * 0.4,
-                        center: Vec2::ZERO,
-                    }),
-                    spatial: SpatialBundle::from_transform(Transform::from_xyz(
-                        stone_x, stone_y, 2.0,
-                    )),
-                    ..default()
-                },
-                Fill::color(color),
-                StoneComponent,
-            ));
+        let info = match pondering.search.take() {
+            Some(ponder) if ponder.is_hit(&game_state) => {
+                match ai.choose_move_after_ponder(&game_state, ponder) {
+                    Ok(info) => {
+                        println!("AI: ponder hit");
+                        info
+                    }
+                    // 猜中了，后台搜索还没结束：不阻塞界面，下一帧再看
+                    Err(ponder) => {
+                        pondering.search = Some(*ponder);
+                        return;
+                    }
+                }
+            }
+            ponder => {
+                if let Some(missed) = ponder {
+                    drop(missed);
+                    println!("AI: ponder miss");
+                }
+                // 正常搜索同样在后台线程中进行，搜索结束前每帧看一次，不阻塞界面
+                match ai.choose_move_in_background(&game_state, pondering.thinking.take()) {
+                    Ok(info) => info,
+                    Err(thinking) => {
+                        pondering.thinking = Some(*thinking);
+                        return;
+                    }
+                }
+            }
+        };
+        if let Some(info) = info {
+            let (row, col) = info.best_move;
+            println!("AI: {}", info);
+            review.grade(&ai, &game_state, (row, col), ai.get_stone(), false);
+            move_log.record(&game_state, row, col, ai.get_stone());
+            spawn_stone(&mut commands, row, col, ai.get_stone());
 
             game_state.board[row][col] = Some(ai.get_stone());
             game_state.current_turn = match ai.get_stone() {
                 Stone::Black => Stone::White,
                 Stone::White => Stone::Black,
             };
+
+            // 趁玩家思考时猜测玩家的应手并提前搜索
+            if settings.ponder && check_victory(&game_state).is_none() {
+                pondering.search = ai.start_ponder(&game_state, &info);
+            }
+            engine_panel.info = Some(info);
+        }
+    }
+}
+
+/// 系统：外部引擎落子。引擎在轮到它时按需启动，崩溃或超时判引擎负。
+#[allow(clippy::too_many_arguments)]
+fn external_engine_move(
+    mut commands: Commands,
+    mut game_state: ResMut<GameState>,
+    ai: Res<SmartAgent>,
+    settings: Res<Settings>,
+    mut opponent: ResMut<ExternalOpponent>,
+    mut review: ResMut<MoveReview>,
+    mut move_log: ResMut<MoveLog>,
+    replay: Res<PsqReplay>,
+) {
+    if game_state.is_game_over
+        || settings.engine.path.is_none()
+        || replay.is_replaying(&game_state)
+    {
+        return;
+    }
+    let engine_stone = ai.get_stone();
+    let ExternalOpponent {
+        engine,
+        requested,
+        failure,
+    } = &mut *opponent;
+
+    if engine.is_none() {
+        match ExternalEngine::launch(&settings.engine) {
+            Ok(launched) => {
+                *engine = Some(launched);
+                *failure = None;
+            }
+            Err(message) => {
+                *failure = Some(message);
+                engine_forfeit(&mut game_state, &mut opponent, engine_stone);
+                return;
+            }
         }
     }
+    let Some(running) = engine.as_mut() else {
+        return;
+    };
+
+    match running.poll() {
+        EnginePoll::Pending => {}
+        EnginePoll::Move(row, col) => {
+            // 等待期间局面已变化 (例如重新开局或换边)，丢弃这步，稍后重新请求
+            let stale = requested.take().map(|state| state.board) != Some(game_state.board);
+            if !stale {
+                if game_state.board[row][col].is_some() {
+                    *failure = Some(format!("engine played an occupied point {},{}", col, row));
+                    engine_forfeit(&mut game_state, &mut opponent, engine_stone);
+                    return;
+                }
+                review.grade(&ai, &game_state, (row, col), engine_stone, false);
+                move_log.record(&game_state, row, col, engine_stone);
+                spawn_stone(&mut commands, row, col, engine_stone);
+                game_state.board[row][col] = Some(engine_stone);
+                game_state.current_turn = engine_stone.opponent();
+            }
+        }
+        EnginePoll::Failed(message) => {
+            *failure = Some(message);
+            engine_forfeit(&mut game_state, &mut opponent, engine_stone);
+            return;
+        }
+    }
+
+    if running.is_ready() && game_state.current_turn == engine_stone {
+        match running.request_move(&game_state, engine_stone) {
+            Ok(()) => *requested = Some(game_state.clone()),
+            Err(message) => {
+                *failure = Some(message);
+                engine_forfeit(&mut game_state, &mut opponent, engine_stone);
+            }
+        }
+    }
+}
+
+/// 外部引擎失败时结束对局，判引擎一方负
+fn engine_forfeit(game_state: &mut GameState, opponent: &mut ExternalOpponent, engine_stone: Stone) {
+    println!("外部引擎失败: {}", opponent.failure.as_deref().unwrap_or(""));
+    opponent.engine = None; // 丢弃时会结束引擎进程
+    opponent.requested = None;
+    game_state.is_game_over = true;
+    game_state.winner = Some(engine_stone.opponent());
 }
 
 /// 系统：更新切换按钮文字
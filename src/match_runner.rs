@@ -0,0 +1,286 @@
+use crate::agent::SmartAgent;
+use crate::elo::{MatchStats, Sprt, SprtVerdict};
+use crate::game::{GameState, Stone, parse_notation};
+use crate::game_manager::check_victory;
+use crate::skill::SkillLevel;
+use crate::tuner::GameRecord;
+use crate::weights::EvalWeights;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+// 没有指定深度时使用的搜索深度
+const DEFAULT_DEPTH: u32 = 3;
+
+/// 内置的开局：天元附近的三手局面 (直指和斜指)，双方大致均势
+pub const DEFAULT_OPENINGS: [&str; 10] = [
+    "h8 h9 i10",
+    "h8 h9 g10",
+    "h8 h9 j10",
+    "h8 h9 i9",
+    "h8 h9 j8",
+    "h8 i9 j8",
+    "h8 i9 h10",
+    "h8 i9 g10",
+    "h8 i9 j7",
+    "h8 i9 i7",
+];
+
+/// 参赛引擎的配置
+#[derive(Debug, Clone)]
+pub struct EngineConfig {
+    pub name: String,
+    pub depth: u32,
+    pub time_limit: Option<Duration>,
+    pub skill_level: Option<u32>, // 设置后按难度等级创建 AI，忽略 depth 和 time_limit
+    pub weights: EvalWeights,
+}
+
+impl EngineConfig {
+    /// 解析形如 `name=new,depth=6,time=200,weights=tuned.toml` 的描述，
+    /// 各项均可省略，time 的单位为毫秒。也可以用 `skill=5` 指定难度等级
+    pub fn from_spec(spec: &str, default_name: &str) -> Result<Self, String> {
+        let mut config = EngineConfig {
+            name: default_name.to_string(),
+            depth: DEFAULT_DEPTH,
+            time_limit: None,
+            skill_level: None,
+            weights: EvalWeights::default(),
+        };
+        for item in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let (key, value) = item
+                .split_once('=')
+                .ok_or_else(|| format!("expected key=value, got {}", item))?;
+            match key.trim() {
+                "name" => config.name = value.trim().to_string(),
+                "depth" => {
+                    config.depth = value
+                        .trim()
+                        .parse()
+                        .map_err(|_| format!("bad depth {}", value))?
+                }
+                "time" => {
+                    let ms: u64 = value
+                        .trim()
+                        .parse()
+                        .map_err(|_| format!("bad time {}", value))?;
+                    config.time_limit = Some(Duration::from_millis(ms));
+                }
+                "skill" => {
+                    config.skill_level = Some(
+                        value
+                            .trim()
+                            .parse()
+                            .map_err(|_| format!("bad skill level {}", value))?,
+                    )
+                }
+                "weights" => {
+                    // 权重文件不存在时 load 会静默使用默认值，这里要求文件必须存在
+                    let path = Path::new(value.trim());
+                    if !path.is_file() {
+                        return Err(format!("weights file {} not found", path.display()));
+                    }
+                    config.weights = EvalWeights::load(path)?;
+                }
+                other => return Err(format!("unknown engine option {}", other)),
+            }
+        }
+        Ok(config)
+    }
+
+    fn agent(&self, stone: Stone) -> SmartAgent {
+        let mut agent = match self.skill_level {
+            Some(level) => SmartAgent::with_skill_level(stone, level),
+            None => {
+                let mut agent = SmartAgent::new(stone, self.depth);
+                agent.set_time_limit(self.time_limit);
+                agent
+            }
+        };
+        agent.set_weights(self.weights.clone());
+        agent
+    }
+
+    /// 同一局面总是走同一步：没有评估扰动、随手失误和时间限制
+    pub fn is_deterministic(&self) -> bool {
+        match self.skill_level {
+            Some(level) => {
+                let skill = SkillLevel::get(level);
+                skill.eval_noise == 0
+                    && skill.blunder_chance == 0.0
+                    && skill.time_limit_ms.is_none()
+            }
+            None => self.time_limit.is_none(),
+        }
+    }
+}
+
+/// 解析开局列表，每行一个开局 (以空白分隔的着法)，`#` 开头的行为注释
+pub fn parse_openings(text: &str) -> Result<Vec<Vec<(usize, usize)>>, String> {
+    let mut openings = Vec::new();
+    for (line_no, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let moves = line
+            .split_whitespace()
+            .map(|token| {
+                parse_notation(token)
+                    .ok_or_else(|| format!("line {}: bad move {}", line_no + 1, token))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        openings.push(moves);
+    }
+    Ok(openings)
+}
+
+pub fn default_openings() -> Vec<Vec<(usize, usize)>> {
+    parse_openings(&DEFAULT_OPENINGS.join("\n")).expect("built-in openings are valid")
+}
+
+/// 从开局局面起让两个引擎对弈一局，直到一方连五或棋盘下满
+pub fn play_game(
+    black: &EngineConfig,
+    white: &EngineConfig,
+    opening: &[(usize, usize)],
+) -> GameRecord {
+    let mut state = GameState::new();
+    let mut moves = Vec::new();
+    let mut stone = Stone::Black;
+    for &(row, col) in opening {
+        if state.apply_move(row, col, stone).is_ok() {
+            moves.push((row, col));
+            stone = stone.opponent();
+        }
+    }
+
+    let agents = [black.agent(Stone::Black), white.agent(Stone::White)];
+    let black_score = loop {
+        if let Some(winner) = check_victory(&state) {
+            break if winner == Stone::Black { 1.0 } else { 0.0 };
+        }
+        state.current_turn = stone;
+        let agent = &agents[if stone == Stone::Black { 0 } else { 1 }];
+        let Some((row, col)) = agent.make_move(&state) else {
+            break 0.5; // 棋盘已满
+        };
+        if state.apply_move(row, col, stone).is_err() {
+            // 非法着法判负
+            break if stone == Stone::Black { 0.0 } else { 1.0 };
+        }
+        moves.push((row, col));
+        stone = stone.opponent();
+    };
+
+    GameRecord { moves, black_score }
+}
+
+/// 一场对抗赛的设置
+pub struct MatchConfig {
+    pub engine_a: EngineConfig,
+    pub engine_b: EngineConfig,
+    pub openings: Vec<Vec<(usize, usize)>>,
+    pub games: usize,
+    pub concurrency: usize,
+    pub sprt: Option<Sprt>,
+}
+
+impl MatchConfig {
+    /// 两个引擎都是确定性的时，每个开局交换先后手的两局之后只会重复同样的对局，
+    /// 重复的对局不是独立的样本。返回这种情况下不重复的对局数
+    pub fn distinct_games(&self) -> Option<usize> {
+        (self.engine_a.is_deterministic() && self.engine_b.is_deterministic())
+            .then_some(self.openings.len() * 2)
+    }
+}
+
+/// 第 index 局使用的开局序号，以及 A 是否执黑：
+/// 每个开局下两局，第 2n 局 A 执黑，第 2n+1 局 B 执黑，开局用完后从头循环
+pub fn schedule(index: usize, openings: usize) -> (usize, bool) {
+    ((index / 2) % openings, index.is_multiple_of(2))
+}
+
+/// 一局结束后的结果
+pub struct GameResult {
+    pub index: usize,
+    pub a_is_black: bool,
+    pub record: GameRecord,
+}
+
+impl GameResult {
+    /// 引擎 A 在这局的得分
+    pub fn a_score(&self) -> f64 {
+        if self.a_is_black {
+            self.record.black_score
+        } else {
+            1.0 - self.record.black_score
+        }
+    }
+}
+
+/// 多线程进行对抗赛，开局和先后手的顺序见 schedule。每完成一局调用一次 on_game。
+/// 设置了 SPRT 时，一旦得出结论就不再开始新的对局。
+/// 两个引擎都是确定性的时最多下 distinct_games 局
+pub fn run_match(
+    config: &MatchConfig,
+    mut on_game: impl FnMut(&GameResult, &MatchStats),
+) -> MatchStats {
+    let mut stats = MatchStats::default();
+    let games = config
+        .distinct_games()
+        .map_or(config.games, |distinct| config.games.min(distinct));
+    if config.openings.is_empty() || games == 0 {
+        return stats;
+    }
+
+    let next_game = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let (sender, receiver) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for _ in 0..config.concurrency.max(1) {
+            let sender = sender.clone();
+            let (next_game, stop) = (&next_game, &stop);
+            scope.spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    let index = next_game.fetch_add(1, Ordering::Relaxed);
+                    if index >= games {
+                        break;
+                    }
+                    let (opening, a_is_black) = schedule(index, config.openings.len());
+                    let opening = &config.openings[opening];
+                    let record = if a_is_black {
+                        play_game(&config.engine_a, &config.engine_b, opening)
+                    } else {
+                        play_game(&config.engine_b, &config.engine_a, opening)
+                    };
+                    let result = GameResult {
+                        index,
+                        a_is_black,
+                        record,
+                    };
+                    if sender.send(result).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
+
+        for result in receiver {
+            stats.add(result.a_score());
+            on_game(&result, &stats);
+            if config
+                .sprt
+                .is_some_and(|sprt| sprt.verdict(&stats) != SprtVerdict::Continue)
+            {
+                stop.store(true, Ordering::Relaxed);
+            }
+        }
+    });
+
+    stats
+}
@@ -0,0 +1,198 @@
+use crate::board::{cell_position, shape_bundle};
+use crate::game::{CELL_SIZE, GRID_SIZE, GameState, Stone};
+use crate::patterns::{Pattern, Threat, find_threats};
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+// 叠加层在棋子 (z = 2) 之上、提示 (z = 3) 之下
+const OVERLAY_Z: f32 = 2.5;
+
+/// 棋盘上的威胁叠加层：标出双方的活三、冲四、活四 (包括跳三、跳四等分开的棋型)，
+/// 以及能完成或封堵它们的空位。按 T 键切换显示
+#[derive(Resource, Default)]
+pub struct ThreatOverlay {
+    pub visible: bool,
+    // 叠加层当前对应的局面，局面变化后重画
+    board: Option<[[Option<Stone>; GRID_SIZE + 1]; GRID_SIZE + 1]>,
+}
+
+impl ThreatOverlay {
+    pub fn new(visible: bool) -> Self {
+        ThreatOverlay {
+            visible,
+            board: None,
+        }
+    }
+}
+
+/// 叠加层画出的线和点
+#[derive(Component)]
+pub struct ThreatMarker;
+
+/// 叠加层的图例
+#[derive(Component)]
+pub struct ThreatLegend;
+
+/// 叠加层显示的棋型：四和活三。眠三、二还不需要立即应对，不显示
+fn is_shown(threat: &Threat) -> bool {
+    matches!(
+        threat.pattern,
+        Pattern::OpenFour | Pattern::Four | Pattern::OpenThree
+    )
+}
+
+/// stone 一方棋型的颜色：黑方为红色，白方为蓝色
+fn threat_color(stone: Stone, alpha: f32) -> Color {
+    match stone {
+        Stone::Black => Color::rgba(0.85, 0.1, 0.1, alpha),
+        Stone::White => Color::rgba(0.1, 0.35, 0.9, alpha),
+    }
+}
+
+/// 系统：按 T 键显示或隐藏威胁叠加层
+pub fn toggle_threat_overlay(keys: Res<Input<KeyCode>>, mut overlay: ResMut<ThreatOverlay>) {
+    if keys.just_pressed(KeyCode::T) {
+        overlay.visible = !overlay.visible;
+    }
+}
+
+/// 系统：叠加层可见时，局面一变化就重新标出双方的威胁；隐藏时清除
+pub fn update_threat_overlay(
+    mut commands: Commands,
+    game_state: Res<GameState>,
+    mut overlay: ResMut<ThreatOverlay>,
+    marker_query: Query<Entity, With<ThreatMarker>>,
+    mut legend_query: Query<&mut Visibility, With<ThreatLegend>>,
+) {
+    let wanted = overlay.visible.then_some(game_state.board);
+    if overlay.board == wanted {
+        return;
+    }
+
+    for entity in marker_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    for mut visibility in legend_query.iter_mut() {
+        *visibility = if overlay.visible {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+    overlay.board = wanted;
+    if overlay.visible {
+        spawn_threat_markers(&mut commands, &game_state);
+    }
+}
+
+/// 叠加层标出的威胁：双方的四和活三，按威胁程度从高到低排列
+pub fn shown_threats(game_state: &GameState) -> Vec<Threat> {
+    find_threats(game_state)
+        .into_iter()
+        .filter(is_shown)
+        .collect()
+}
+
+/// threats 的关键空位 (空位, 哪一方的关键点, 最高的威胁)。同一方多个威胁共用的空位只列一次，
+/// 双方共用的空位各列一次。threats 须按威胁程度从高到低排列
+pub fn key_points(threats: &[Threat]) -> Vec<((usize, usize), Stone, Pattern)> {
+    let mut points: Vec<((usize, usize), Stone, Pattern)> = Vec::new();
+    for threat in threats {
+        for &point in &threat.points {
+            // 先记录的就是最高的
+            if !points
+                .iter()
+                .any(|&(p, stone, _)| p == point && stone == threat.stone)
+            {
+                points.push((point, threat.stone, threat.pattern));
+            }
+        }
+    }
+    points
+}
+
+/// 每个威胁画一条穿过其棋子的线 (四用粗线)，每个关键空位画一个小圆点。
+/// 同一空位是多个威胁的关键点时只画一次，大小取最高的威胁
+fn spawn_threat_markers(commands: &mut Commands, game_state: &GameState) {
+    let threats = shown_threats(game_state);
+    for threat in &threats {
+        let (Some(&first), Some(&last)) = (threat.stones.first(), threat.stones.last()) else {
+            continue;
+        };
+        let width = match threat.pattern {
+            Pattern::OpenThree => 3.0,
+            _ => 6.0,
+        };
+        commands.spawn((
+            shape_bundle(
+                GeometryBuilder::build_as(&shapes::Line(
+                    cell_position(first.0, first.1),
+                    cell_position(last.0, last.1),
+                )),
+                Vec3::new(0.0, 0.0, OVERLAY_Z),
+            ),
+            Stroke::new(threat_color(threat.stone, 0.6), width),
+            ThreatMarker,
+        ));
+    }
+
+    for ((row, col), stone, pattern) in key_points(&threats) {
+        // 双方的关键点重合时错开一点，两个都能看见
+        let shift = match stone {
+            Stone::Black => Vec2::new(-0.12, 0.12),
+            Stone::White => Vec2::new(0.12, -0.12),
+        } * CELL_SIZE;
+        let position = cell_position(row, col) + shift;
+        let radius = match pattern {
+            Pattern::OpenThree => 0.12,
+            _ => 0.18,
+        } * CELL_SIZE;
+        commands.spawn((
+            shape_bundle(
+                GeometryBuilder::build_as(&shapes::Circle {
+                    radius,
+                    center: Vec2::ZERO,
+                }),
+                position.extend(OVERLAY_Z),
+            ),
+            Fill::color(threat_color(stone, 0.8)),
+            ThreatMarker,
+        ));
+    }
+}
+
+// 创建叠加层的图例，位于右侧引擎信息面板下方
+pub fn setup_threat_legend(mut commands: Commands, overlay: Res<ThreatOverlay>) {
+    let style = |color| TextStyle {
+        font_size: 16.0,
+        color,
+        ..default()
+    };
+    commands.spawn((
+        TextBundle {
+            text: Text::from_sections([
+                TextSection::new("Threats (T)  ", style(Color::rgb(0.2, 0.2, 0.2))),
+                TextSection::new("Black  ", style(threat_color(Stone::Black, 1.0))),
+                TextSection::new("White\n", style(threat_color(Stone::White, 1.0))),
+                TextSection::new(
+                    "Thick line: four, thin line: open three\nDots: points that complete or block",
+                    style(Color::rgb(0.2, 0.2, 0.2)),
+                ),
+            ]),
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(780.0),
+                top: Val::Px(720.0),
+                width: Val::Px(400.0),
+                ..default()
+            },
+            visibility: if overlay.visible {
+                Visibility::Visible
+            } else {
+                Visibility::Hidden
+            },
+            ..default()
+        },
+        ThreatLegend,
+    ));
+}
@@ -0,0 +1,377 @@
+use crate::game::{GRID_SIZE, GameState, Stone};
+use crate::weights::Feature;
+use std::sync::OnceLock;
+
+const BOARD_SIZE: usize = GRID_SIZE + 1;
+// 检查方向：水平、垂直、主对角线、副对角线
+const DIRECTIONS: [(isize, isize); 4] = [(0, 1), (1, 0), (1, 1), (1, -1)];
+// 一条线上最多的 5 子窗口数
+const MAX_WINDOWS: usize = BOARD_SIZE - 4;
+
+/// 棋型种类数
+pub const PATTERN_COUNT: usize = 7;
+
+/// 一条线上的棋型 (威胁)，按威胁程度从高到低排列。
+///
+/// 一条线上同一组己方棋子算一个棋型：这组棋子所在的、没有对手棋子的 5 子窗口
+/// 有两个以上时为"活"，只有一个时为"冲"/"眠"。被更大的棋型包含的棋子组不单独计数，
+/// 例如活三中的两子不再算作活二。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pattern {
+    Five = 0,  // 五子连珠
+    OpenFour,  // 活四：两个成五点
+    Four,      // 冲四：一个成五点
+    OpenThree, // 活三：能走成活四
+    Three,     // 眠三：只能走成冲四
+    OpenTwo,   // 活二
+    Two,       // 眠二
+}
+
+impl Pattern {
+    pub const ALL: [Pattern; PATTERN_COUNT] = [
+        Pattern::Five,
+        Pattern::OpenFour,
+        Pattern::Four,
+        Pattern::OpenThree,
+        Pattern::Three,
+        Pattern::OpenTwo,
+        Pattern::Two,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Pattern::Five => "Five",
+            Pattern::OpenFour => "Open four",
+            Pattern::Four => "Four",
+            Pattern::OpenThree => "Open three",
+            Pattern::Three => "Three",
+            Pattern::OpenTwo => "Open two",
+            Pattern::Two => "Two",
+        }
+    }
+
+    /// 文本格式 (如棋型测试集) 中使用的键名
+    pub fn key(self) -> &'static str {
+        match self {
+            Pattern::Five => "five",
+            Pattern::OpenFour => "open_four",
+            Pattern::Four => "four",
+            Pattern::OpenThree => "open_three",
+            Pattern::Three => "three",
+            Pattern::OpenTwo => "open_two",
+            Pattern::Two => "two",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|p| p.key() == key)
+    }
+
+    /// 评估函数中对应的特征，权重取自 EvalWeights
+    pub fn feature(self) -> Feature {
+        match self {
+            Pattern::Five => Feature::Five,
+            Pattern::OpenFour => Feature::OpenFour,
+            Pattern::Four => Feature::HalfFour,
+            Pattern::OpenThree => Feature::OpenThree,
+            Pattern::Three => Feature::HalfThree,
+            Pattern::OpenTwo => Feature::OpenTwo,
+            Pattern::Two => Feature::HalfTwo,
+        }
+    }
+
+    /// 由棋子数和窗口数判断棋型
+    fn classify(stones: u32, windows: u32) -> Option<Self> {
+        let open = windows >= 2;
+        match stones {
+            5.. => Some(Pattern::Five),
+            4 if open => Some(Pattern::OpenFour),
+            4 => Some(Pattern::Four),
+            3 if open => Some(Pattern::OpenThree),
+            3 => Some(Pattern::Three),
+            2 if open => Some(Pattern::OpenTwo),
+            2 => Some(Pattern::Two),
+            _ => None,
+        }
+    }
+}
+
+/// 一方各类棋型的数量，以及这些棋型所在的 5 子窗口总数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PatternCounts {
+    counts: [u32; PATTERN_COUNT],
+    windows: [u32; PATTERN_COUNT],
+}
+
+impl PatternCounts {
+    pub fn get(&self, pattern: Pattern) -> u32 {
+        self.counts[pattern as usize]
+    }
+
+    /// pattern 类棋型所在的 5 子窗口总数。窗口越多，棋型能连成五子的方式越多，
+    /// 例如相邻的活二有四个窗口，隔两格的活二只有两个
+    pub fn windows(&self, pattern: Pattern) -> u32 {
+        self.windows[pattern as usize]
+    }
+
+    pub fn add(&mut self, pattern: Pattern, windows: u32) {
+        self.counts[pattern as usize] += 1;
+        self.windows[pattern as usize] += windows;
+    }
+
+    /// 各类棋型的数量，顺序同 Pattern::ALL
+    pub fn as_array(&self) -> [u32; PATTERN_COUNT] {
+        self.counts
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.iter().all(|&n| n == 0)
+    }
+}
+
+/// 双方的棋型统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PatternReport {
+    pub black: PatternCounts,
+    pub white: PatternCounts,
+}
+
+impl PatternReport {
+    pub fn get(&self, stone: Stone) -> &PatternCounts {
+        match stone {
+            Stone::White => &self.white,
+            Stone::Black => &self.black,
+        }
+    }
+
+    fn get_mut(&mut self, stone: Stone) -> &mut PatternCounts {
+        match stone {
+            Stone::White => &mut self.white,
+            Stone::Black => &mut self.black,
+        }
+    }
+}
+
+/// 棋盘上的一个棋型及其关键点
+#[derive(Debug, Clone, PartialEq)]
+pub struct Threat {
+    pub pattern: Pattern,
+    pub stone: Stone,
+    /// 组成棋型的棋子
+    pub stones: Vec<(usize, usize)>,
+    /// 棋型所在窗口中的空位：己方落在这里使棋型升级 (成五、成四等)，也是对手的防守点
+    pub points: Vec<(usize, usize)>,
+    /// 棋型所在的、没有对手棋子的 5 子窗口数
+    pub windows: u32,
+}
+
+/// 统计整个棋盘上双方的棋型
+pub fn analyse_position(game_state: &GameState) -> PatternReport {
+    let mut report = PatternReport::default();
+    for line in all_lines() {
+        for stone in [Stone::Black, Stone::White] {
+            scan_line(game_state, line, stone, |found| {
+                report.get_mut(stone).add(found.pattern, found.windows)
+            });
+        }
+    }
+    report
+}
+
+/// 统计经过 (row, col) 的棋型：棋子或关键点包含这一格的棋型才计数
+pub fn analyse_cell(game_state: &GameState, row: usize, col: usize) -> PatternReport {
+    let mut report = PatternReport::default();
+    for line in lines_through(row, col) {
+        let bit = 1 << line.index_of(row, col);
+        for stone in [Stone::Black, Stone::White] {
+            scan_line(game_state, &line, stone, |found| {
+                if (found.stones | found.points) & bit != 0 {
+                    report.get_mut(stone).add(found.pattern, found.windows);
+                }
+            });
+        }
+    }
+    report
+}
+
+/// 列出棋盘上双方的所有棋型，按威胁程度从高到低排列
+pub fn find_threats(game_state: &GameState) -> Vec<Threat> {
+    let mut threats = Vec::new();
+    for line in all_lines() {
+        for stone in [Stone::Black, Stone::White] {
+            scan_line(game_state, line, stone, |found| {
+                threats.push(Threat {
+                    pattern: found.pattern,
+                    stone,
+                    stones: line.cells(found.stones),
+                    points: line.cells(found.points),
+                    windows: found.windows,
+                });
+            });
+        }
+    }
+    threats.sort_by_key(|t| t.pattern as usize);
+    threats
+}
+
+/// 棋盘上长度不小于 5 的一条线
+struct Line {
+    start: (isize, isize),
+    direction: (isize, isize),
+    len: usize,
+}
+
+impl Line {
+    /// 从起点沿 direction 延伸到棋盘边缘的线
+    fn from_start(start: (isize, isize), direction: (isize, isize)) -> Self {
+        let mut line = Line {
+            start,
+            direction,
+            len: 0,
+        };
+        while on_board(line.offset(line.len)) {
+            line.len += 1;
+        }
+        line
+    }
+
+    fn offset(&self, i: usize) -> (isize, isize) {
+        (
+            self.start.0 + i as isize * self.direction.0,
+            self.start.1 + i as isize * self.direction.1,
+        )
+    }
+
+    fn cell(&self, i: usize) -> (usize, usize) {
+        let (r, c) = self.offset(i);
+        (r as usize, c as usize)
+    }
+
+    /// 位掩码对应的格子
+    fn cells(&self, mask: u16) -> Vec<(usize, usize)> {
+        (0..self.len)
+            .filter(|&i| mask & (1 << i) != 0)
+            .map(|i| self.cell(i))
+            .collect()
+    }
+
+    /// (row, col) 在线上的序号，调用者保证它在线上
+    fn index_of(&self, row: usize, col: usize) -> usize {
+        if self.direction.0 == 0 {
+            (col as isize - self.start.1) as usize
+        } else {
+            (row as isize - self.start.0) as usize
+        }
+    }
+
+    /// stone 一方和对方的棋子在线上的位掩码
+    fn masks(&self, game_state: &GameState, stone: Stone) -> (u16, u16) {
+        let mut own = 0;
+        let mut opponent = 0;
+        for i in 0..self.len {
+            let (r, c) = self.cell(i);
+            match game_state.board[r][c] {
+                Some(s) if s == stone => own |= 1 << i,
+                Some(_) => opponent |= 1 << i,
+                None => {}
+            }
+        }
+        (own, opponent)
+    }
+}
+
+fn on_board((r, c): (isize, isize)) -> bool {
+    r >= 0 && r < BOARD_SIZE as isize && c >= 0 && c < BOARD_SIZE as isize
+}
+
+/// 棋盘上所有能容纳 5 子窗口的线，只计算一次
+fn all_lines() -> &'static [Line] {
+    static LINES: OnceLock<Vec<Line>> = OnceLock::new();
+    LINES.get_or_init(|| {
+        let mut lines = Vec::new();
+        for direction in DIRECTIONS {
+            for r in 0..BOARD_SIZE as isize {
+                for c in 0..BOARD_SIZE as isize {
+                    // 每条线的起点：反方向上的前一格已经出界
+                    if on_board((r - direction.0, c - direction.1)) {
+                        continue;
+                    }
+                    let line = Line::from_start((r, c), direction);
+                    if line.len >= 5 {
+                        lines.push(line);
+                    }
+                }
+            }
+        }
+        lines
+    })
+}
+
+/// 经过 (row, col) 的四条线中能容纳 5 子窗口的
+fn lines_through(row: usize, col: usize) -> impl Iterator<Item = Line> {
+    DIRECTIONS.into_iter().filter_map(move |direction| {
+        let mut start = (row as isize, col as isize);
+        while on_board((start.0 - direction.0, start.1 - direction.1)) {
+            start = (start.0 - direction.0, start.1 - direction.1);
+        }
+        let line = Line::from_start(start, direction);
+        (line.len >= 5).then_some(line)
+    })
+}
+
+/// 一条线上找到的棋型，棋子和空位用线上的位掩码表示
+struct LineThreat {
+    pattern: Pattern,
+    stones: u16,
+    points: u16,
+    windows: u32,
+}
+
+/// 找出 stone 一方在一条线上的棋型，对每个棋型调用 f
+fn scan_line(game_state: &GameState, line: &Line, stone: Stone, mut f: impl FnMut(LineThreat)) {
+    let (own, opponent) = line.masks(game_state, stone);
+    if own.count_ones() < 2 {
+        return;
+    }
+
+    // 按棋子组合并窗口：(棋子位掩码, 空位位掩码, 窗口数)
+    let mut groups = [(0u16, 0u16, 0u32); MAX_WINDOWS];
+    let mut group_count = 0;
+    for start in 0..=line.len - 5 {
+        let window = 0b11111 << start;
+        let stones = own & window;
+        if opponent & window != 0 || stones.count_ones() < 2 {
+            continue;
+        }
+        let empties = window & !own;
+        match groups[..group_count].iter_mut().find(|g| g.0 == stones) {
+            Some(group) => {
+                group.1 |= empties;
+                group.2 += 1;
+            }
+            None => {
+                groups[group_count] = (stones, empties, 1);
+                group_count += 1;
+            }
+        }
+    }
+
+    let groups = &groups[..group_count];
+    for &(stones, empties, windows) in groups {
+        // 被同一条线上更大的棋子组包含时，算作那个棋型的一部分
+        let contained = groups
+            .iter()
+            .any(|&(other, _, _)| other != stones && other & stones == stones);
+        if contained {
+            continue;
+        }
+        if let Some(pattern) = Pattern::classify(stones.count_ones(), windows) {
+            f(LineThreat {
+                pattern,
+                stones,
+                points: empties,
+                windows,
+            });
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use crate::game::GRID_SIZE;
+use std::fmt;
+use std::str::FromStr;
+
+/// Piskvork 协议的棋盘边长，协议坐标为 "x,y"，即 (列, 行)，从 0 开始
+pub const BOARD_SIZE: usize = GRID_SIZE + 1;
+
+/// 管理器发给引擎的命令
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Start(usize),
+    RectStart(usize, usize),
+    Restart,
+    Begin,
+    Turn(usize, usize), // 对手落子 (行, 列)
+    Board,              // 之后若干行 "x,y,field"，以 DONE 结束
+    Info(String, String),
+    Takeback(usize, usize), // 撤销 (行, 列) 上的棋子
+    End,
+    About,
+    Unknown(String),
+}
+
+/// BOARD 命令中每一行的落子归属
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BoardField {
+    Own,
+    Opponent,
+}
+
+/// 解析 "x,y" 形式的坐标，返回 (行, 列)
+pub fn parse_coords(text: &str) -> Option<(usize, usize)> {
+    let mut parts = text.trim().split(',');
+    let x = parts.next()?.trim().parse::<usize>().ok()?;
+    let y = parts.next()?.trim().parse::<usize>().ok()?;
+    if parts.next().is_some() || x >= BOARD_SIZE || y >= BOARD_SIZE {
+        return None;
+    }
+    Some((y, x))
+}
+
+/// 将 (行, 列) 格式化为协议使用的 "x,y"
+pub fn format_coords(row: usize, col: usize) -> String {
+    format!("{},{}", col, row)
+}
+
+/// 解析 BOARD 命令中的一行 "x,y,field"，返回 (行, 列, 归属)
+pub fn parse_board_line(line: &str) -> Option<(usize, usize, BoardField)> {
+    let (coords, field) = line.trim().rsplit_once(',')?;
+    let (row, col) = parse_coords(coords)?;
+    let field = match field.trim() {
+        "1" => BoardField::Own,
+        "2" => BoardField::Opponent,
+        _ => return None, // 3 表示连续棋局中的棋子，本引擎不支持
+    };
+    Some((row, col, field))
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let line = line.trim();
+        let (keyword, args) = match line.split_once(' ') {
+            Some((keyword, args)) => (keyword, args.trim()),
+            None => (line, ""),
+        };
+
+        let coords = || parse_coords(args).ok_or_else(|| format!("bad coordinates: {}", args));
+
+        match keyword.to_ascii_uppercase().as_str() {
+            "START" => args
+                .parse()
+                .map(Command::Start)
+                .map_err(|_| format!("bad board size: {}", args)),
+            "RECTSTART" => {
+                let (w, h) = args
+                    .split_once(',')
+                    .ok_or_else(|| format!("bad board size: {}", args))?;
+                match (w.trim().parse(), h.trim().parse()) {
+                    (Ok(w), Ok(h)) => Ok(Command::RectStart(w, h)),
+                    _ => Err(format!("bad board size: {}", args)),
+                }
+            }
+            "RESTART" => Ok(Command::Restart),
+            "BEGIN" => Ok(Command::Begin),
+            "TURN" => coords().map(|(r, c)| Command::Turn(r, c)),
+            "BOARD" => Ok(Command::Board),
+            "INFO" => {
+                let (key, value) = args.split_once(' ').unwrap_or((args, ""));
+                Ok(Command::Info(key.to_ascii_lowercase(), value.trim().to_string()))
+            }
+            "TAKEBACK" => coords().map(|(r, c)| Command::Takeback(r, c)),
+            "END" => Ok(Command::End),
+            "ABOUT" => Ok(Command::About),
+            _ => Ok(Command::Unknown(line.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Command::Start(size) => write!(f, "START {}", size),
+            Command::RectStart(w, h) => write!(f, "RECTSTART {},{}", w, h),
+            Command::Restart => write!(f, "RESTART"),
+            Command::Begin => write!(f, "BEGIN"),
+            Command::Turn(r, c) => write!(f, "TURN {}", format_coords(*r, *c)),
+            Command::Board => write!(f, "BOARD"),
+            Command::Info(key, value) => write!(f, "INFO {} {}", key, value),
+            Command::Takeback(r, c) => write!(f, "TAKEBACK {}", format_coords(*r, *c)),
+            Command::End => write!(f, "END"),
+            Command::About => write!(f, "ABOUT"),
+            Command::Unknown(line) => write!(f, "{}", line),
+        }
+    }
+}
@@ -0,0 +1,300 @@
+use crate::adaptive::AdaptiveDifficulty;
+use crate::agent::SmartAgent;
+use crate::game::{GRID_SIZE, GameState, PlayerScore, Stone, StoneComponent};
+use crate::game_manager::check_victory;
+use crate::profile::PlayerProfile;
+use crate::review::MoveReview;
+use crate::savegame::{
+    FREESTYLE_RULE, GameResult, MoveLog, SaveStatusText, SavedGame, TimedMove, ctrl_pressed,
+    restore_game, set_status,
+};
+use arboard::Clipboard;
+use bevy::prelude::*;
+use std::fmt;
+use std::str::FromStr;
+
+const BOARD_SIZE: usize = GRID_SIZE + 1;
+
+/// 用一行文字描述的局面，类似国际象棋的 FEN。
+///
+/// 四个字段以空格分隔：棋盘大小、棋盘、行棋方 (`b` 黑、`w` 白)、规则，如
+/// `15 15/15/15/15/15/15/15/7x7/15/15/15/15/15/15/15 w freestyle`。
+/// 棋盘从第 15 行 (上方) 写到第 1 行，行之间用 `/` 分隔，每行从 a 列写起：
+/// `x` 为黑子，`o` 为白子，数字为连续空位的个数
+#[derive(Debug, Clone, PartialEq)]
+pub struct Position {
+    pub board: [[Option<Stone>; BOARD_SIZE]; BOARD_SIZE],
+    pub side_to_move: Stone,
+    pub rule: String,
+}
+
+impl Position {
+    pub fn from_game(game_state: &GameState) -> Self {
+        Position {
+            board: game_state.board,
+            side_to_move: game_state.current_turn,
+            rule: FREESTYLE_RULE.to_string(),
+        }
+    }
+
+    /// 对应的局面。已有连五时对局结束
+    pub fn to_game_state(&self) -> GameState {
+        let mut game_state = GameState::new();
+        game_state.board = self.board;
+        game_state.current_turn = self.side_to_move;
+        game_state.winner = check_victory(&game_state);
+        game_state.is_game_over = game_state.winner.is_some();
+        game_state
+    }
+
+    /// 走到这个局面的一种着法顺序：黑白交替，各自按从下到上、从左到右的顺序落子
+    pub fn saved_game(&self) -> SavedGame {
+        let stones = |stone: Stone| {
+            (0..BOARD_SIZE)
+                .flat_map(|row| (0..BOARD_SIZE).map(move |col| (row, col)))
+                .filter(move |&(row, col)| self.board[row][col] == Some(stone))
+                .collect::<Vec<_>>()
+        };
+        let (black, white) = (stones(Stone::Black), stones(Stone::White));
+        let mut moves = Vec::new();
+        for index in 0..black.len() {
+            for (points, stone) in [(&black, Stone::Black), (&white, Stone::White)] {
+                if let Some(&(row, col)) = points.get(index) {
+                    moves.push(TimedMove {
+                        row,
+                        col,
+                        stone,
+                        time: 0,
+                    });
+                }
+            }
+        }
+        let result = match check_victory(&self.to_game_state()) {
+            Some(winner) => GameResult::Win(winner),
+            None if moves.len() == BOARD_SIZE * BOARD_SIZE => GameResult::Draw,
+            None => GameResult::Ongoing,
+        };
+        SavedGame::from_moves(moves, result)
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ", BOARD_SIZE)?;
+        for row in (0..BOARD_SIZE).rev() {
+            let mut empty = 0;
+            for col in 0..BOARD_SIZE {
+                let Some(stone) = self.board[row][col] else {
+                    empty += 1;
+                    continue;
+                };
+                if empty > 0 {
+                    write!(f, "{}", empty)?;
+                    empty = 0;
+                }
+                write!(f, "{}", stone_char(stone))?;
+            }
+            if empty > 0 {
+                write!(f, "{}", empty)?;
+            }
+            if row > 0 {
+                write!(f, "/")?;
+            }
+        }
+        let side = match self.side_to_move {
+            Stone::Black => 'b',
+            Stone::White => 'w',
+        };
+        write!(f, " {} {}", side, self.rule)
+    }
+}
+
+impl FromStr for Position {
+    type Err = String;
+
+    /// 读取局面。除了格式错误，棋盘不是 15x15、规则不是 freestyle，
+    /// 或双方棋子数与行棋方不符 (黑先交替落子时黑子与白子一样多或多一个) 时也返回错误
+    fn from_str(text: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = text.split_whitespace().collect();
+        let [size, rows, side, rule] = fields.as_slice() else {
+            return Err(format!(
+                "expected 4 fields (size, board, side to move, rule), got {}",
+                fields.len()
+            ));
+        };
+        if size.parse() != Ok(BOARD_SIZE) {
+            return Err(format!("unsupported board size {}", size));
+        }
+        if *rule != FREESTYLE_RULE {
+            return Err(format!("unsupported rule {}", rule));
+        }
+        let side_to_move = match *side {
+            "b" => Stone::Black,
+            "w" => Stone::White,
+            _ => return Err(format!("side to move must be b or w, got {}", side)),
+        };
+
+        let rows: Vec<&str> = rows.split('/').collect();
+        if rows.len() != BOARD_SIZE {
+            return Err(format!("expected {} rows, got {}", BOARD_SIZE, rows.len()));
+        }
+        let mut board = [[None; BOARD_SIZE]; BOARD_SIZE];
+        for (index, text) in rows.iter().enumerate() {
+            let row = GRID_SIZE - index;
+            let mut col = 0;
+            let mut chars = text.chars().peekable();
+            while let Some(c) = chars.next() {
+                let (stone, count) = match c {
+                    'x' => (Some(Stone::Black), 1),
+                    'o' => (Some(Stone::White), 1),
+                    '0'..='9' => {
+                        let mut count = c.to_digit(10).unwrap_or(0) as usize;
+                        while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+                            count = count * 10 + digit as usize;
+                            chars.next();
+                            // 先检查长度，过长的数字不会溢出
+                            if count > BOARD_SIZE {
+                                break;
+                            }
+                        }
+                        (None, count)
+                    }
+                    _ => return Err(format!("row {}: unexpected character {:?}", row + 1, c)),
+                };
+                if col + count > BOARD_SIZE {
+                    return Err(format!("row {}: more than {} points", row + 1, BOARD_SIZE));
+                }
+                for cell in &mut board[row][col..col + count] {
+                    *cell = stone;
+                }
+                col += count;
+            }
+            if col != BOARD_SIZE {
+                return Err(format!(
+                    "row {}: {} points instead of {}",
+                    row + 1,
+                    col,
+                    BOARD_SIZE
+                ));
+            }
+        }
+
+        let count = |stone: Stone| {
+            board
+                .iter()
+                .flatten()
+                .filter(|&&s| s == Some(stone))
+                .count()
+        };
+        let (black, white) = (count(Stone::Black), count(Stone::White));
+        let expected = if black == white {
+            Stone::Black
+        } else {
+            Stone::White
+        };
+        if black < white || black > white + 1 || side_to_move != expected {
+            return Err(format!(
+                "{} black and {} white stones cannot have {} to move",
+                black,
+                white,
+                match side_to_move {
+                    Stone::Black => "black",
+                    Stone::White => "white",
+                }
+            ));
+        }
+
+        Ok(Position {
+            board,
+            side_to_move,
+            rule: rule.to_string(),
+        })
+    }
+}
+
+fn stone_char(stone: Stone) -> char {
+    match stone {
+        Stone::Black => 'x',
+        Stone::White => 'o',
+    }
+}
+
+// 剪贴板在第一次复制、粘贴时打开。X11 下剪贴板的内容由打开它的程序提供，
+// 因此一直保留到程序退出
+fn clipboard(local: &mut Option<Clipboard>) -> Result<&mut Clipboard, String> {
+    if local.is_none() {
+        *local = Some(Clipboard::new().map_err(|e| e.to_string())?);
+    }
+    local.as_mut().ok_or_else(|| "no clipboard".to_string())
+}
+
+/// 系统：按 Ctrl+C 把当前局面复制到剪贴板
+pub fn copy_position(
+    keys: Res<Input<KeyCode>>,
+    game_state: Res<GameState>,
+    mut local: Local<Option<Clipboard>>,
+    mut status_query: Query<&mut Text, With<SaveStatusText>>,
+) {
+    if !ctrl_pressed(&keys, KeyCode::C) {
+        return;
+    }
+    let position = Position::from_game(&game_state).to_string();
+    let message = match clipboard(&mut local).and_then(|clipboard| {
+        clipboard
+            .set_text(position.clone())
+            .map_err(|e| e.to_string())
+    }) {
+        Ok(()) => format!("Copied position {}", position),
+        Err(e) => format!("Copy failed: {}", e),
+    };
+    set_status(&mut status_query, message);
+}
+
+/// 系统：按 Ctrl+V 在棋盘上摆出剪贴板中的局面
+#[allow(clippy::too_many_arguments)]
+pub fn paste_position(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    mut local: Local<Option<Clipboard>>,
+    ai: Res<SmartAgent>,
+    mut game_state: ResMut<GameState>,
+    mut log: ResMut<MoveLog>,
+    mut player_score: ResMut<PlayerScore>,
+    mut review: ResMut<MoveReview>,
+    mut profile: ResMut<PlayerProfile>,
+    mut adaptive: ResMut<AdaptiveDifficulty>,
+    stone_query: Query<Entity, With<StoneComponent>>,
+    mut status_query: Query<&mut Text, With<SaveStatusText>>,
+) {
+    if !ctrl_pressed(&keys, KeyCode::V) {
+        return;
+    }
+    let parsed = clipboard(&mut local)
+        .and_then(|clipboard| clipboard.get_text().map_err(|e| e.to_string()))
+        .and_then(|text| text.parse::<Position>());
+    let position = match parsed {
+        Ok(position) => position,
+        Err(e) => {
+            set_status(&mut status_query, format!("Paste failed: {}", e));
+            return;
+        }
+    };
+
+    let saved = position.saved_game();
+    restore_game(
+        &mut commands,
+        &saved,
+        &ai,
+        &mut game_state,
+        &mut log,
+        &mut player_score,
+        &mut review,
+        &mut profile,
+        &mut adaptive,
+        &stone_query,
+    );
+    set_status(
+        &mut status_query,
+        format!("Pasted position with {} stones", saved.moves.len()),
+    );
+}
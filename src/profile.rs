@@ -0,0 +1,623 @@
+use crate::agent::SmartAgent;
+use crate::config::{ConfigFile, ConfigWriter};
+use crate::elo::{Glicko2, MatchStats};
+use crate::game::{GameState, PlayerScore};
+use crate::review::MoveReview;
+use crate::settings::Settings;
+use crate::skill::{DEFAULT_SKILL_LEVEL, MAX_SKILL_LEVEL, MIN_SKILL_LEVEL, SkillLevel};
+use crate::ui::ProfileButton;
+use bevy::prelude::*;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 玩家档案所在的目录 (程序工作目录下)，每个档案一个 `<名字>.toml`
+pub const PROFILE_DIR: &str = "profiles";
+/// 未在设置文件中指定档案时使用的档案名
+pub const DEFAULT_PROFILE: &str = "Player";
+// AI 等级分的评分偏差：等级分是固定的名义值，按较可靠的对手计算
+const AI_DEVIATION: f64 = 50.0;
+// 档案窗口中最多列出的其他档案数
+const PROFILES_SHOWN: usize = 6;
+// 档案名的最大长度
+const MAX_NAME_LEN: usize = 20;
+// 档案窗口中评分曲线的尺寸
+const CHART_WIDTH: f32 = 460.0;
+const CHART_HEIGHT: f32 = 100.0;
+
+/// 档案中记录的一局
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameRecord {
+    pub time: u64,     // 结束时间 (Unix 时间戳，秒)
+    pub level: u32,    // AI 的难度等级
+    pub result: f64,   // 玩家得分：胜 1，和 0.5，负 0
+    pub accuracy: u32, // 本局的玩家评分 (0-100)
+    pub rating: f64,   // 这局之后的总评分
+}
+
+/// 本地玩家档案：总评分和对各 AI 等级的评分、胜负统计以及每局的记录
+#[derive(Resource, Debug, Clone)]
+pub struct PlayerProfile {
+    pub name: String,
+    pub rating: Glicko2,                       // 根据所有对局计算的总评分
+    pub level_ratings: BTreeMap<u32, Glicko2>, // 只根据与该等级的对局计算的评分
+    pub stats: MatchStats,                     // 玩家视角的胜/和/负
+    pub games: Vec<GameRecord>,
+    game_recorded: bool, // 本局的结果是否已经记录
+}
+
+impl PlayerProfile {
+    pub fn new(name: &str) -> Self {
+        PlayerProfile {
+            name: name.to_string(),
+            rating: Glicko2::default(),
+            level_ratings: BTreeMap::new(),
+            stats: MatchStats::default(),
+            games: Vec::new(),
+            game_recorded: false,
+        }
+    }
+
+    /// 档案文件的位置。名字中只保留字母、数字、'-' 和 '_'，其余字符换成 '_'
+    pub fn path(name: &str) -> PathBuf {
+        let file_name: String = name
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || c == '-' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        Path::new(PROFILE_DIR).join(format!("{}.toml", file_name))
+    }
+
+    /// 已保存的档案名，按字母顺序
+    pub fn list() -> Vec<String> {
+        let Ok(entries) = fs::read_dir(PROFILE_DIR) else {
+            return Vec::new();
+        };
+        let mut names: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+            .filter_map(|path| Self::load(&path).ok().map(|profile| profile.name))
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// 读取档案文件，缺失的项使用默认值
+    pub fn load(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Err(format!("{}: profile not found", path.display()));
+        }
+        let config = ConfigFile::load(path)?;
+        let name = config
+            .get_str("profile.name")
+            .map(str::to_string)
+            .or_else(|| path.file_stem().map(|s| s.to_string_lossy().into_owned()))
+            .unwrap_or_else(|| DEFAULT_PROFILE.to_string());
+        let mut profile = PlayerProfile::new(&name);
+        profile.rating = read_rating(&config, "profile");
+        profile.stats = MatchStats {
+            wins: config.get_u64("profile.wins").unwrap_or(0) as u32,
+            draws: config.get_u64("profile.draws").unwrap_or(0) as u32,
+            losses: config.get_u64("profile.losses").unwrap_or(0) as u32,
+        };
+        for level in MIN_SKILL_LEVEL..=MAX_SKILL_LEVEL {
+            let section = format!("level.{}", level);
+            if config.get_str(&format!("{}.rating", section)).is_some() {
+                profile
+                    .level_ratings
+                    .insert(level, read_rating(&config, &section));
+            }
+        }
+        let game_count = config.get_u64("profile.games").unwrap_or(0);
+        for index in 1..=game_count {
+            let key = |field: &str| format!("game.{}.{}", index, field);
+            profile.games.push(GameRecord {
+                time: config.get_u64(&key("time")).unwrap_or(0),
+                level: config
+                    .get_u64(&key("level"))
+                    .map_or(DEFAULT_SKILL_LEVEL, |level| level as u32),
+                result: config.get_f64(&key("result")).unwrap_or(0.0),
+                accuracy: config.get_u64(&key("accuracy")).unwrap_or(0) as u32,
+                rating: config
+                    .get_f64(&key("rating"))
+                    .unwrap_or(profile.rating.rating),
+            });
+        }
+        Ok(profile)
+    }
+
+    /// 读取名为 name 的档案，不存在或读取失败时新建一个 (失败时打印原因)
+    pub fn load_or_new(name: &str) -> Self {
+        let path = Self::path(name);
+        if !path.exists() {
+            return PlayerProfile::new(name);
+        }
+        Self::load(&path).unwrap_or_else(|e| {
+            eprintln!("读取玩家档案失败，使用新档案: {}", e);
+            PlayerProfile::new(name)
+        })
+    }
+
+    pub fn to_toml(&self) -> String {
+        let mut writer = ConfigWriter::default();
+        writer
+            .section("profile")
+            .value("name", format!("{:?}", self.name))
+            .value("wins", self.stats.wins)
+            .value("draws", self.stats.draws)
+            .value("losses", self.stats.losses)
+            .value("games", self.games.len());
+        write_rating(&mut writer, &self.rating);
+        for (level, rating) in &self.level_ratings {
+            writer.section(&format!("level.{}", level));
+            write_rating(&mut writer, rating);
+        }
+        for (index, game) in self.games.iter().enumerate() {
+            writer
+                .section(&format!("game.{}", index + 1))
+                .value("time", game.time)
+                .value("level", game.level)
+                .value("result", game.result)
+                .value("accuracy", game.accuracy)
+                .value("rating", format!("{:.1}", game.rating));
+        }
+        writer.finish().to_string()
+    }
+
+    /// 保存到 PROFILE_DIR 下的档案文件
+    pub fn save(&self) -> Result<(), String> {
+        fs::create_dir_all(PROFILE_DIR).map_err(|e| format!("{}: {}", PROFILE_DIR, e))?;
+        let path = Self::path(&self.name);
+        fs::write(&path, self.to_toml()).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// 对 level 级 AI 的评分，还没有对局时为初始评分
+    pub fn level_rating(&self, level: u32) -> Glicko2 {
+        self.level_ratings.get(&level).copied().unwrap_or_default()
+    }
+
+    /// 记录一局：更新总评分、对该等级的评分和胜负统计
+    pub fn record_game(&mut self, level: u32, result: f64, accuracy: u32, time: u64) {
+        let opponent = Glicko2 {
+            rating: SkillLevel::get(level).nominal_rating(),
+            deviation: AI_DEVIATION,
+            ..Glicko2::default()
+        };
+        self.rating = self.rating.update(&opponent, result);
+        let level_rating = self.level_rating(level).update(&opponent, result);
+        self.level_ratings.insert(level, level_rating);
+        self.stats.add(result);
+        self.games.push(GameRecord {
+            time,
+            level,
+            result,
+            accuracy,
+            rating: self.rating.rating,
+        });
+    }
+
+    /// 当前已经结束的对局不计入档案 (例如读取的已结束存档)
+    pub fn skip_game(&mut self) {
+        self.game_recorded = true;
+    }
+
+    /// 各局玩家评分的平均值，没有对局时为 None
+    pub fn average_accuracy(&self) -> Option<f64> {
+        if self.games.is_empty() {
+            return None;
+        }
+        Some(self.games.iter().map(|g| g.accuracy as f64).sum::<f64>() / self.games.len() as f64)
+    }
+
+    /// 与 level 级 AI 下过的局数
+    pub fn games_against(&self, level: u32) -> usize {
+        self.games.iter().filter(|g| g.level == level).count()
+    }
+}
+
+fn read_rating(config: &ConfigFile, section: &str) -> Glicko2 {
+    let defaults = Glicko2::default();
+    let get = |field: &str, default: f64| {
+        config
+            .get_f64(&format!("{}.{}", section, field))
+            .unwrap_or(default)
+    };
+    Glicko2 {
+        rating: get("rating", defaults.rating),
+        deviation: get("deviation", defaults.deviation),
+        volatility: get("volatility", defaults.volatility),
+    }
+}
+
+fn write_rating(writer: &mut ConfigWriter, rating: &Glicko2) {
+    writer
+        .value("rating", format!("{:.1}", rating.rating))
+        .value("deviation", format!("{:.1}", rating.deviation))
+        .value("volatility", format!("{:.6}", rating.volatility));
+}
+
+/// 系统：对局结束后把结果记入当前档案并保存。使用外部引擎或手动设置了搜索深度时
+/// 没有对应的 AI 等级，不记录。
+/// 等玩家的落子都评价完，评分才是最终的
+pub fn record_profile_game(
+    game_state: Res<GameState>,
+    player_score: Res<PlayerScore>,
+    review: Res<MoveReview>,
+    ai: Res<SmartAgent>,
+    settings: Res<Settings>,
+    mut profile: ResMut<PlayerProfile>,
+) {
+    if !game_state.is_game_over {
+        if profile.game_recorded {
+            profile.game_recorded = false;
+        }
+        return;
+    }
+    if profile.game_recorded || settings.engine.path.is_some() || review.is_grading() {
+        return;
+    }
+    profile.game_recorded = true;
+
+    let player_stone = ai.get_stone().opponent();
+    let result = match game_state.winner {
+        Some(winner) if winner == player_stone => 1.0,
+        Some(_) => 0.0,
+        None => 0.5,
+    };
+    let Some(level) = ai.skill_level() else {
+        println!("AI 没有使用难度等级，本局不计入档案 {}", profile.name);
+        return;
+    };
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    profile.record_game(level, result, player_score.current_rating, time);
+    println!(
+        "档案 {}: 评分 {:.0} +/- {:.0}",
+        profile.name,
+        profile.rating.rating,
+        profile.rating.margin()
+    );
+    if let Err(e) = profile.save() {
+        eprintln!("保存玩家档案失败: {}", e);
+    }
+}
+
+/// 主菜单中的档案窗口
+#[derive(Component)]
+pub struct ProfileWindow;
+
+/// 档案窗口中的按钮
+#[derive(Component, Clone, PartialEq)]
+pub enum ProfileWindowButton {
+    Switch(String), // 切换到已有的档案
+    Create,         // 用输入的名字新建档案
+    Close,
+}
+
+/// 档案窗口中新档案名的输入框文字
+#[derive(Component)]
+pub struct ProfileNameInput;
+
+/// 系统：点击主菜单的 "Profile" 按钮打开档案窗口
+pub fn handle_profile_button(
+    mut commands: Commands,
+    button_query: Query<&Interaction, (Changed<Interaction>, With<ProfileButton>)>,
+    profile: Res<PlayerProfile>,
+    window_query: Query<Entity, With<ProfileWindow>>,
+) {
+    if button_query.iter().any(|i| *i == Interaction::Pressed) && window_query.is_empty() {
+        spawn_profile_window(&mut commands, &profile);
+    }
+}
+
+/// 系统：档案窗口中的切换、新建和关闭按钮，以及输入新档案名 (回车新建)
+pub fn handle_profile_window(
+    mut commands: Commands,
+    button_query: Query<(&Interaction, &ProfileWindowButton), Changed<Interaction>>,
+    mut characters: EventReader<ReceivedCharacter>,
+    keys: Res<Input<KeyCode>>,
+    mut input_query: Query<&mut Text, With<ProfileNameInput>>,
+    mut profile: ResMut<PlayerProfile>,
+    window_query: Query<Entity, With<ProfileWindow>>,
+) {
+    if window_query.is_empty() {
+        characters.clear();
+        return;
+    }
+
+    let mut action = button_query
+        .iter()
+        .find(|(interaction, _)| **interaction == Interaction::Pressed)
+        .map(|(_, button)| button.clone());
+
+    for mut text in input_query.iter_mut() {
+        let name = &mut text.sections[0].value;
+        for event in characters.read() {
+            if (event.char.is_alphanumeric() || event.char == '-' || event.char == '_')
+                && name.chars().count() < MAX_NAME_LEN
+            {
+                name.push(event.char);
+            }
+        }
+        if keys.just_pressed(KeyCode::Back) {
+            name.pop();
+        }
+        if keys.just_pressed(KeyCode::Return) {
+            action = Some(ProfileWindowButton::Create);
+        }
+    }
+
+    let Some(action) = action else {
+        return;
+    };
+    let name = match action {
+        ProfileWindowButton::Switch(name) => name,
+        ProfileWindowButton::Create => {
+            let name = input_query
+                .iter()
+                .next()
+                .map(|text| text.sections[0].value.clone())
+                .unwrap_or_default();
+            if name.is_empty() {
+                return;
+            }
+            name
+        }
+        ProfileWindowButton::Close => {
+            for entity in window_query.iter() {
+                commands.entity(entity).despawn_recursive();
+            }
+            return;
+        }
+    };
+
+    *profile = PlayerProfile::load_or_new(&name);
+    if let Err(e) = profile.save() {
+        eprintln!("保存玩家档案失败: {}", e);
+    }
+    println!("当前玩家档案: {}", profile.name);
+    for entity in window_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    spawn_profile_window(&mut commands, &profile);
+}
+
+/// 生成档案窗口：评分、胜负统计、对各等级的评分、评分曲线，以及切换和新建档案
+fn spawn_profile_window(commands: &mut Commands, profile: &PlayerProfile) {
+    let text_style = |font_size: f32| TextStyle {
+        font_size,
+        color: Color::rgb(0.2, 0.2, 0.2),
+        ..default()
+    };
+
+    let mut summary = format!(
+        "Rating {:.0} +/- {:.0}\nGames {}: {} won, {} drawn, {} lost",
+        profile.rating.rating,
+        profile.rating.margin(),
+        profile.stats.games(),
+        profile.stats.wins,
+        profile.stats.draws,
+        profile.stats.losses
+    );
+    if let Some(accuracy) = profile.average_accuracy() {
+        summary.push_str(&format!("\nAverage accuracy {:.0}", accuracy));
+    }
+    let levels = if profile.level_ratings.is_empty() {
+        "No rated games yet".to_string()
+    } else {
+        profile
+            .level_ratings
+            .iter()
+            .map(|(&level, rating)| {
+                format!(
+                    "Level {} {}: {:.0} +/- {:.0} ({} games)",
+                    level,
+                    SkillLevel::get(level).name,
+                    rating.rating,
+                    rating.margin(),
+                    profile.games_against(level)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    let others: Vec<String> = PlayerProfile::list()
+        .into_iter()
+        .filter(|name| *name != profile.name)
+        .take(PROFILES_SHOWN)
+        .collect();
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(20.0),
+                    top: Val::Px(20.0),
+                    width: Val::Px(CHART_WIDTH + 40.0),
+                    height: Val::Px(760.0),
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(20.0)),
+                    ..default()
+                },
+                background_color: Color::rgb(0.9, 0.9, 0.9).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            ProfileWindow,
+        ))
+        .with_children(|parent| {
+            let margin = Style {
+                margin: UiRect::bottom(Val::Px(10.0)),
+                ..default()
+            };
+            parent.spawn(
+                TextBundle::from_section(format!("Profile: {}", profile.name), text_style(28.0))
+                    .with_style(margin.clone()),
+            );
+            parent.spawn(
+                TextBundle::from_section(summary, text_style(18.0)).with_style(margin.clone()),
+            );
+
+            // 评分曲线：每局之后的总评分，纵轴按最低和最高评分缩放
+            parent.spawn(
+                TextBundle::from_section("Rating history", text_style(18.0))
+                    .with_style(margin.clone()),
+            );
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(CHART_WIDTH),
+                        height: Val::Px(CHART_HEIGHT),
+                        margin: UiRect::bottom(Val::Px(10.0)),
+                        ..default()
+                    },
+                    background_color: Color::rgb(0.75, 0.75, 0.75).into(),
+                    ..default()
+                })
+                .with_children(|chart| {
+                    let low = profile
+                        .games
+                        .iter()
+                        .map(|g| g.rating)
+                        .fold(f64::MAX, f64::min);
+                    let high = profile
+                        .games
+                        .iter()
+                        .map(|g| g.rating)
+                        .fold(f64::MIN, f64::max);
+                    let span = (high - low).max(1.0);
+                    let step = CHART_WIDTH / profile.games.len().max(1) as f32;
+                    for (i, game) in profile.games.iter().enumerate() {
+                        let height = ((game.rating - low) / span) as f32 * (CHART_HEIGHT - 4.0);
+                        let color = if game.result > 0.75 {
+                            Color::rgb(0.1, 0.5, 0.2)
+                        } else if game.result < 0.25 {
+                            Color::rgb(0.7, 0.15, 0.1)
+                        } else {
+                            Color::rgb(0.4, 0.4, 0.4)
+                        };
+                        chart.spawn(NodeBundle {
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                left: Val::Px(i as f32 * step),
+                                top: Val::Px(CHART_HEIGHT - 4.0 - height),
+                                width: Val::Px((step - 1.0).clamp(2.0, 12.0)),
+                                height: Val::Px(4.0),
+                                ..default()
+                            },
+                            background_color: color.into(),
+                            ..default()
+                        });
+                    }
+                });
+
+            parent.spawn(
+                TextBundle::from_section(levels, text_style(16.0)).with_style(Style {
+                    margin: UiRect::bottom(Val::Px(10.0)),
+                    flex_grow: 1.0,
+                    ..default()
+                }),
+            );
+
+            parent.spawn(
+                TextBundle::from_section("Switch profile", text_style(18.0))
+                    .with_style(margin.clone()),
+            );
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        flex_wrap: FlexWrap::Wrap,
+                        column_gap: Val::Px(10.0),
+                        row_gap: Val::Px(10.0),
+                        margin: UiRect::bottom(Val::Px(10.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|row| {
+                    for name in others {
+                        spawn_window_button(row, &name.clone(), ProfileWindowButton::Switch(name));
+                    }
+                });
+
+            parent.spawn(
+                TextBundle::from_section("New profile name (type, then Enter):", text_style(16.0))
+                    .with_style(Style {
+                        margin: UiRect::bottom(Val::Px(4.0)),
+                        ..default()
+                    }),
+            );
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(CHART_WIDTH),
+                        height: Val::Px(30.0),
+                        padding: UiRect::horizontal(Val::Px(6.0)),
+                        align_items: AlignItems::Center,
+                        margin: UiRect::bottom(Val::Px(10.0)),
+                        ..default()
+                    },
+                    background_color: Color::WHITE.into(),
+                    ..default()
+                })
+                .with_children(|field| {
+                    field.spawn((
+                        TextBundle::from_section("", text_style(18.0)),
+                        ProfileNameInput,
+                    ));
+                });
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        column_gap: Val::Px(20.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|row| {
+                    spawn_window_button(row, "Create", ProfileWindowButton::Create);
+                    spawn_window_button(row, "Close", ProfileWindowButton::Close);
+                });
+        });
+}
+
+fn spawn_window_button(parent: &mut ChildBuilder, label: &str, button: ProfileWindowButton) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    min_width: Val::Px(100.0),
+                    height: Val::Px(36.0),
+                    padding: UiRect::horizontal(Val::Px(10.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::rgb(0.15, 0.15, 0.15).into(),
+                ..default()
+            },
+            button,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                label,
+                TextStyle {
+                    font_size: 18.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+        });
+}
@@ -0,0 +1,289 @@
+use crate::adaptive::AdaptiveDifficulty;
+use crate::agent::SmartAgent;
+use crate::game::{GRID_SIZE, GameState, PlayerScore, Stone, StoneComponent};
+use crate::profile::PlayerProfile;
+use crate::review::MoveReview;
+use crate::savegame::{
+    GameResult, MoveLog, SaveStatusText, SavedGame, TimedMove, ctrl_pressed, restore_game,
+    set_status,
+};
+use crate::settings::Settings;
+use crate::sgf::player_name;
+use bevy::prelude::*;
+use std::fs;
+use std::path::Path;
+
+/// 导入、导出 Piskvork 棋谱的默认文件 (程序工作目录下)
+pub const PSQ_FILE: &str = "gobang.psq";
+const BOARD_SIZE: usize = GRID_SIZE + 1;
+
+/// 一局 Piskvork (.psq) 棋谱。
+///
+/// 文件第一行为 `Piskvorky 15x15, 11:11, 0`，之后每行一步 `x,y,毫秒`：
+/// x、y 从 1 开始，原点在左上角，毫秒为这一步的思考时间。
+/// 着法之后是双方 (通常是引擎文件名) 等其他信息，读取时只取前两行作为双方名字
+#[derive(Debug, Clone, PartialEq)]
+pub struct PsqGame {
+    pub black: String,
+    pub white: String,
+    /// 黑先交替的着法。导出时 time 为落子时间 (Unix 时间戳，毫秒)；
+    /// 导入的棋谱没有落子时刻，time 为从开局起累计的思考时间
+    pub moves: Vec<TimedMove>,
+}
+
+impl PsqGame {
+    /// 从当前对局生成棋谱，每步的思考时间取与上一步落子时间之差 (第一步记为 0)
+    pub fn from_game(game_state: &GameState, log: &MoveLog, black: &str, white: &str) -> Self {
+        let moves = if log.is_stale(game_state) {
+            Vec::new()
+        } else {
+            log.moves.clone()
+        };
+        PsqGame {
+            black: black.to_string(),
+            white: white.to_string(),
+            moves,
+        }
+    }
+
+    pub fn to_psq(&self) -> String {
+        let mut text = format!("Piskvorky {}x{}, 11:11, 0\n", BOARD_SIZE, BOARD_SIZE);
+        let mut previous = self.moves.first().map_or(0, |m| m.time);
+        for m in &self.moves {
+            let (x, y) = psq_point(m.row, m.col);
+            text.push_str(&format!(
+                "{},{},{}\n",
+                x,
+                y,
+                m.time.saturating_sub(previous)
+            ));
+            previous = m.time;
+        }
+        for name in [&self.black, &self.white] {
+            text.push_str(&format!("{}\n", name.replace(['\r', '\n'], " ")));
+        }
+        text.push_str("-1\n");
+        text
+    }
+
+    /// 读取棋谱。棋盘不是 15x15、着法落在棋盘外或已有棋子上时返回错误
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines().map(str::trim);
+        let header = lines.next().unwrap_or_default();
+        let size = header
+            .strip_prefix("Piskvorky")
+            .ok_or_else(|| "not a Piskvork record (missing \"Piskvorky\" header)".to_string())?
+            .split(',')
+            .next()
+            .unwrap_or_default()
+            .trim();
+        if size != format!("{}x{}", BOARD_SIZE, BOARD_SIZE) {
+            return Err(format!("unsupported board size {}", size));
+        }
+
+        let mut game_state = GameState::new();
+        let mut moves: Vec<TimedMove> = Vec::new();
+        let mut elapsed = 0;
+        let mut names = Vec::new();
+        let mut stone = Stone::Black;
+        for (index, line) in lines.enumerate() {
+            if line.is_empty() {
+                continue;
+            }
+            // 着法之后的行都不是着法
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let numbers: Option<Vec<u64>> = fields.iter().map(|f| f.parse().ok()).collect();
+            match numbers {
+                Some(numbers) if names.is_empty() && (2..=3).contains(&numbers.len()) => {
+                    let (row, col) = parse_psq_point(numbers[0], numbers[1]).ok_or_else(|| {
+                        format!("line {}: move {} is off the board", index + 2, line)
+                    })?;
+                    game_state
+                        .apply_move(row, col, stone)
+                        .map_err(|_| format!("line {}: {} is already occupied", index + 2, line))?;
+                    elapsed += numbers.get(2).copied().unwrap_or(0);
+                    moves.push(TimedMove {
+                        row,
+                        col,
+                        stone,
+                        time: elapsed,
+                    });
+                    stone = stone.opponent();
+                }
+                // 结尾的数字行 (如 -1) 不是名字
+                _ if line.parse::<i64>().is_ok() => {}
+                _ => names.push(line.to_string()),
+            }
+        }
+
+        let mut names = names.into_iter();
+        Ok(PsqGame {
+            black: names.next().unwrap_or_default(),
+            white: names.next().unwrap_or_default(),
+            moves,
+        })
+    }
+
+    /// 前 count 步棋对应的对局，结果由棋盘判断
+    pub fn saved_game(&self, count: usize) -> SavedGame {
+        let moves = self.moves[..count.min(self.moves.len())].to_vec();
+        let result = match moves.len() {
+            n if n == BOARD_SIZE * BOARD_SIZE => GameResult::Draw,
+            _ => GameResult::Ongoing,
+        };
+        SavedGame::from_moves(moves, result)
+    }
+}
+
+/// (行, 列) 转为 Piskvork 坐标：x 为列、y 为行，都从 1 开始，y 从上往下数
+pub fn psq_point(row: usize, col: usize) -> (usize, usize) {
+    (col + 1, GRID_SIZE - row + 1)
+}
+
+pub fn parse_psq_point(x: u64, y: u64) -> Option<(usize, usize)> {
+    let (x, y) = (x as usize, y as usize);
+    if !(1..=BOARD_SIZE).contains(&x) || !(1..=BOARD_SIZE).contains(&y) {
+        return None;
+    }
+    Some((GRID_SIZE + 1 - y, x - 1))
+}
+
+/// 导入的 Piskvork 棋谱，以及棋盘上显示到第几步
+#[derive(Resource, Default)]
+pub struct PsqReplay {
+    game: Option<PsqGame>,
+    shown: usize,
+    // 显示的局面；棋盘被改动 (落子、重新开局) 后方向键不再回放
+    board: Option<[[Option<Stone>; GRID_SIZE + 1]; GRID_SIZE + 1]>,
+    // 由其他界面 (如对局库浏览) 打开、还没有显示到棋盘上
+    pending: bool,
+}
+
+impl PsqReplay {
+    /// 棋盘上显示的是回放中的局面。回放时 AI 不落子
+    pub fn is_replaying(&self, game_state: &GameState) -> bool {
+        self.game.is_some() && self.board == Some(game_state.board)
+    }
+
+    /// 回放 game：下一帧在棋盘上显示开局局面
+    pub fn open(&mut self, game: PsqGame) {
+        self.game = Some(game);
+        self.shown = 0;
+        self.pending = true;
+    }
+}
+
+/// 系统：按 Ctrl+P 把当前对局导出为 PSQ_FILE
+pub fn export_psq(
+    keys: Res<Input<KeyCode>>,
+    game_state: Res<GameState>,
+    log: Res<MoveLog>,
+    ai: Res<SmartAgent>,
+    settings: Res<Settings>,
+    profile: Res<PlayerProfile>,
+    mut status_query: Query<&mut Text, With<SaveStatusText>>,
+) {
+    if !ctrl_pressed(&keys, KeyCode::P) {
+        return;
+    }
+    let black = player_name(ai.get_stone() == Stone::Black, &ai, &settings, &profile);
+    let white = player_name(ai.get_stone() == Stone::White, &ai, &settings, &profile);
+    let game = PsqGame::from_game(&game_state, &log, &black, &white);
+    let message = match fs::write(PSQ_FILE, game.to_psq()) {
+        Ok(()) => format!("Exported {} moves to {}", game.moves.len(), PSQ_FILE),
+        Err(e) => format!("Export failed: {}: {}", PSQ_FILE, e),
+    };
+    set_status(&mut status_query, message);
+}
+
+/// 系统：按 Ctrl+O 从 PSQ_FILE 导入棋谱并显示终局 (或显示用 PsqReplay::open 打开的棋谱)。
+/// 之后只要棋盘没有被改动，按左、右方向键逐步后退、前进，Home、End 回到开局、终局
+#[allow(clippy::too_many_arguments)]
+pub fn replay_psq(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    mut replay: ResMut<PsqReplay>,
+    ai: Res<SmartAgent>,
+    mut game_state: ResMut<GameState>,
+    mut log: ResMut<MoveLog>,
+    mut player_score: ResMut<PlayerScore>,
+    mut review: ResMut<MoveReview>,
+    mut profile: ResMut<PlayerProfile>,
+    mut adaptive: ResMut<AdaptiveDifficulty>,
+    stone_query: Query<Entity, With<StoneComponent>>,
+    mut status_query: Query<&mut Text, With<SaveStatusText>>,
+) {
+    if ctrl_pressed(&keys, KeyCode::O) {
+        let parsed = fs::read_to_string(Path::new(PSQ_FILE))
+            .map_err(|e| e.to_string())
+            .and_then(|text| PsqGame::parse(&text));
+        match parsed {
+            Ok(game) => {
+                replay.shown = game.moves.len();
+                replay.game = Some(game);
+            }
+            Err(e) => {
+                set_status(
+                    &mut status_query,
+                    format!("Import failed: {}: {}", PSQ_FILE, e),
+                );
+                return;
+            }
+        }
+    } else if replay.pending {
+        replay.pending = false;
+    } else {
+        let Some(game) = &replay.game else {
+            return;
+        };
+        let total = game.moves.len();
+        if !replay.is_replaying(&game_state) {
+            return;
+        }
+        let shown = if keys.just_pressed(KeyCode::Left) {
+            replay.shown.saturating_sub(1)
+        } else if keys.just_pressed(KeyCode::Right) {
+            (replay.shown + 1).min(total)
+        } else if keys.just_pressed(KeyCode::Home) {
+            0
+        } else if keys.just_pressed(KeyCode::End) {
+            total
+        } else {
+            return;
+        };
+        if shown == replay.shown {
+            return;
+        }
+        replay.shown = shown;
+    }
+    let Some(game) = &replay.game else {
+        return;
+    };
+
+    let name = |name: &str| match name {
+        "" => "?".to_string(),
+        name => name.to_string(),
+    };
+    let message = format!(
+        "{} vs {}: move {}/{}\nLeft / Right: step, Home / End: start / end",
+        name(&game.black),
+        name(&game.white),
+        replay.shown,
+        game.moves.len()
+    );
+    let saved = game.saved_game(replay.shown);
+    restore_game(
+        &mut commands,
+        &saved,
+        &ai,
+        &mut game_state,
+        &mut log,
+        &mut player_score,
+        &mut review,
+        &mut profile,
+        &mut adaptive,
+        &stone_query,
+    );
+    replay.board = Some(game_state.board);
+    set_status(&mut status_query, message);
+}
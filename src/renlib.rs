@@ -0,0 +1,390 @@
+use crate::agent::SmartAgent;
+use crate::board::{cell_position, shape_bundle};
+use crate::game::{CELL_SIZE, GRID_SIZE, GameState, Stone};
+use crate::savegame::{SaveStatusText, set_status};
+use crate::transposition::{SYMMETRIES, inverse_transform_point, symmetric_hash, zobrist_key};
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+const BOARD_SIZE: usize = GRID_SIZE + 1;
+// 文件头：0xFF "RenLib" 0xFF，主、次版本号，之后填充到 20 字节
+const MAGIC: [u8; 8] = [0xFF, b'R', b'e', b'n', b'L', b'i', b'b', 0xFF];
+const HEADER_LEN: usize = 20;
+// 本程序能读取的最高主版本号 (RenLib 3.x)
+const MAX_MAJOR_VERSION: u8 = 3;
+
+// 每个节点的标志位 (第二个字节)
+const DOWN: u32 = 0x80; // 有后续着法，下一个节点是它的第一个子节点
+const RIGHT: u32 = 0x40; // 有另一个变化，它的兄弟节点在它的整个子树之后
+const OLD_COMMENT: u32 = 0x20; // 旧版本的注释
+const MARK: u32 = 0x10; // 作者标记的节点
+const COMMENT: u32 = 0x08; // 节点之后跟着注释
+const NO_MOVE: u32 = 0x02; // 没有着法的节点 (通常是根节点)
+const EXTENSION: u32 = 0x01; // 节点之后还有两个字节的扩展标志
+// 扩展标志 (第一个扩展字节对应第 8-15 位)
+const BOARD_TEXT: u32 = 0x100; // 注释之后跟着棋盘上的文字
+
+// 浏览棋谱库时的标记在棋子之上、提示 (z = 3) 之下
+const BROWSER_Z: f32 = 2.7;
+
+/// RenLib 棋谱库的一个节点：一步棋 (根节点没有着法) 及其注释和后续变化
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LibNode {
+    pub point: Option<(usize, usize)>, // (行, 列)
+    pub comment: Option<String>,
+    pub board_text: Option<String>,
+    pub marked: bool,
+    pub children: Vec<usize>, // 子节点在 nodes 中的下标，第一个为主变化
+}
+
+/// RenLib (.lib) 棋谱库：以节点树表示的开局变化。nodes[0] 为空棋盘
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenLib {
+    pub version: (u8, u8),
+    pub nodes: Vec<LibNode>,
+}
+
+impl RenLib {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let bytes = fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::parse(&bytes).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// 读取棋谱库。节点按先序排列，每个节点两个字节：位置 (高 4 位为从上数的行，
+    /// 低 4 位为列 + 1，0 表示没有着法) 和标志位。有 RIGHT 标志的节点
+    /// 记下它的父节点，没有 DOWN 标志的节点 (叶子) 之后回到最近记下的父节点
+    pub fn parse(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() < HEADER_LEN || bytes[..MAGIC.len()] != MAGIC {
+            return Err("not a RenLib library".to_string());
+        }
+        let version = (bytes[8], bytes[9]);
+        if version.0 > MAX_MAJOR_VERSION {
+            return Err(format!(
+                "unsupported RenLib version {}.{}",
+                version.0, version.1
+            ));
+        }
+
+        let mut reader = LibReader {
+            bytes,
+            pos: HEADER_LEN,
+        };
+        let mut nodes = vec![LibNode::default()];
+        let mut parent = 0;
+        let mut siblings = Vec::new();
+        while let Some([position, flags]) = reader.pair() {
+            let mut flags = flags as u32;
+            if flags & EXTENSION != 0 {
+                let [extension, _] = reader.pair().ok_or("truncated node extension")?;
+                flags |= (extension as u32) << 8;
+            }
+            let point = if position == 0 || flags & NO_MOVE != 0 {
+                None
+            } else {
+                Some(lib_point(position).ok_or_else(|| {
+                    format!(
+                        "node {}: position {:#04x} is off the board",
+                        nodes.len(),
+                        position
+                    )
+                })?)
+            };
+            let comment = if flags & (COMMENT | OLD_COMMENT) != 0 {
+                Some(reader.text()?)
+            } else {
+                None
+            };
+            let board_text = if flags & BOARD_TEXT != 0 {
+                Some(reader.text()?)
+            } else {
+                None
+            };
+
+            let index = nodes.len();
+            nodes.push(LibNode {
+                point,
+                comment: comment.filter(|c| !c.is_empty()),
+                board_text: board_text.filter(|t| !t.is_empty()),
+                marked: flags & MARK != 0,
+                children: Vec::new(),
+            });
+            nodes[parent].children.push(index);
+
+            if flags & RIGHT != 0 {
+                siblings.push(parent);
+            }
+            if flags & DOWN != 0 {
+                parent = index;
+            } else {
+                match siblings.pop() {
+                    Some(next) => parent = next,
+                    None => break,
+                }
+            }
+        }
+        Ok(RenLib { version, nodes })
+    }
+}
+
+/// 按两个字节一组读取节点和文字
+struct LibReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl LibReader<'_> {
+    fn pair(&mut self) -> Option<[u8; 2]> {
+        let pair = self.bytes.get(self.pos..self.pos + 2)?;
+        self.pos += 2;
+        Some([pair[0], pair[1]])
+    }
+
+    /// 以 0 结尾、补齐到偶数字节的文字 (Latin-1 编码)
+    fn text(&mut self) -> Result<String, String> {
+        let mut text = String::new();
+        loop {
+            let pair = self.pair().ok_or("truncated comment")?;
+            for byte in pair {
+                if byte == 0 {
+                    return Ok(text.replace("\r\n", "\n").trim().to_string());
+                }
+                text.push(byte as char);
+            }
+        }
+    }
+}
+
+/// RenLib 的位置字节转为 (行, 列)
+fn lib_point(position: u8) -> Option<(usize, usize)> {
+    let y = (position >> 4) as usize;
+    let x = (position & 0x0F) as usize;
+    if x == 0 || y >= BOARD_SIZE {
+        return None;
+    }
+    Some((GRID_SIZE - y, x - 1))
+}
+
+/// 开局库中的一步后续着法
+#[derive(Debug, Clone, PartialEq)]
+pub struct BookMove {
+    pub row: usize,
+    pub col: usize,
+    pub marked: bool,
+}
+
+/// 开局库中的一个局面：后续着法 (第一个为主变化) 和走到这里的那步棋的注释
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BookEntry {
+    pub moves: Vec<BookMove>,
+    pub comment: Option<String>,
+}
+
+/// 由棋谱库生成的开局库：按局面 (棋子的 Zobrist 哈希) 查后续着法。
+/// 不同着法顺序走到的同一局面合并在一起；查找时也尝试棋盘的 8 种对称变换
+#[derive(Debug, Clone, Default)]
+pub struct OpeningBook {
+    positions: HashMap<u64, BookEntry>,
+}
+
+impl OpeningBook {
+    pub fn from_library(library: &RenLib) -> Self {
+        let mut book = OpeningBook::default();
+        let mut game_state = GameState::new();
+        book.add_node(library, 0, &mut game_state, 0, Stone::Black);
+        book
+    }
+
+    /// 读取 RenLib 棋谱库生成开局库
+    pub fn load(path: &Path) -> Result<Self, String> {
+        RenLib::load(path).map(|library| Self::from_library(&library))
+    }
+
+    /// 开局库中的局面数
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    // 把 index 节点 (已经走在 game_state 上，哈希为 hash，轮到 stone) 的后续着法加入开局库
+    fn add_node(
+        &mut self,
+        library: &RenLib,
+        index: usize,
+        game_state: &mut GameState,
+        hash: u64,
+        stone: Stone,
+    ) {
+        let node = &library.nodes[index];
+        let entry = self.positions.entry(hash).or_default();
+        if entry.comment.is_none() {
+            entry.comment.clone_from(&node.comment);
+        }
+        for &child in &node.children {
+            let child_node = &library.nodes[child];
+            let Some((row, col)) = child_node.point else {
+                // 没有着法的节点：后续着法仍属于当前局面
+                self.add_node(library, child, game_state, hash, stone);
+                continue;
+            };
+            // 与已有棋子重叠的变化 (损坏的棋谱库) 不能继续
+            if game_state.apply_move(row, col, stone).is_err() {
+                continue;
+            }
+            let entry = self.positions.entry(hash).or_default();
+            if !entry.moves.iter().any(|m| (m.row, m.col) == (row, col)) {
+                entry.moves.push(BookMove {
+                    row,
+                    col,
+                    marked: child_node.marked,
+                });
+            }
+            let child_hash = hash ^ zobrist_key(row, col, stone);
+            self.add_node(library, child, game_state, child_hash, stone.opponent());
+            game_state.undo_move(row, col);
+        }
+    }
+
+    /// 当前局面在开局库中的条目 (着法已换回当前棋盘的方向)
+    pub fn lookup(&self, game_state: &GameState) -> Option<BookEntry> {
+        (0..SYMMETRIES).find_map(|symmetry| {
+            let hash = symmetric_hash(game_state, symmetry);
+            let entry = self.positions.get(&hash)?;
+            Some(BookEntry {
+                moves: entry
+                    .moves
+                    .iter()
+                    .map(|m| {
+                        let (row, col) = inverse_transform_point((m.row, m.col), symmetry);
+                        BookMove { row, col, ..*m }
+                    })
+                    .collect(),
+                comment: entry.comment.clone(),
+            })
+        })
+    }
+
+    /// 当前局面在开局库中的后续着法 (只包括空位)
+    pub fn moves(&self, game_state: &GameState) -> Vec<BookMove> {
+        self.lookup(game_state)
+            .map(|entry| entry.moves)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|m| game_state.board[m.row][m.col].is_none())
+            .collect()
+    }
+}
+
+/// 开局库浏览：在棋盘上标出当前局面在开局库中的后续着法。按 B 键切换显示
+#[derive(Resource, Default)]
+pub struct BookBrowser {
+    pub visible: bool,
+    // 标记当前对应的局面，局面变化后重画
+    board: Option<[[Option<Stone>; GRID_SIZE + 1]; GRID_SIZE + 1]>,
+}
+
+/// 浏览开局库时画出的后续着法
+#[derive(Component)]
+pub struct BookMarker;
+
+/// 系统：按 B 键显示或隐藏开局库的后续着法
+pub fn toggle_book_browser(keys: Res<Input<KeyCode>>, mut browser: ResMut<BookBrowser>) {
+    if keys.just_pressed(KeyCode::B) {
+        browser.visible = !browser.visible;
+    }
+}
+
+/// 系统：浏览开局库时，局面一变化就标出新局面的后续着法 (按库中的顺序标 a、b、c…，
+/// 作者标记的着法用红色)，并显示走到这里的那步棋的注释。沿着标记落子即可在库中逐层浏览
+pub fn update_book_browser(
+    mut commands: Commands,
+    game_state: Res<GameState>,
+    ai: Res<SmartAgent>,
+    mut browser: ResMut<BookBrowser>,
+    marker_query: Query<Entity, With<BookMarker>>,
+    mut status_query: Query<&mut Text, With<SaveStatusText>>,
+) {
+    let wanted = browser.visible.then_some(game_state.board);
+    if browser.board == wanted {
+        return;
+    }
+    for entity in marker_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    browser.board = wanted;
+    if !browser.visible {
+        return;
+    }
+
+    let Some(book) = ai.book() else {
+        set_status(
+            &mut status_query,
+            "No opening book: set [ai] book to a RenLib .lib file".to_string(),
+        );
+        return;
+    };
+    let entry = book.lookup(&game_state).unwrap_or_default();
+    let moves: Vec<&BookMove> = entry
+        .moves
+        .iter()
+        .filter(|m| game_state.board[m.row][m.col].is_none())
+        .collect();
+    for (index, m) in moves.iter().enumerate() {
+        let position = cell_position(m.row, m.col);
+        let color = if m.marked {
+            Color::rgb(0.8, 0.1, 0.1)
+        } else {
+            Color::rgb(0.1, 0.4, 0.1)
+        };
+        commands.spawn((
+            shape_bundle(
+                GeometryBuilder::build_as(&shapes::Circle {
+                    radius: 0.35 * CELL_SIZE,
+                    center: Vec2::ZERO,
+                }),
+                position.extend(BROWSER_Z),
+            ),
+            Fill::color(Color::rgba(1.0, 1.0, 1.0, 0.5)),
+            Stroke::new(color, 2.0),
+            BookMarker,
+        ));
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    book_label(index),
+                    TextStyle {
+                        font_size: 16.0,
+                        color,
+                        ..default()
+                    },
+                )
+                .with_alignment(TextAlignment::Center),
+                transform: Transform::from_xyz(position.x, position.y, BROWSER_Z + 0.1),
+                ..default()
+            },
+            BookMarker,
+        ));
+    }
+
+    let mut message = match moves.len() {
+        0 => "Opening book: no continuations for this position".to_string(),
+        n => format!("Opening book: {} continuations", n),
+    };
+    if let Some(comment) = &entry.comment {
+        message.push_str(&format!("\n{}", comment));
+    }
+    set_status(&mut status_query, message);
+}
+
+/// 第 index 个后续着法的标签：a-z，之后为序号
+fn book_label(index: usize) -> String {
+    match u8::try_from(index) {
+        Ok(i) if i < 26 => ((b'a' + i) as char).to_string(),
+        _ => (index + 1).to_string(),
+    }
+}
@@ -0,0 +1,398 @@
+use crate::agent::{MATE_THRESHOLD, SmartAgent};
+use crate::board::{cell_position, shape_bundle};
+use crate::game::{CELL_SIZE, GRID_SIZE, GameState, PlayerScore, Stone, win_rate_loss};
+use crate::game_manager::check_victory;
+use crate::threats::{five_points, vcf};
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, OnceLock, Weak};
+use std::thread;
+
+/// 评价落子时引擎的搜索深度，与难度等级无关，双方使用同一标准
+pub const GRADING_DEPTH: u32 = 3;
+
+/// 落子的分类。前五类按损失的胜率划分，后两类由 VCF 检查得出，优先于前五类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveClass {
+    Best,        // 损失不到 1 个百分点
+    Good,        // 损失不到 5 个百分点
+    Inaccuracy,  // 损失不到 10 个百分点
+    Mistake,     // 损失不到 20 个百分点
+    Blunder,     // 损失 20 个百分点以上
+    MissedWin,   // 有连续冲四的必胜却没有走
+    AllowedLoss, // 落子后让对手有了连续冲四的必胜
+}
+
+impl MoveClass {
+    pub const ALL: [MoveClass; 7] = [
+        MoveClass::Best,
+        MoveClass::Good,
+        MoveClass::Inaccuracy,
+        MoveClass::Mistake,
+        MoveClass::Blunder,
+        MoveClass::MissedWin,
+        MoveClass::AllowedLoss,
+    ];
+
+    /// 按损失的胜率 (百分点) 分类
+    pub fn from_loss(loss: f64) -> Self {
+        if loss < 1.0 {
+            MoveClass::Best
+        } else if loss < 5.0 {
+            MoveClass::Good
+        } else if loss < 10.0 {
+            MoveClass::Inaccuracy
+        } else if loss < 20.0 {
+            MoveClass::Mistake
+        } else {
+            MoveClass::Blunder
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MoveClass::Best => "Best",
+            MoveClass::Good => "Good",
+            MoveClass::Inaccuracy => "Inaccuracy",
+            MoveClass::Mistake => "Mistake",
+            MoveClass::Blunder => "Blunder",
+            MoveClass::MissedWin => "Missed win",
+            MoveClass::AllowedLoss => "Allowed loss",
+        }
+    }
+
+    /// 棋子上徽标的文字
+    pub fn badge(self) -> &'static str {
+        match self {
+            MoveClass::Best => "!",
+            MoveClass::Good => "=",
+            MoveClass::Inaccuracy => "?!",
+            MoveClass::Mistake => "?",
+            MoveClass::Blunder => "??",
+            MoveClass::MissedWin => "W",
+            MoveClass::AllowedLoss => "L",
+        }
+    }
+
+    /// 徽标的底色
+    pub fn color(self) -> Color {
+        match self {
+            MoveClass::Best => Color::rgb(0.1, 0.6, 0.2),
+            MoveClass::Good => Color::rgb(0.45, 0.45, 0.45),
+            MoveClass::Inaccuracy => Color::rgb(0.85, 0.65, 0.1),
+            MoveClass::Mistake => Color::rgb(0.9, 0.4, 0.1),
+            MoveClass::Blunder => Color::rgb(0.8, 0.1, 0.1),
+            MoveClass::MissedWin => Color::rgb(0.6, 0.1, 0.7),
+            MoveClass::AllowedLoss => Color::rgb(0.5, 0.0, 0.0),
+        }
+    }
+}
+
+/// 一步棋的评价结果
+#[derive(Debug, Clone)]
+pub struct ReviewedMove {
+    pub row: usize,
+    pub col: usize,
+    pub stone: Stone,
+    pub best_score: i32, // 最佳落子的分数
+    pub score: i32,      // 这步棋的分数
+    pub class: MoveClass,
+}
+
+// 评价线程算出的结果，评价完成前为空
+type GradingResult = Arc<OnceLock<Option<ReviewedMove>>>;
+
+/// 等待评价线程评价的一步棋
+struct GradingJob {
+    before: GameState, // 落子前的局面
+    player: bool,      // 玩家的落子，评价完成后还计入玩家评分
+    result: GradingResult,
+}
+
+/// 交给评价线程的请求。只持有结果的弱引用：对应的 GradingJob 已被丢弃
+/// (如重新开局、载入棋谱) 时评价线程直接跳过，不再搜索
+struct GradingRequest {
+    grader: SmartAgent,
+    before: GameState,
+    mv: (usize, usize),
+    stone: Stone,
+    result: Weak<OnceLock<Option<ReviewedMove>>>,
+}
+
+/// 启动评价线程：按收到的顺序逐个评价，同一时刻只有一个评价搜索。
+/// MoveReview 被丢弃 (通道关闭) 时线程结束
+fn spawn_grader() -> Sender<GradingRequest> {
+    let (sender, receiver) = mpsc::channel::<GradingRequest>();
+    thread::spawn(move || {
+        for request in receiver {
+            let Some(result) = request.result.upgrade() else {
+                continue;
+            };
+            // 评价出错时记为没有结果，避免等待这步棋的界面一直等下去
+            let reviewed = panic::catch_unwind(AssertUnwindSafe(|| {
+                review_move(&request.grader, &request.before, request.mv, request.stone)
+            }))
+            .ok()
+            .flatten();
+            let _ = result.set(reviewed);
+        }
+    });
+    sender
+}
+
+/// 本局双方每一步棋的评价，按落子顺序
+#[derive(Resource, Default)]
+pub struct MoveReview {
+    pub moves: Vec<ReviewedMove>,
+    pending: VecDeque<GradingJob>, // 还在后台评价的落子，按落子顺序
+    grader: Option<Sender<GradingRequest>>, // 评价线程，第一次评价时启动
+}
+
+impl MoveReview {
+    /// 把 stone 一方在 game_state 下走 mv 交给评价线程，不阻塞界面。
+    /// 完成后由 record_graded_moves 按落子顺序记录
+    pub fn grade(
+        &mut self,
+        ai: &SmartAgent,
+        game_state: &GameState,
+        mv: (usize, usize),
+        stone: Stone,
+        player: bool,
+    ) {
+        let result = GradingResult::default();
+        let request = GradingRequest {
+            grader: ai.clone(),
+            before: game_state.clone(),
+            mv,
+            stone,
+            result: Arc::downgrade(&result),
+        };
+        let grader = self.grader.get_or_insert_with(spawn_grader);
+        if let Err(error) = grader.send(request) {
+            // 评价线程意外退出时重新启动
+            let _ = self.grader.insert(spawn_grader()).send(error.0);
+        }
+        self.pending.push_back(GradingJob {
+            before: game_state.clone(),
+            player,
+            result,
+        });
+    }
+
+    /// 清空评价记录。还在排队的落子随之丢弃，评价线程不再搜索它们
+    pub fn clear(&mut self) {
+        self.moves.clear();
+        self.pending.clear();
+    }
+
+    /// 是否还有落子在后台评价
+    pub fn is_grading(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// 记录一步棋。局面已经重新开始 (记录的棋子不在棋盘上) 时先清空旧的记录
+    pub fn record(&mut self, game_state: &GameState, reviewed: ReviewedMove) {
+        if self.is_stale(game_state) {
+            self.moves.clear();
+        }
+        self.moves.push(reviewed);
+    }
+
+    /// 记录的落子有不在棋盘上的，说明已经重新开局
+    pub fn is_stale(&self, game_state: &GameState) -> bool {
+        !game_state.has_stones(self.moves.iter().map(|m| (m.row, m.col, m.stone)))
+    }
+
+    /// stone 一方每类落子的数量，顺序同 MoveClass::ALL
+    pub fn counts(&self, stone: Stone) -> [u32; 7] {
+        let mut counts = [0; 7];
+        for m in self.moves.iter().filter(|m| m.stone == stone) {
+            let index = MoveClass::ALL
+                .iter()
+                .position(|&c| c == m.class)
+                .unwrap_or(0);
+            counts[index] += 1;
+        }
+        counts
+    }
+
+    /// 对局结束窗口中的统计表，每类一行，两列分别是玩家和 AI
+    pub fn summary_text(&self, player_stone: Stone) -> String {
+        let player = self.counts(player_stone);
+        let ai = self.counts(player_stone.opponent());
+        let mut text = format!("{:<13}{:>4}{:>4}", "", "You", "AI");
+        for (index, class) in MoveClass::ALL.iter().enumerate() {
+            text.push_str(&format!(
+                "\n{:<13}{:>4}{:>4}",
+                class.name(),
+                player[index],
+                ai[index]
+            ));
+        }
+        text
+    }
+}
+
+/// 系统：按落子顺序取出后台评价完的落子记入复盘，玩家的落子同时计入玩家评分。
+/// 评价期间已经重新开局 (落子前的棋子不都在棋盘上) 时丢弃结果
+pub fn record_graded_moves(
+    game_state: Res<GameState>,
+    mut review: ResMut<MoveReview>,
+    mut player_score: ResMut<PlayerScore>,
+) {
+    // 只在有结果时才可变借用，避免每帧都触发复盘的变更检测
+    while review
+        .pending
+        .front()
+        .is_some_and(|job| job.result.get().is_some())
+    {
+        let Some(job) = review.pending.pop_front() else {
+            break;
+        };
+        let Some(reviewed) = job.result.get().cloned().flatten() else {
+            if job.player {
+                // 如果没有可行的移动，则不更新评分
+                println!("警告: 找不到最佳落子/分数。也许没有可落子的地方了？");
+            }
+            continue;
+        };
+        let current = (0..GRID_SIZE + 1)
+            .flat_map(|row| (0..GRID_SIZE + 1).map(move |col| (row, col)))
+            .all(|(row, col)| {
+                job.before.board[row][col].is_none()
+                    || job.before.board[row][col] == game_state.board[row][col]
+            });
+        if !current {
+            continue;
+        }
+        if job.player {
+            // 更新玩家评分，直接传入当前步得分和最优步得分
+            player_score.add_move(reviewed.score, reviewed.best_score);
+            println!("最佳落子得分: {}", reviewed.best_score);
+            println!(
+                "玩家落子: ({}, {}), 得分: {}, {}",
+                reviewed.row,
+                reviewed.col,
+                reviewed.score,
+                reviewed.class.name()
+            );
+        } else {
+            println!("AI 落子评价: {}", reviewed.class.name());
+        }
+        review.record(&job.before, reviewed);
+    }
+}
+
+/// 评价 stone 一方在 game_state 下走 (row, col)：用 GRADING_DEPTH 层搜索算出胜率损失，
+/// 再用 VCF 检查是否错过了必胜或送给对手必胜。ai 提供评估权重
+pub fn review_move(
+    ai: &SmartAgent,
+    game_state: &GameState,
+    (row, col): (usize, usize),
+    stone: Stone,
+) -> Option<ReviewedMove> {
+    let mut grader = SmartAgent::new(stone, GRADING_DEPTH);
+    grader.set_weights(ai.weights().clone());
+    let (best_score, score) = grader.grade_move(game_state, (row, col))?;
+
+    let mut after = game_state.clone();
+    after.board[row][col] = Some(stone);
+    let class = if check_victory(&after) == Some(stone) {
+        MoveClass::Best
+    } else if score < MATE_THRESHOLD
+        && vcf(game_state, stone).is_some()
+        && !keeps_vcf(&after, stone)
+    {
+        MoveClass::MissedWin
+    } else if best_score > -MATE_THRESHOLD && vcf(&after, stone.opponent()).is_some() {
+        MoveClass::AllowedLoss
+    } else {
+        MoveClass::from_loss(win_rate_loss(best_score, score))
+    };
+
+    Some(ReviewedMove {
+        row,
+        col,
+        stone,
+        best_score,
+        score,
+        class,
+    })
+}
+
+/// stone 刚走完 (轮到对手)，这步是否是连续冲四必胜中的一步
+fn keeps_vcf(after: &GameState, stone: Stone) -> bool {
+    if !five_points(after, stone.opponent()).is_empty() {
+        return false;
+    }
+    match five_points(after, stone).as_slice() {
+        [] => false,
+        &[(br, bc)] => {
+            let mut blocked = after.clone();
+            blocked.board[br][bc] = Some(stone.opponent());
+            vcf(&blocked, stone).is_some()
+        }
+        _ => true,
+    }
+}
+
+/// 复盘时棋子上显示的分类徽标
+#[derive(Component)]
+pub struct MoveBadge;
+
+/// 系统：对局结束后在每个棋子右上角显示它的分类徽标，重新开局后移除
+pub fn update_move_badges(
+    mut commands: Commands,
+    game_state: Res<GameState>,
+    mut review: ResMut<MoveReview>,
+    badge_query: Query<Entity, With<MoveBadge>>,
+) {
+    if review.is_stale(&game_state) {
+        review.moves.clear();
+    }
+
+    if !game_state.is_game_over {
+        for entity in badge_query.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+    // 等最后几步在后台评价完再显示
+    if !badge_query.is_empty() || review.is_grading() {
+        return;
+    }
+
+    for m in &review.moves {
+        let position = cell_position(m.row, m.col) + Vec2::splat(CELL_SIZE * 0.3);
+        commands.spawn((
+            shape_bundle(
+                GeometryBuilder::build_as(&shapes::Circle {
+                    radius: CELL_SIZE * 0.22,
+                    center: Vec2::ZERO,
+                }),
+                position.extend(5.0),
+            ),
+            Fill::color(m.class.color()),
+            MoveBadge,
+        ));
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    m.class.badge(),
+                    TextStyle {
+                        font_size: 12.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                )
+                .with_alignment(TextAlignment::Center),
+                transform: Transform::from_xyz(position.x, position.y, 6.0),
+                ..default()
+            },
+            MoveBadge,
+        ));
+    }
+}
@@ -0,0 +1,341 @@
+use crate::database::DbGame;
+use crate::game::{GameState, Stone, parse_notation};
+use crate::savegame::GameResult;
+use std::collections::HashMap;
+
+/// XML 中的一个元素开始标签 (或自闭合标签) 及其属性
+#[derive(Debug, Clone, PartialEq)]
+pub struct XmlElement {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+}
+
+impl XmlElement {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum XmlEvent {
+    Start(XmlElement),
+    /// 结束标签；自闭合标签在 Start 之后也产生一个 End
+    End(String),
+    Text(String),
+}
+
+/// 只读取元素、属性和文字的简单 XML 解析器。跳过声明、注释和 DOCTYPE，
+/// 支持 CDATA 和常用的字符实体，不检查标签是否配对
+pub struct XmlReader<'a> {
+    text: &'a str,
+    pos: usize,
+    pending_end: Option<String>,
+}
+
+impl<'a> XmlReader<'a> {
+    pub fn new(text: &'a str) -> Self {
+        XmlReader {
+            text,
+            pos: 0,
+            pending_end: None,
+        }
+    }
+
+    /// 当前位置所在的行号，用于错误信息
+    fn line(&self) -> usize {
+        self.text[..self.pos].matches('\n').count() + 1
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("line {}: {}", self.line(), message)
+    }
+
+    /// 跳到 end 之后，返回跳过的内容
+    fn skip_past(&mut self, end: &str) -> Result<&'a str, String> {
+        let rest = &self.text[self.pos..];
+        let offset = rest
+            .find(end)
+            .ok_or_else(|| self.error(&format!("missing {}", end)))?;
+        self.pos += offset + end.len();
+        Ok(&rest[..offset])
+    }
+
+    fn read_tag(&mut self) -> Result<Option<XmlEvent>, String> {
+        let rest = &self.text[self.pos..];
+        if rest.starts_with("<?") {
+            self.skip_past("?>")?;
+            return Ok(None);
+        }
+        if rest.starts_with("<!--") {
+            self.skip_past("-->")?;
+            return Ok(None);
+        }
+        if rest.starts_with("<![CDATA[") {
+            self.pos += "<![CDATA[".len();
+            let text = self.skip_past("]]>")?;
+            return Ok(Some(XmlEvent::Text(text.to_string())));
+        }
+        if rest.starts_with("<!") {
+            self.skip_past(">")?;
+            return Ok(None);
+        }
+
+        self.pos += 1;
+        let tag = self.skip_past(">")?;
+        if let Some(name) = tag.strip_prefix('/') {
+            return Ok(Some(XmlEvent::End(name.trim().to_string())));
+        }
+        let (tag, self_closing) = match tag.strip_suffix('/') {
+            Some(tag) => (tag, true),
+            None => (tag, false),
+        };
+        let element = parse_element(tag).map_err(|e| self.error(&e))?;
+        if self_closing {
+            self.pending_end = Some(element.name.clone());
+        }
+        Ok(Some(XmlEvent::Start(element)))
+    }
+}
+
+impl Iterator for XmlReader<'_> {
+    type Item = Result<XmlEvent, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(name) = self.pending_end.take() {
+            return Some(Ok(XmlEvent::End(name)));
+        }
+        while self.pos < self.text.len() {
+            let rest = &self.text[self.pos..];
+            if !rest.starts_with('<') {
+                let end = rest.find('<').unwrap_or(rest.len());
+                self.pos += end;
+                let text = &rest[..end];
+                if text.trim().is_empty() {
+                    continue;
+                }
+                return Some(Ok(XmlEvent::Text(unescape(text))));
+            }
+            match self.read_tag() {
+                Ok(Some(event)) => return Some(Ok(event)),
+                Ok(None) => continue,
+                Err(e) => {
+                    self.pos = self.text.len();
+                    return Some(Err(e));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// 解析标签内容 `name key="value" ...`
+fn parse_element(tag: &str) -> Result<XmlElement, String> {
+    let tag = tag.trim();
+    let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+    let name = &tag[..name_end];
+    if name.is_empty() {
+        return Err("empty tag".to_string());
+    }
+
+    let mut attributes = Vec::new();
+    let mut rest = tag[name_end..].trim_start();
+    while !rest.is_empty() {
+        let (key, after) = rest
+            .split_once('=')
+            .ok_or_else(|| format!("attribute without value in <{}>", name))?;
+        let after = after.trim_start();
+        let quote = after
+            .chars()
+            .next()
+            .filter(|&c| c == '"' || c == '\'')
+            .ok_or_else(|| format!("unquoted attribute {} in <{}>", key.trim(), name))?;
+        let value_end = after[1..]
+            .find(quote)
+            .ok_or_else(|| format!("unterminated attribute {} in <{}>", key.trim(), name))?;
+        attributes.push((key.trim().to_string(), unescape(&after[1..1 + value_end])));
+        rest = after[value_end + 2..].trim_start();
+    }
+    Ok(XmlElement {
+        name: name.to_string(),
+        attributes,
+    })
+}
+
+/// 替换字符实体 (&amp; &lt; &gt; &quot; &apos; 以及 &#NN; &#xNN;)
+fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+        let entity = &rest[1..end];
+        let ch = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        match ch {
+            Some(ch) => {
+                result.push(ch);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                result.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// 读取 RIF 对局库的结果
+#[derive(Debug, Clone, Default)]
+pub struct RifImport {
+    pub games: Vec<DbGame>,
+    /// 没有着法、着法无法识别或不是黑白交替落子而跳过的对局
+    pub skipped: usize,
+}
+
+// 对局的原始属性，读完整个文件后再查找棋手、赛事等名字
+struct RawGame {
+    element: XmlElement,
+    moves: String,
+}
+
+/// 读取 RIF (renju.net) 数据库的 XML 导出。
+///
+/// 文件中的 `<player>`、`<tournament>`、`<opening>`、`<rule>` 以 id 属性编号，
+/// 每个 `<game>` 用这些 id 引用黑方、白方、赛事、开局和规则，`bresult` 为黑方得分
+/// (1、0.5、0)，着法写在 `<move>` 元素中，如 `h8 h9 i9`
+pub fn parse_rif(text: &str) -> Result<RifImport, String> {
+    let mut players = HashMap::new();
+    let mut tournaments = HashMap::new();
+    let mut openings = HashMap::new();
+    let mut rules = HashMap::new();
+    let mut raw_games = Vec::new();
+
+    let mut game: Option<RawGame> = None;
+    let mut in_move = false;
+    let mut found_database = false;
+    for event in XmlReader::new(text) {
+        match event? {
+            XmlEvent::Start(element) => {
+                let id = element.get("id").unwrap_or_default().to_string();
+                match element.name.as_str() {
+                    "database" => found_database = true,
+                    "player" => {
+                        let name = [element.get("name"), element.get("surname")]
+                            .into_iter()
+                            .flatten()
+                            .map(str::trim)
+                            .filter(|part| !part.is_empty())
+                            .collect::<Vec<_>>()
+                            .join(" ");
+                        players.insert(id, name);
+                    }
+                    "tournament" => {
+                        let year = element
+                            .get("year")
+                            .or_else(|| element.get("start"))
+                            .and_then(|date| date.get(..4))
+                            .and_then(|year| year.parse().ok());
+                        let name = element.get("name").unwrap_or_default().trim().to_string();
+                        tournaments.insert(id, (name, year));
+                    }
+                    "opening" => {
+                        let name = element.get("name").unwrap_or_default().trim();
+                        openings.insert(id, name.to_string());
+                    }
+                    "rule" => {
+                        let name = element.get("name").unwrap_or_default().trim();
+                        rules.insert(id, name.to_string());
+                    }
+                    "game" => {
+                        game = Some(RawGame {
+                            element,
+                            moves: String::new(),
+                        })
+                    }
+                    "move" => in_move = game.is_some(),
+                    _ => {}
+                }
+            }
+            XmlEvent::Text(text) => {
+                if in_move && let Some(game) = &mut game {
+                    game.moves.push_str(&text);
+                    game.moves.push(' ');
+                }
+            }
+            XmlEvent::End(name) => match name.as_str() {
+                "move" => in_move = false,
+                "game" => raw_games.extend(game.take()),
+                _ => {}
+            },
+        }
+    }
+    if !found_database {
+        return Err("not a RIF database export (missing <database>)".to_string());
+    }
+
+    let mut import = RifImport::default();
+    let lookup =
+        |table: &HashMap<String, String>, key: &str| table.get(key).cloned().unwrap_or_default();
+    for raw in raw_games {
+        let element = &raw.element;
+        let attr = |key: &str| element.get(key).unwrap_or_default();
+        let Some(moves) = parse_moves(&raw.moves) else {
+            import.skipped += 1;
+            continue;
+        };
+        let (event, year) = tournaments
+            .get(attr("tournament"))
+            .cloned()
+            .unwrap_or_default();
+        import.games.push(DbGame {
+            source: format!("rif:{}", attr("id")),
+            black: lookup(&players, attr("black")),
+            white: lookup(&players, attr("white")),
+            result: match attr("bresult").trim() {
+                "1" => GameResult::Win(Stone::Black),
+                "0" => GameResult::Win(Stone::White),
+                "0.5" => GameResult::Draw,
+                _ => GameResult::Ongoing,
+            },
+            opening: lookup(&openings, attr("opening")),
+            event,
+            year,
+            rule: lookup(&rules, attr("rule")),
+            moves,
+        });
+    }
+    Ok(import)
+}
+
+/// 解析以空白分隔的着法。没有着法、有无法识别的着法或落在已有棋子上时返回 None
+fn parse_moves(text: &str) -> Option<Vec<(usize, usize)>> {
+    let mut game_state = GameState::new();
+    let mut stone = Stone::Black;
+    let mut moves = Vec::new();
+    for token in text.split_whitespace() {
+        let (row, col) = parse_notation(token)?;
+        game_state.apply_move(row, col, stone).ok()?;
+        moves.push((row, col));
+        stone = stone.opponent();
+    }
+    (!moves.is_empty()).then_some(moves)
+}
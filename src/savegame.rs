@@ -0,0 +1,574 @@
+use crate::adaptive::AdaptiveDifficulty;
+use crate::agent::SmartAgent;
+use crate::board::spawn_stone;
+use crate::config::{ConfigFile, ConfigWriter};
+use crate::game::{
+    GRID_SIZE, GameState, PlayerScore, Stone, StoneComponent, move_to_notation, parse_notation,
+};
+use crate::game_manager::check_victory;
+use crate::profile::PlayerProfile;
+use crate::review::MoveReview;
+use crate::settings::Settings;
+use crate::weights::EvalWeights;
+use bevy::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 存档文件的默认位置 (程序工作目录下)
+pub const SAVE_FILE: &str = "gobang-save.toml";
+/// 存档文件 `[file] format` 的值，用来识别存档
+pub const SAVE_FORMAT: &str = "gobang-save";
+/// 当前的存档格式版本。读取时拒绝更高版本的存档
+pub const SAVE_VERSION: u32 = 1;
+/// 本程序使用的规则：自由规则，没有禁手，五子及以上连珠获胜
+pub const FREESTYLE_RULE: &str = "freestyle";
+
+/// 当前的 Unix 时间戳 (毫秒)
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+/// 一步棋及其落子时间
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimedMove {
+    pub row: usize,
+    pub col: usize,
+    pub stone: Stone,
+    pub time: u64, // 落子时间 (Unix 时间戳，毫秒)
+}
+
+/// 本局按顺序的落子记录，存档时写入
+#[derive(Resource, Default)]
+pub struct MoveLog {
+    pub moves: Vec<TimedMove>,
+}
+
+impl MoveLog {
+    /// 记录 stone 在 game_state (落子之前的局面) 下走 (row, col)。
+    /// 局面已经重新开始 (记录的棋子不在棋盘上) 时先清空旧的记录
+    pub fn record(&mut self, game_state: &GameState, row: usize, col: usize, stone: Stone) {
+        if self.is_stale(game_state) {
+            self.moves.clear();
+        }
+        self.moves.push(TimedMove {
+            row,
+            col,
+            stone,
+            time: now_millis(),
+        });
+    }
+
+    /// 记录的落子有不在棋盘上的，说明已经重新开局
+    pub fn is_stale(&self, game_state: &GameState) -> bool {
+        !game_state.has_stones(self.moves.iter().map(|m| (m.row, m.col, m.stone)))
+    }
+}
+
+/// 对局的一方
+#[derive(Debug, Clone, PartialEq)]
+pub enum Player {
+    Human,
+    /// 内置 AI：难度等级 (手动设置深度时为 None)、搜索深度和评估权重文件
+    Ai {
+        level: Option<u32>,
+        depth: u32,
+        weights: PathBuf,
+    },
+    /// 外部 Piskvork 引擎
+    Engine {
+        path: PathBuf,
+    },
+}
+
+impl Player {
+    fn write(&self, writer: &mut ConfigWriter, section: &str) {
+        writer.section(section);
+        match self {
+            Player::Human => {
+                writer.value("player", "\"human\"");
+            }
+            Player::Ai {
+                level,
+                depth,
+                weights,
+            } => {
+                writer.value("player", "\"ai\"");
+                if let Some(level) = level {
+                    writer.value("level", level);
+                }
+                writer
+                    .value("depth", depth)
+                    .value("weights", format!("{:?}", weights.display().to_string()));
+            }
+            Player::Engine { path } => {
+                writer
+                    .value("player", "\"engine\"")
+                    .value("path", format!("{:?}", path.display().to_string()));
+            }
+        }
+    }
+
+    fn read(config: &ConfigFile, section: &str) -> Result<Self, String> {
+        let key = |field: &str| format!("{}.{}", section, field);
+        match config.get_str(&key("player")) {
+            Some("human") => Ok(Player::Human),
+            Some("ai") => Ok(Player::Ai {
+                level: config.get_u64(&key("level")).map(|level| level as u32),
+                depth: config.get_u64(&key("depth")).unwrap_or(1) as u32,
+                weights: PathBuf::from(config.get_str(&key("weights")).unwrap_or_default()),
+            }),
+            Some("engine") => Ok(Player::Engine {
+                path: PathBuf::from(config.get_str(&key("path")).unwrap_or_default()),
+            }),
+            other => Err(format!(
+                "[{}]: unknown player {:?}",
+                section,
+                other.unwrap_or("")
+            )),
+        }
+    }
+
+    fn is_human(&self) -> bool {
+        *self == Player::Human
+    }
+}
+
+/// 对局结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameResult {
+    Ongoing,
+    Win(Stone),
+    Draw,
+}
+
+impl GameResult {
+    pub fn of(game_state: &GameState) -> Self {
+        match (game_state.is_game_over, game_state.winner) {
+            (false, _) => GameResult::Ongoing,
+            (true, Some(winner)) => GameResult::Win(winner),
+            (true, None) => GameResult::Draw,
+        }
+    }
+
+    /// 结果记号，与对局列表相同，未结束为 `*`
+    pub fn notation(self) -> &'static str {
+        match self {
+            GameResult::Ongoing => "*",
+            GameResult::Win(Stone::Black) => "1-0",
+            GameResult::Win(Stone::White) => "0-1",
+            GameResult::Draw => "1/2-1/2",
+        }
+    }
+
+    pub fn from_notation(text: &str) -> Option<Self> {
+        match text {
+            "*" => Some(GameResult::Ongoing),
+            "1-0" => Some(GameResult::Win(Stone::Black)),
+            "0-1" => Some(GameResult::Win(Stone::White)),
+            "1/2-1/2" => Some(GameResult::Draw),
+            _ => None,
+        }
+    }
+}
+
+/// 一局存档：规则、棋盘大小、双方、带时间的着法、结果和玩家评分
+#[derive(Debug, Clone)]
+pub struct SavedGame {
+    pub rule: String,
+    pub board_size: usize,
+    pub black: Player,
+    pub white: Player,
+    pub moves: Vec<TimedMove>,
+    pub result: GameResult,
+    pub score: PlayerScore,
+}
+
+impl SavedGame {
+    /// 从当前对局生成存档。落子记录与棋盘不一致时返回错误
+    pub fn capture(
+        game_state: &GameState,
+        log: &MoveLog,
+        ai: &SmartAgent,
+        settings: &Settings,
+        score: &PlayerScore,
+    ) -> Result<Self, String> {
+        let moves = if log.is_stale(game_state) {
+            Vec::new()
+        } else {
+            log.moves.clone()
+        };
+        let stones = game_state.board.iter().flatten().flatten().count();
+        if moves.len() != stones {
+            return Err(format!(
+                "move history has {} moves but the board has {} stones",
+                moves.len(),
+                stones
+            ));
+        }
+
+        let opponent = match &settings.engine.path {
+            Some(path) => Player::Engine { path: path.clone() },
+            None => Player::Ai {
+                level: ai.skill_level(),
+                depth: ai.get_depth(),
+                weights: settings.weights_path.clone(),
+            },
+        };
+        let (black, white) = match ai.get_stone() {
+            Stone::Black => (opponent, Player::Human),
+            Stone::White => (Player::Human, opponent),
+        };
+        Ok(SavedGame {
+            rule: FREESTYLE_RULE.to_string(),
+            board_size: GRID_SIZE + 1,
+            black,
+            white,
+            moves,
+            result: GameResult::of(game_state),
+            score: score.clone(),
+        })
+    }
+
+    /// 只有着法和结果的对局 (如导入的棋谱)：双方未知，记为玩家；评分从头开始
+    pub fn from_moves(moves: Vec<TimedMove>, result: GameResult) -> Self {
+        SavedGame {
+            rule: FREESTYLE_RULE.to_string(),
+            board_size: GRID_SIZE + 1,
+            black: Player::Human,
+            white: Player::Human,
+            moves,
+            result,
+            score: PlayerScore::new(),
+        }
+    }
+
+    pub fn to_toml(&self) -> String {
+        let mut writer = ConfigWriter::default();
+        writer
+            .section("file")
+            .value("format", format!("{:?}", SAVE_FORMAT))
+            .value("version", SAVE_VERSION);
+        writer
+            .section("game")
+            .value("rule", format!("{:?}", self.rule))
+            .value("board_size", self.board_size)
+            .value("result", format!("{:?}", self.result.notation()))
+            .value("moves", self.moves.len());
+        self.black.write(&mut writer, "black");
+        self.white.write(&mut writer, "white");
+
+        let move_scores: Vec<String> = self.score.move_scores.iter().map(u32::to_string).collect();
+        writer
+            .section("score")
+            .value("move_count", self.score.move_count)
+            .value("rating", self.score.current_rating)
+            .value("move_score", self.score.current_move_score)
+            .value("best_move_score", self.score.best_move_score)
+            .value("move_scores", format!("{:?}", move_scores.join(" ")))
+            .value("hints_used", self.score.hints_used);
+
+        for (index, m) in self.moves.iter().enumerate() {
+            writer
+                .section(&format!("move.{}", index + 1))
+                .value("at", format!("{:?}", move_to_notation(m.row, m.col)))
+                .value("time", m.time);
+        }
+        writer.finish().to_string()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.to_toml()).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// 解析存档文本并检查格式、版本、规则和棋盘大小。着法按黑先交替，不能走在已有棋子上
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let config = ConfigFile::parse(text)?;
+        if config.get_str("file.format") != Some(SAVE_FORMAT) {
+            return Err("not a gobang save file".to_string());
+        }
+        let version = config.get_u64("file.version").unwrap_or(0);
+        if version == 0 || version > SAVE_VERSION as u64 {
+            return Err(format!("unsupported save file version {}", version));
+        }
+        let rule = config.get_str("game.rule").unwrap_or(FREESTYLE_RULE);
+        if rule != FREESTYLE_RULE {
+            return Err(format!("unsupported rule {:?}", rule));
+        }
+        let board_size = config.get_u64("game.board_size").unwrap_or(0) as usize;
+        if board_size != GRID_SIZE + 1 {
+            return Err(format!("unsupported board size {}", board_size));
+        }
+        let result = config.get_str("game.result").unwrap_or("*");
+        let result =
+            GameResult::from_notation(result).ok_or_else(|| format!("bad result {:?}", result))?;
+        let black = Player::read(&config, "black")?;
+        let white = Player::read(&config, "white")?;
+
+        let mut moves = Vec::new();
+        let mut stone = Stone::Black;
+        for index in 1..=config.get_u64("game.moves").unwrap_or(0) {
+            let key = |field: &str| format!("move.{}.{}", index, field);
+            let at = config.get_str(&key("at")).unwrap_or("");
+            let (row, col) =
+                parse_notation(at).ok_or_else(|| format!("move {}: bad point {:?}", index, at))?;
+            if moves
+                .iter()
+                .any(|m: &TimedMove| (m.row, m.col) == (row, col))
+            {
+                return Err(format!("move {}: {} is already occupied", index, at));
+            }
+            moves.push(TimedMove {
+                row,
+                col,
+                stone,
+                time: config.get_u64(&key("time")).unwrap_or(0),
+            });
+            stone = stone.opponent();
+        }
+
+        let score = PlayerScore {
+            move_count: config.get_u64("score.move_count").unwrap_or(0) as u32,
+            current_rating: config.get_u64("score.rating").unwrap_or(100) as u32,
+            current_move_score: config.get_i32("score.move_score").unwrap_or(0),
+            best_move_score: config.get_i32("score.best_move_score").unwrap_or(0),
+            move_scores: config
+                .get_str("score.move_scores")
+                .unwrap_or("")
+                .split_whitespace()
+                .filter_map(|s| s.parse().ok())
+                .collect(),
+            hints_used: config.get_u64("score.hints_used").unwrap_or(0) as u32,
+        };
+
+        Ok(SavedGame {
+            rule: rule.to_string(),
+            board_size,
+            black,
+            white,
+            moves,
+            result,
+            score,
+        })
+    }
+
+    /// 按着法重建局面。结果以棋盘上的连五为准，没有连五时使用存档中的结果
+    /// (例如外部引擎超时判负)
+    pub fn replay(&self) -> GameState {
+        let mut game_state = GameState::new();
+        for m in &self.moves {
+            game_state.board[m.row][m.col] = Some(m.stone);
+            game_state.current_turn = m.stone.opponent();
+        }
+        match check_victory(&game_state) {
+            Some(winner) => {
+                game_state.is_game_over = true;
+                game_state.winner = Some(winner);
+            }
+            None => {
+                game_state.is_game_over = self.result != GameResult::Ongoing;
+                game_state.winner = match self.result {
+                    GameResult::Win(winner) => Some(winner),
+                    _ => None,
+                };
+            }
+        }
+        game_state
+    }
+}
+
+/// 存档、读档 (以及导入、导出棋谱) 结果的提示文字
+#[derive(Component)]
+pub struct SaveStatusText;
+
+// 创建提示文字，位于右侧按钮和引擎信息面板之间
+pub fn setup_save_status(mut commands: Commands) {
+    commands.spawn((
+        TextBundle {
+            text: Text::from_section(
+                "Ctrl+S / Ctrl+L: save / load game, Ctrl+E / Ctrl+I: export / import SGF, \
+                 Ctrl+P / Ctrl+O: export / replay PSQ, Ctrl+C / Ctrl+V: copy / paste position",
+                TextStyle {
+                    font_size: 16.0,
+                    color: Color::rgb(0.4, 0.4, 0.4),
+                    ..default()
+                },
+            ),
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(780.0),
+                top: Val::Px(392.0),
+                width: Val::Px(400.0),
+                ..default()
+            },
+            ..default()
+        },
+        SaveStatusText,
+    ));
+}
+
+/// 显示提示文字，同时打印到终端
+pub fn set_status(query: &mut Query<&mut Text, With<SaveStatusText>>, message: String) {
+    println!("{}", message);
+    for mut text in query.iter_mut() {
+        text.sections[0].value = message.clone();
+    }
+}
+
+/// 是否按下了 Ctrl + key
+pub fn ctrl_pressed(keys: &Input<KeyCode>, key: KeyCode) -> bool {
+    keys.just_pressed(key) && keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+}
+
+/// 系统：按 Ctrl+S 把当前对局保存到 SAVE_FILE
+pub fn save_game(
+    keys: Res<Input<KeyCode>>,
+    game_state: Res<GameState>,
+    log: Res<MoveLog>,
+    ai: Res<SmartAgent>,
+    settings: Res<Settings>,
+    player_score: Res<PlayerScore>,
+    mut status_query: Query<&mut Text, With<SaveStatusText>>,
+) {
+    if !ctrl_pressed(&keys, KeyCode::S) {
+        return;
+    }
+    let path = Path::new(SAVE_FILE);
+    let message = match SavedGame::capture(&game_state, &log, &ai, &settings, &player_score)
+        .and_then(|saved| saved.save(path).map(|()| saved))
+    {
+        Ok(saved) => format!("Saved {} moves to {}", saved.moves.len(), SAVE_FILE),
+        Err(e) => format!("Save failed: {}", e),
+    };
+    set_status(&mut status_query, message);
+}
+
+/// 系统：按 Ctrl+L 读取 SAVE_FILE，重建棋盘和棋子，恢复双方设置和玩家评分
+#[allow(clippy::too_many_arguments)]
+pub fn load_game(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    mut game_state: ResMut<GameState>,
+    mut log: ResMut<MoveLog>,
+    mut ai: ResMut<SmartAgent>,
+    settings: Res<Settings>,
+    mut player_score: ResMut<PlayerScore>,
+    mut review: ResMut<MoveReview>,
+    mut profile: ResMut<PlayerProfile>,
+    mut adaptive: ResMut<AdaptiveDifficulty>,
+    stone_query: Query<Entity, With<StoneComponent>>,
+    mut status_query: Query<&mut Text, With<SaveStatusText>>,
+) {
+    if !ctrl_pressed(&keys, KeyCode::L) {
+        return;
+    }
+    let saved = match SavedGame::load(Path::new(SAVE_FILE)) {
+        Ok(saved) => saved,
+        Err(e) => {
+            set_status(&mut status_query, format!("Load failed: {}", e));
+            return;
+        }
+    };
+    // 本程序总是一方为玩家、一方为 AI
+    let (ai_stone, opponent) = match (saved.black.is_human(), saved.white.is_human()) {
+        (true, false) => (Stone::White, &saved.white),
+        (false, true) => (Stone::Black, &saved.black),
+        _ => {
+            set_status(
+                &mut status_query,
+                "Load failed: the game must have one human and one AI player".to_string(),
+            );
+            return;
+        }
+    };
+
+    let mut note = String::new();
+    ai.set_stone(ai_stone);
+    match opponent {
+        Player::Ai {
+            level,
+            depth,
+            weights,
+        } => {
+            match level {
+                Some(level) => ai.set_skill_level(*level),
+                None => ai.set_depth(*depth),
+            }
+            // 总是使用存档中的权重，之前读取的存档可能换过权重
+            ai.set_weights(EvalWeights::load_or_default(weights));
+        }
+        Player::Engine { path } => {
+            if settings.engine.path.as_ref() != Some(path) {
+                note = format!(" (saved against engine {})", path.display());
+            }
+        }
+        Player::Human => {}
+    }
+
+    restore_game(
+        &mut commands,
+        &saved,
+        &ai,
+        &mut game_state,
+        &mut log,
+        &mut player_score,
+        &mut review,
+        &mut profile,
+        &mut adaptive,
+        &stone_query,
+    );
+
+    set_status(
+        &mut status_query,
+        format!(
+            "Loaded {} moves from {}{}",
+            saved.moves.len(),
+            SAVE_FILE,
+            note
+        ),
+    );
+}
+
+/// 在界面上恢复 saved 中的对局：重建棋子实体、局面、落子记录和玩家评分。
+/// 最后一步在后台用 ai 的权重重新评价，评估条从恢复的局面开始
+#[allow(clippy::too_many_arguments)]
+pub fn restore_game(
+    commands: &mut Commands,
+    saved: &SavedGame,
+    ai: &SmartAgent,
+    game_state: &mut GameState,
+    log: &mut MoveLog,
+    player_score: &mut PlayerScore,
+    review: &mut MoveReview,
+    profile: &mut PlayerProfile,
+    adaptive: &mut AdaptiveDifficulty,
+    stone_query: &Query<Entity, With<StoneComponent>>,
+) {
+    for entity in stone_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    for m in &saved.moves {
+        spawn_stone(commands, m.row, m.col, m.stone);
+    }
+    *game_state = saved.replay();
+    log.moves = saved.moves.clone();
+    *player_score = saved.score.clone();
+    // 存档中没有每步的评价，恢复后不显示之前落子的分类，只评价最后一步供评估条使用
+    review.clear();
+    if let Some((last, earlier)) = saved.moves.split_last() {
+        let before = SavedGame::from_moves(earlier.to_vec(), GameResult::Ongoing).replay();
+        review.grade(ai, &before, (last.row, last.col), last.stone, false);
+    }
+    // 已经结束的对局在当初已经计入档案和自适应难度，不再重复记录
+    if game_state.is_game_over {
+        profile.skip_game();
+        adaptive.skip_game();
+    }
+}
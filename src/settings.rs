@@ -0,0 +1,137 @@
+use crate::config::ConfigFile;
+use crate::profile::DEFAULT_PROFILE;
+use crate::skill::DEFAULT_SKILL_LEVEL;
+use crate::weights::WEIGHTS_FILE;
+use bevy::prelude::*;
+use std::path::{Path, PathBuf};
+
+/// 设置文件的默认位置 (程序工作目录下)
+pub const SETTINGS_FILE: &str = "gobang.toml";
+/// 每局默认可用的提示次数
+pub const DEFAULT_HINTS_PER_GAME: u32 = 3;
+/// 自适应难度默认的玩家目标胜率
+pub const DEFAULT_TARGET_WIN_RATE: f32 = 0.5;
+
+/// 外部引擎的设置 (对应设置文件中的 `[engine]` 段)
+#[derive(Debug, Clone)]
+pub struct EngineSettings {
+    pub path: Option<PathBuf>, // 引擎可执行文件，未设置时使用内置 AI
+    pub timeout_turn_ms: u64,  // 每步时间上限
+    pub timeout_match_ms: u64, // 整局时间上限，0 表示不限制
+    pub start_timeout_ms: u64, // 等待引擎回复 START 的时间
+    pub grace_ms: u64,         // 超过时间上限后额外等待的时间，超出则判引擎超时
+}
+
+impl Default for EngineSettings {
+    fn default() -> Self {
+        EngineSettings {
+            path: None,
+            timeout_turn_ms: 5_000,
+            timeout_match_ms: 0,
+            start_timeout_ms: 10_000,
+            grace_ms: 1_000,
+        }
+    }
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct Settings {
+    pub engine: EngineSettings,
+    pub weights_path: PathBuf,   // 内置 AI 的评估权重文件 (`[ai] weights`)
+    pub book: Option<PathBuf>,   // 内置 AI 的开局库，RenLib 棋谱库文件 (`[ai] book`)
+    pub skill_level: u32,        // 内置 AI 的初始难度等级 (`[ai] skill`，1-10)
+    pub ponder: bool,            // 内置 AI 是否在玩家思考时后台思考 (`[ai] ponder`)
+    pub adaptive: bool,          // 是否根据玩家的表现自动调整难度 (`[ai] adaptive`)
+    pub target_win_rate: f32,    // 自适应难度的玩家目标胜率 (`[ai] target_win_rate`)
+    pub hints_per_game: u32,     // 每局可用的提示次数 (`[game] hints`)
+    pub profile: String,         // 启动时使用的玩家档案 (`[game] profile`)
+    pub show_engine_panel: bool, // 是否一开始就显示引擎信息面板 (`[ui] engine_panel`)
+    pub show_threats: bool,      // 是否一开始就显示威胁叠加层 (`[ui] threats`)
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            engine: EngineSettings::default(),
+            weights_path: PathBuf::from(WEIGHTS_FILE),
+            book: None,
+            skill_level: DEFAULT_SKILL_LEVEL,
+            ponder: false,
+            adaptive: false,
+            target_win_rate: DEFAULT_TARGET_WIN_RATE,
+            hints_per_game: DEFAULT_HINTS_PER_GAME,
+            profile: DEFAULT_PROFILE.to_string(),
+            show_engine_panel: false,
+            show_threats: false,
+        }
+    }
+}
+
+impl Settings {
+    /// 读取设置文件，缺失的项使用默认值
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let config = ConfigFile::load(path)?;
+        let defaults = EngineSettings::default();
+
+        let engine = EngineSettings {
+            path: config
+                .get_str("engine.path")
+                .filter(|p| !p.is_empty())
+                .map(PathBuf::from),
+            timeout_turn_ms: config
+                .get_u64("engine.timeout_turn_ms")
+                .unwrap_or(defaults.timeout_turn_ms),
+            timeout_match_ms: config
+                .get_u64("engine.timeout_match_ms")
+                .unwrap_or(defaults.timeout_match_ms),
+            start_timeout_ms: config
+                .get_u64("engine.start_timeout_ms")
+                .unwrap_or(defaults.start_timeout_ms),
+            grace_ms: config
+                .get_u64("engine.grace_ms")
+                .unwrap_or(defaults.grace_ms),
+        };
+
+        let weights_path = config
+            .get_str("ai.weights")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(WEIGHTS_FILE));
+
+        let skill_level = config
+            .get_u64("ai.skill")
+            .map_or(DEFAULT_SKILL_LEVEL, |level| level as u32);
+
+        Ok(Settings {
+            engine,
+            weights_path,
+            book: config
+                .get_str("ai.book")
+                .filter(|p| !p.is_empty())
+                .map(PathBuf::from),
+            skill_level,
+            ponder: config.get_bool("ai.ponder").unwrap_or(false),
+            adaptive: config.get_bool("ai.adaptive").unwrap_or(false),
+            target_win_rate: config
+                .get_f32("ai.target_win_rate")
+                .unwrap_or(DEFAULT_TARGET_WIN_RATE),
+            hints_per_game: config
+                .get_u64("game.hints")
+                .map_or(DEFAULT_HINTS_PER_GAME, |hints| hints as u32),
+            profile: config
+                .get_str("game.profile")
+                .filter(|name| !name.is_empty())
+                .unwrap_or(DEFAULT_PROFILE)
+                .to_string(),
+            show_engine_panel: config.get_bool("ui.engine_panel").unwrap_or(false),
+            show_threats: config.get_bool("ui.threats").unwrap_or(false),
+        })
+    }
+
+    /// 读取默认位置的设置文件，出错时打印原因并使用默认设置
+    pub fn load_or_default() -> Self {
+        Self::load(Path::new(SETTINGS_FILE)).unwrap_or_else(|e| {
+            eprintln!("读取设置失败，使用默认设置: {}", e);
+            Settings::default()
+        })
+    }
+}
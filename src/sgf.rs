@@ -0,0 +1,678 @@
+use crate::adaptive::AdaptiveDifficulty;
+use crate::agent::SmartAgent;
+use crate::game::{GRID_SIZE, GameState, PlayerScore, Stone, StoneComponent, win_rate_loss};
+use crate::game_manager::check_victory;
+use crate::profile::PlayerProfile;
+use crate::review::MoveReview;
+use crate::savegame::{
+    FREESTYLE_RULE, GameResult, MoveLog, SaveStatusText, SavedGame, TimedMove, ctrl_pressed,
+    restore_game, set_status,
+};
+use crate::settings::Settings;
+use bevy::prelude::*;
+use std::fs;
+use std::path::Path;
+
+/// 导入、导出 SGF 棋谱的默认文件 (程序工作目录下)
+pub const SGF_FILE: &str = "gobang.sgf";
+/// SGF 中五子棋 (Gomoku+Renju) 的游戏编号
+const GAME_GOMOKU: &str = "4";
+const BOARD_SIZE: usize = GRID_SIZE + 1;
+
+/// SGF 的一个节点：按出现顺序的属性，每个属性可以有多个值
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SgfNode {
+    pub properties: Vec<(String, Vec<String>)>,
+}
+
+impl SgfNode {
+    /// 属性的第一个值
+    pub fn get(&self, id: &str) -> Option<&str> {
+        self.properties
+            .iter()
+            .find(|(name, _)| name == id)
+            .and_then(|(_, values)| values.first())
+            .map(String::as_str)
+    }
+
+    pub fn has(&self, id: &str) -> bool {
+        self.properties.iter().any(|(name, _)| name == id)
+    }
+
+    pub fn set(&mut self, id: &str, value: impl Into<String>) {
+        self.properties.push((id.to_string(), vec![value.into()]));
+    }
+}
+
+/// SGF 的一棵 (子) 树：一串节点，最后一个节点之后分出的变化
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SgfTree {
+    pub nodes: Vec<SgfNode>,
+    pub children: Vec<SgfTree>,
+}
+
+impl SgfTree {
+    /// 写成 SGF 文本
+    pub fn to_sgf(&self) -> String {
+        let mut text = String::from("(");
+        for node in &self.nodes {
+            text.push(';');
+            for (id, values) in &node.properties {
+                text.push_str(id);
+                for value in values {
+                    text.push('[');
+                    text.push_str(&escape(value));
+                    text.push(']');
+                }
+            }
+        }
+        for child in &self.children {
+            text.push_str(&child.to_sgf());
+        }
+        text.push(')');
+        text
+    }
+
+    /// 从根到每个叶子的节点序列，第一条为主线 (总是选第一个变化)
+    fn lines(&self) -> Vec<Vec<&SgfNode>> {
+        let own: Vec<&SgfNode> = self.nodes.iter().collect();
+        if self.children.is_empty() {
+            return vec![own];
+        }
+        let mut lines = Vec::new();
+        for child in &self.children {
+            for tail in child.lines() {
+                let mut line = own.clone();
+                line.extend(tail);
+                lines.push(line);
+            }
+        }
+        lines
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace(']', "\\]")
+}
+
+/// 解析 SGF 文本中的所有棋谱 (一个文件可以包含多局)
+pub fn parse_sgf(text: &str) -> Result<Vec<SgfTree>, String> {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        pos: 0,
+    };
+    let mut trees = Vec::new();
+    parser.skip_whitespace();
+    while parser.peek().is_some() {
+        trees.push(parser.tree()?);
+        parser.skip_whitespace();
+    }
+    if trees.is_empty() {
+        return Err("no game found".to_string());
+    }
+    Ok(trees)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(c) => Err(format!(
+                "offset {}: expected '{}', found '{}'",
+                self.pos, expected, c
+            )),
+            None => Err(format!("unexpected end of file, expected '{}'", expected)),
+        }
+    }
+
+    // GameTree = "(" Sequence { GameTree } ")"
+    fn tree(&mut self) -> Result<SgfTree, String> {
+        self.expect('(')?;
+        let mut tree = SgfTree::default();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some(';') => {
+                    self.pos += 1;
+                    tree.nodes.push(self.node()?);
+                }
+                Some('(') => tree.children.push(self.tree()?),
+                Some(')') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(c) => return Err(format!("offset {}: unexpected '{}'", self.pos, c)),
+                None => return Err("unexpected end of file, expected ')'".to_string()),
+            }
+        }
+        if tree.nodes.is_empty() {
+            return Err("empty game tree".to_string());
+        }
+        Ok(tree)
+    }
+
+    // Node = ";" { PropIdent PropValue { PropValue } }
+    fn node(&mut self) -> Result<SgfNode, String> {
+        let mut node = SgfNode::default();
+        loop {
+            self.skip_whitespace();
+            let start = self.pos;
+            let mut id = String::new();
+            while let Some(c) = self.peek().filter(char::is_ascii_alphabetic) {
+                // FF[3] 允许属性名中的小写字母，只保留大写部分
+                if c.is_ascii_uppercase() {
+                    id.push(c);
+                }
+                self.pos += 1;
+            }
+            if self.pos == start {
+                return Ok(node);
+            }
+            let mut values = Vec::new();
+            self.skip_whitespace();
+            while self.peek() == Some('[') {
+                values.push(self.value()?);
+                self.skip_whitespace();
+            }
+            if values.is_empty() {
+                return Err(format!("offset {}: property {} has no value", start, id));
+            }
+            node.properties.push((id, values));
+        }
+    }
+
+    fn value(&mut self) -> Result<String, String> {
+        self.pos += 1; // '['
+        let mut value = String::new();
+        loop {
+            match self.peek() {
+                Some(']') => {
+                    self.pos += 1;
+                    return Ok(value);
+                }
+                Some('\\') => {
+                    self.pos += 1;
+                    match self.peek() {
+                        // 转义的换行是软换行，不属于值
+                        Some('\n') => {}
+                        Some(c) => value.push(c),
+                        None => break,
+                    }
+                    self.pos += 1;
+                }
+                Some(c) => {
+                    value.push(c);
+                    self.pos += 1;
+                }
+                None => break,
+            }
+        }
+        Err("unexpected end of file inside a property value".to_string())
+    }
+}
+
+/// SGF 坐标 (如 "hh") 对应的 (行, 列)。SGF 的第一行在上方，本程序的第 0 行在下方
+pub fn sgf_point(row: usize, col: usize) -> String {
+    let x = (b'a' + col as u8) as char;
+    let y = (b'a' + (GRID_SIZE - row) as u8) as char;
+    format!("{}{}", x, y)
+}
+
+pub fn parse_sgf_point(text: &str) -> Option<(usize, usize)> {
+    let bytes = text.as_bytes();
+    if bytes.len() != 2 {
+        return None;
+    }
+    let col = bytes[0].checked_sub(b'a')? as usize;
+    let y = bytes[1].checked_sub(b'a')? as usize;
+    if col > GRID_SIZE || y > GRID_SIZE {
+        return None;
+    }
+    Some((GRID_SIZE - y, col))
+}
+
+/// 从 SGF 读入的一步棋及其注释
+#[derive(Debug, Clone, PartialEq)]
+pub struct SgfMove {
+    pub row: usize,
+    pub col: usize,
+    pub stone: Stone,
+    pub comment: Option<String>,
+}
+
+/// 一局 GM[4] 棋谱：对局信息和所有变化
+#[derive(Debug, Clone, PartialEq)]
+pub struct SgfGame {
+    pub black: String,
+    pub white: String,
+    pub result: GameResult,
+    pub rule: String,
+    pub comment: Option<String>, // 整局的注释 (GC，或根节点的 C)
+    /// 从开局到每个叶子的着法，第一条为主线
+    pub lines: Vec<Vec<SgfMove>>,
+}
+
+impl SgfGame {
+    /// 从当前对局生成棋谱。落子分类写成每步的注释
+    pub fn from_game(
+        game_state: &GameState,
+        log: &MoveLog,
+        review: &MoveReview,
+        black: &str,
+        white: &str,
+    ) -> Self {
+        let moves = if log.is_stale(game_state) {
+            &[][..]
+        } else {
+            &log.moves[..]
+        };
+        let line = moves
+            .iter()
+            .map(|m| SgfMove {
+                row: m.row,
+                col: m.col,
+                stone: m.stone,
+                comment: review
+                    .moves
+                    .iter()
+                    .find(|r| (r.row, r.col, r.stone) == (m.row, m.col, m.stone))
+                    .map(|r| {
+                        format!(
+                            "{}: -{:.1}% win rate",
+                            r.class.name(),
+                            win_rate_loss(r.best_score, r.score)
+                        )
+                    }),
+            })
+            .collect();
+        SgfGame {
+            black: black.to_string(),
+            white: white.to_string(),
+            result: GameResult::of(game_state),
+            rule: FREESTYLE_RULE.to_string(),
+            comment: None,
+            lines: vec![line],
+        }
+    }
+
+    /// 写成 SGF 树，每条变化在与主线分开处分叉
+    pub fn to_tree(&self) -> SgfTree {
+        let mut root = SgfNode::default();
+        root.set("GM", GAME_GOMOKU);
+        root.set("FF", "4");
+        root.set("CA", "UTF-8");
+        root.set("AP", "gobang");
+        root.set("SZ", BOARD_SIZE.to_string());
+        root.set("RU", self.rule.clone());
+        root.set("PB", self.black.clone());
+        root.set("PW", self.white.clone());
+        if let Some(result) = result_property(self.result, self.lines.first()) {
+            root.set("RE", result);
+        }
+        if let Some(comment) = &self.comment {
+            root.set("GC", comment.clone());
+        }
+
+        let mut tree = SgfTree {
+            nodes: vec![root],
+            children: Vec::new(),
+        };
+        for line in &self.lines {
+            insert_line(&mut tree, line);
+        }
+        tree
+    }
+
+    pub fn to_sgf(&self) -> String {
+        self.to_tree().to_sgf() + "\n"
+    }
+
+    /// 读取 GM[4] 棋谱。不是五子棋、棋盘不是 15 路、含有摆子 (AB/AW/AE)、
+    /// 或着法不是黑白交替的棋谱不能导入
+    pub fn from_tree(tree: &SgfTree) -> Result<Self, String> {
+        let root = &tree.nodes[0];
+        if let Some(game) = root.get("GM")
+            && game.trim() != GAME_GOMOKU
+        {
+            return Err(format!("GM[{}] is not a gomoku record", game));
+        }
+        if let Some(size) = root.get("SZ") {
+            let square = format!("{}:{}", BOARD_SIZE, BOARD_SIZE);
+            if size.trim() != BOARD_SIZE.to_string() && size.trim() != square {
+                return Err(format!("unsupported board size SZ[{}]", size));
+            }
+        }
+
+        let mut lines = Vec::new();
+        for (index, nodes) in tree.lines().into_iter().enumerate() {
+            let line = read_line(&nodes).map_err(|e| match index {
+                0 => format!("main line: {}", e),
+                _ => format!("variation {}: {}", index, e),
+            })?;
+            // 只有注释而没有着法的变化 (如根节点后的说明) 不单独列出
+            if index == 0 || !line.is_empty() {
+                lines.push(line);
+            }
+        }
+
+        let text = |id: &str| root.get(id).map(|s| s.trim().to_string());
+        Ok(SgfGame {
+            black: text("PB").unwrap_or_default(),
+            white: text("PW").unwrap_or_default(),
+            result: text("RE")
+                .as_deref()
+                .map_or(GameResult::Ongoing, parse_result),
+            rule: text("RU").unwrap_or_else(|| FREESTYLE_RULE.to_string()),
+            comment: text("GC").or_else(|| text("C")),
+            lines,
+        })
+    }
+
+    /// 第 index 条变化对应的对局。结果只属于主线，变化的结局由棋盘判断
+    pub fn saved_game(&self, index: usize) -> SavedGame {
+        let moves = self.lines[index]
+            .iter()
+            .map(|m| TimedMove {
+                row: m.row,
+                col: m.col,
+                stone: m.stone,
+                time: 0, // SGF 没有每步的落子时间
+            })
+            .collect();
+        let result = if index == 0 {
+            self.result
+        } else {
+            GameResult::Ongoing
+        };
+        SavedGame::from_moves(moves, result)
+    }
+}
+
+/// 检查一条变化的着法：黑先、交替落子、不落在已有棋子上
+fn read_line(nodes: &[&SgfNode]) -> Result<Vec<SgfMove>, String> {
+    let mut line: Vec<SgfMove> = Vec::new();
+    let mut expected = Stone::Black;
+    for node in nodes {
+        if ["AB", "AW", "AE"].iter().any(|id| node.has(id)) {
+            return Err("setup stones (AB/AW/AE) are not supported".to_string());
+        }
+        let (stone, point) = match (node.get("B"), node.get("W")) {
+            (Some(point), None) => (Stone::Black, point),
+            (None, Some(point)) => (Stone::White, point),
+            (None, None) => continue,
+            (Some(_), Some(_)) => return Err("a node has both B and W".to_string()),
+        };
+        if stone != expected {
+            return Err(format!(
+                "move {}: expected {:?} to move",
+                line.len() + 1,
+                expected
+            ));
+        }
+        let (row, col) = parse_sgf_point(point)
+            .ok_or_else(|| format!("move {}: bad point [{}]", line.len() + 1, point))?;
+        if line.iter().any(|m| (m.row, m.col) == (row, col)) {
+            return Err(format!(
+                "move {}: [{}] is already occupied",
+                line.len() + 1,
+                point
+            ));
+        }
+        line.push(SgfMove {
+            row,
+            col,
+            stone,
+            comment: node.get("C").map(|c| c.trim().to_string()),
+        });
+        expected = expected.opponent();
+    }
+    Ok(line)
+}
+
+/// 把一条变化挂到树上：沿已有的相同着法往下走，在第一步不同处分叉
+fn insert_line(tree: &mut SgfTree, line: &[SgfMove]) {
+    let node = |m: &SgfMove| {
+        let mut node = SgfNode::default();
+        let id = match m.stone {
+            Stone::Black => "B",
+            Stone::White => "W",
+        };
+        node.set(id, sgf_point(m.row, m.col));
+        if let Some(comment) = &m.comment {
+            node.set("C", comment.clone());
+        }
+        node
+    };
+    let same = |node: &SgfNode, m: &SgfMove| {
+        let id = match m.stone {
+            Stone::Black => "B",
+            Stone::White => "W",
+        };
+        node.get(id) == Some(sgf_point(m.row, m.col).as_str())
+    };
+
+    // tree 的第一个节点是根 (或上一层已经匹配的着法)，之后的节点依次对应 line 中的着法
+    let mut matched = 0;
+    let mut i = 1;
+    while i < tree.nodes.len() && matched < line.len() && same(&tree.nodes[i], &line[matched]) {
+        i += 1;
+        matched += 1;
+    }
+    if matched == line.len() {
+        return; // 已有的变化包含了这一条
+    }
+    if i < tree.nodes.len() {
+        // 在节点序列中间分叉：把剩余部分移到第一个子树
+        let rest = SgfTree {
+            nodes: tree.nodes.split_off(i),
+            children: std::mem::take(&mut tree.children),
+        };
+        tree.children.push(rest);
+    } else if tree.children.is_empty() {
+        tree.nodes.extend(line[matched..].iter().map(node));
+        return;
+    }
+    let Some(next) = line.get(matched) else {
+        return;
+    };
+    match tree
+        .children
+        .iter_mut()
+        .find(|child| same(&child.nodes[0], next))
+    {
+        Some(child) => {
+            let mut sub = std::mem::take(child);
+            // 子树的第一个节点已经匹配 next，从它之后继续
+            insert_line(&mut sub, &line[matched + 1..]);
+            *child = sub;
+        }
+        None => tree.children.push(SgfTree {
+            nodes: line[matched..].iter().map(node).collect(),
+            children: Vec::new(),
+        }),
+    }
+}
+
+/// RE 属性：连五获胜为 "B+"/"W+"，没有连五的胜负 (如引擎超时) 为 "B+F"/"W+F"，和棋为 "0"
+fn result_property(result: GameResult, main_line: Option<&Vec<SgfMove>>) -> Option<String> {
+    let winner = match result {
+        GameResult::Ongoing => return None,
+        GameResult::Draw => return Some("0".to_string()),
+        GameResult::Win(winner) => winner,
+    };
+    let mut game_state = GameState::new();
+    for m in main_line.into_iter().flatten() {
+        game_state.board[m.row][m.col] = Some(m.stone);
+    }
+    let side = match winner {
+        Stone::Black => "B",
+        Stone::White => "W",
+    };
+    let five = check_victory(&game_state) == Some(winner);
+    Some(format!("{}+{}", side, if five { "" } else { "F" }))
+}
+
+fn parse_result(text: &str) -> GameResult {
+    match text.chars().next() {
+        Some('B' | 'b') => GameResult::Win(Stone::Black),
+        Some('W' | 'w') => GameResult::Win(Stone::White),
+        Some('0') | Some('D' | 'd') => GameResult::Draw,
+        _ => GameResult::Ongoing,
+    }
+}
+
+/// 读入的 SGF 棋谱，以及当前显示的是哪一条变化
+#[derive(Resource, Default)]
+pub struct SgfImport {
+    game: Option<SgfGame>,
+    line: usize,
+    // 显示这条变化后的局面；棋盘没有变化时再按 Ctrl+I 切换到下一条变化
+    board: Option<[[Option<Stone>; GRID_SIZE + 1]; GRID_SIZE + 1]>,
+}
+
+/// 棋谱中这一方的名字：玩家为档案名，AI 为引擎文件名或难度
+pub fn player_name(
+    is_ai: bool,
+    ai: &SmartAgent,
+    settings: &Settings,
+    profile: &PlayerProfile,
+) -> String {
+    if !is_ai {
+        return profile.name.clone();
+    }
+    match (&settings.engine.path, ai.skill_level()) {
+        (Some(path), _) => path
+            .file_stem()
+            .map_or("engine".to_string(), |s| s.to_string_lossy().into_owned()),
+        (None, Some(level)) => format!("Gobang AI (level {})", level),
+        (None, None) => format!("Gobang AI (depth {})", ai.get_depth()),
+    }
+}
+
+/// 系统：按 Ctrl+E 把当前对局 (已结束或进行中) 导出为 SGF_FILE
+#[allow(clippy::too_many_arguments)]
+pub fn export_sgf(
+    keys: Res<Input<KeyCode>>,
+    game_state: Res<GameState>,
+    log: Res<MoveLog>,
+    review: Res<MoveReview>,
+    ai: Res<SmartAgent>,
+    settings: Res<Settings>,
+    profile: Res<PlayerProfile>,
+    mut status_query: Query<&mut Text, With<SaveStatusText>>,
+) {
+    if !ctrl_pressed(&keys, KeyCode::E) {
+        return;
+    }
+    let black = player_name(ai.get_stone() == Stone::Black, &ai, &settings, &profile);
+    let white = player_name(ai.get_stone() == Stone::White, &ai, &settings, &profile);
+    let game = SgfGame::from_game(&game_state, &log, &review, &black, &white);
+    let message = match fs::write(SGF_FILE, game.to_sgf()) {
+        Ok(()) => format!("Exported {} moves to {}", game.lines[0].len(), SGF_FILE),
+        Err(e) => format!("Export failed: {}: {}", SGF_FILE, e),
+    };
+    set_status(&mut status_query, message);
+}
+
+/// 系统：按 Ctrl+I 从 SGF_FILE 导入棋谱的主线 (文件中有多局时取第一局)。
+/// 导入后棋盘没有变化时再按 Ctrl+I 依次显示其他变化
+#[allow(clippy::too_many_arguments)]
+pub fn import_sgf(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    mut import: ResMut<SgfImport>,
+    ai: Res<SmartAgent>,
+    mut game_state: ResMut<GameState>,
+    mut log: ResMut<MoveLog>,
+    mut player_score: ResMut<PlayerScore>,
+    mut review: ResMut<MoveReview>,
+    mut profile: ResMut<PlayerProfile>,
+    mut adaptive: ResMut<AdaptiveDifficulty>,
+    stone_query: Query<Entity, With<StoneComponent>>,
+    mut status_query: Query<&mut Text, With<SaveStatusText>>,
+) {
+    if !ctrl_pressed(&keys, KeyCode::I) {
+        return;
+    }
+    let next_line = import.game.is_some() && import.board == Some(game_state.board);
+    if next_line {
+        let count = import.game.as_ref().map_or(1, |game| game.lines.len());
+        import.line = (import.line + 1) % count;
+    } else {
+        let parsed = fs::read_to_string(Path::new(SGF_FILE))
+            .map_err(|e| e.to_string())
+            .and_then(|text| parse_sgf(&text))
+            .and_then(|trees| SgfGame::from_tree(&trees[0]));
+        match parsed {
+            Ok(game) => {
+                import.game = Some(game);
+                import.line = 0;
+            }
+            Err(e) => {
+                set_status(
+                    &mut status_query,
+                    format!("Import failed: {}: {}", SGF_FILE, e),
+                );
+                return;
+            }
+        }
+    }
+    let Some(game) = &import.game else {
+        return;
+    };
+
+    let saved = game.saved_game(import.line);
+    restore_game(
+        &mut commands,
+        &saved,
+        &ai,
+        &mut game_state,
+        &mut log,
+        &mut player_score,
+        &mut review,
+        &mut profile,
+        &mut adaptive,
+        &stone_query,
+    );
+
+    let line = &game.lines[import.line];
+    let mut message = format!(
+        "Imported {} vs {} ({}), line {}/{}: {} moves",
+        game.black,
+        game.white,
+        game.result.notation(),
+        import.line + 1,
+        game.lines.len(),
+        line.len()
+    );
+    if game.lines.len() > 1 {
+        message.push_str("\nCtrl+I again: next variation");
+    }
+    if game.rule != FREESTYLE_RULE {
+        message.push_str(&format!("\nRule {} is played as freestyle", game.rule));
+    }
+    if let Some(comment) = line.last().and_then(|m| m.comment.as_ref()) {
+        message.push_str(&format!("\n{}", comment));
+    }
+    import.board = Some(game_state.board);
+    set_status(&mut status_query, message);
+}
@@ -0,0 +1,132 @@
+/// 一个难度等级对应的 AI 参数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SkillLevel {
+    pub level: u32,
+    pub name: &'static str,
+    pub depth: u32,                 // 搜索深度
+    pub time_limit_ms: Option<u64>, // 每步思考时间上限，设置后改为迭代加深
+    pub move_radius: usize,         // 只考虑距离已有棋子这么远以内的空位
+    pub eval_noise: i32,            // 叶节点评估值加上 ±eval_noise 的随机扰动
+    pub blunder_chance: f64,        // 每步随手乱下的概率
+}
+
+pub const MIN_SKILL_LEVEL: u32 = 1;
+pub const MAX_SKILL_LEVEL: u32 = 10;
+// 默认等级与原先 "深度 3" 的强度相同
+pub const DEFAULT_SKILL_LEVEL: u32 = 7;
+
+// AI 等级的名义等级分没有经过对局校准，只是约定的标度：1 级记为 1000，
+// 每高一级加 100，即假定每一级对低一级的期望得分约为 64%。
+// 玩家档案中的评分是相对这个标度的，不能与其他网站或程序的等级分比较。
+// 两个等级的实际差距可以用 `gobang-match --a skill=N --b skill=M` 测量
+pub const NOMINAL_RATING_BASE: f64 = 1000.0;
+pub const NOMINAL_RATING_STEP: f64 = 100.0;
+
+// 从弱到强排列。低等级主要靠噪声和失误削弱，高等级靠更深的搜索加强；
+// 深度 4 以上单步耗时明显变长，因此用时间上限约束
+pub const SKILL_LEVELS: [SkillLevel; MAX_SKILL_LEVEL as usize] = [
+    SkillLevel {
+        level: 1,
+        name: "Novice",
+        depth: 1,
+        time_limit_ms: None,
+        move_radius: 1,
+        eval_noise: 2000,
+        blunder_chance: 0.35,
+    },
+    SkillLevel {
+        level: 2,
+        name: "Beginner",
+        depth: 1,
+        time_limit_ms: None,
+        move_radius: 1,
+        eval_noise: 1000,
+        blunder_chance: 0.25,
+    },
+    SkillLevel {
+        level: 3,
+        name: "Casual",
+        depth: 1,
+        time_limit_ms: None,
+        move_radius: 2,
+        eval_noise: 500,
+        blunder_chance: 0.15,
+    },
+    SkillLevel {
+        level: 4,
+        name: "Amateur",
+        depth: 2,
+        time_limit_ms: None,
+        move_radius: 2,
+        eval_noise: 300,
+        blunder_chance: 0.10,
+    },
+    SkillLevel {
+        level: 5,
+        name: "Intermediate",
+        depth: 2,
+        time_limit_ms: None,
+        move_radius: 2,
+        eval_noise: 100,
+        blunder_chance: 0.05,
+    },
+    SkillLevel {
+        level: 6,
+        name: "Club",
+        depth: 3,
+        time_limit_ms: None,
+        move_radius: 2,
+        eval_noise: 50,
+        blunder_chance: 0.02,
+    },
+    SkillLevel {
+        level: 7,
+        name: "Advanced",
+        depth: 3,
+        time_limit_ms: None,
+        move_radius: 2,
+        eval_noise: 0,
+        blunder_chance: 0.0,
+    },
+    SkillLevel {
+        level: 8,
+        name: "Expert",
+        depth: 4,
+        time_limit_ms: Some(1000),
+        move_radius: 2,
+        eval_noise: 0,
+        blunder_chance: 0.0,
+    },
+    SkillLevel {
+        level: 9,
+        name: "Master",
+        depth: 6,
+        time_limit_ms: Some(2000),
+        move_radius: 2,
+        eval_noise: 0,
+        blunder_chance: 0.0,
+    },
+    SkillLevel {
+        level: 10,
+        name: "Grandmaster",
+        depth: 8,
+        time_limit_ms: Some(3000),
+        move_radius: 2,
+        eval_noise: 0,
+        blunder_chance: 0.0,
+    },
+];
+
+impl SkillLevel {
+    /// 按等级查找参数，超出范围时取最接近的等级
+    pub fn get(level: u32) -> &'static SkillLevel {
+        let level = level.clamp(MIN_SKILL_LEVEL, MAX_SKILL_LEVEL);
+        &SKILL_LEVELS[(level - MIN_SKILL_LEVEL) as usize]
+    }
+
+    /// 计算玩家档案评分时这个等级的名义等级分 (Glicko 标度)：
+    /// 1 级为 NOMINAL_RATING_BASE，每级加 NOMINAL_RATING_STEP
+    pub fn nominal_rating(&self) -> f64 {
+        NOMINAL_RATING_BASE + NOMINAL_RATING_STEP * (self.level - MIN_SKILL_LEVEL) as f64
+    }
+}
@@ -0,0 +1,117 @@
+use crate::game::{GRID_SIZE, GameState, Stone};
+
+const BOARD_SIZE: usize = GRID_SIZE + 1;
+// VCF 搜索中攻方最多连续冲四的次数
+const MAX_VCF_DEPTH: u32 = 12;
+// VCF 搜索最多尝试的冲四数，超出时按没有找到处理
+const MAX_VCF_NODES: u32 = 20_000;
+// 检查方向：水平、垂直、主对角线、副对角线
+const DIRECTIONS: [(isize, isize); 4] = [(0, 1), (1, 0), (1, 1), (1, -1)];
+
+/// stone 落子后能连成五子的空位 (成五点)。对手有成五点时必须立即封堵
+pub fn five_points(game_state: &GameState, stone: Stone) -> Vec<(usize, usize)> {
+    window_points(game_state, stone, 4)
+}
+
+/// stone 落子后能形成四 (下一步出现成五点) 的空位，即冲四或活四的落点
+pub fn four_points(game_state: &GameState, stone: Stone) -> Vec<(usize, usize)> {
+    window_points(game_state, stone, 3)
+}
+
+/// 轮到 stone 落子时搜索连续冲四取胜 (VCF)：每一步都冲四，对手只能封堵，直到连五。
+/// 找到时返回攻方的落子序列，走完后对手无法阻止连五；找不到或超出搜索上限时返回 None
+pub fn vcf(game_state: &GameState, stone: Stone) -> Option<Vec<(usize, usize)>> {
+    let mut state = game_state.clone();
+    let mut nodes = 0;
+    let mut line = Vec::new();
+    vcf_search(&mut state, stone, MAX_VCF_DEPTH, &mut nodes, &mut line).then_some(line)
+}
+
+fn vcf_search(
+    state: &mut GameState,
+    stone: Stone,
+    depth: u32,
+    nodes: &mut u32,
+    line: &mut Vec<(usize, usize)>,
+) -> bool {
+    if let Some(&point) = five_points(state, stone).first() {
+        line.push(point);
+        return true;
+    }
+    // 对手有成五点时攻方必须先封堵，不再是连续冲四
+    if depth == 0 || *nodes >= MAX_VCF_NODES || !five_points(state, stone.opponent()).is_empty() {
+        return false;
+    }
+
+    for (r, c) in four_points(state, stone) {
+        *nodes += 1;
+        state.board[r][c] = Some(stone);
+        line.push((r, c));
+        let wins = match five_points(state, stone).as_slice() {
+            [] => false,
+            // 只有一个成五点：对手必须封堵，之后继续冲四
+            &[(br, bc)] => {
+                state.board[br][bc] = Some(stone.opponent());
+                let wins = vcf_search(state, stone, depth - 1, nodes, line);
+                state.board[br][bc] = None;
+                wins
+            }
+            // 活四或双四：对手挡不住
+            _ => true,
+        };
+        state.board[r][c] = None;
+        if wins {
+            return true;
+        }
+        line.pop();
+    }
+    false
+}
+
+/// 收集所有恰好有 count 个 stone 棋子、没有对手棋子的 5 子窗口中的空位
+fn window_points(game_state: &GameState, stone: Stone, count: usize) -> Vec<(usize, usize)> {
+    let mut seen = [[false; BOARD_SIZE]; BOARD_SIZE];
+    let mut points = Vec::new();
+    for r in 0..BOARD_SIZE {
+        for c in 0..BOARD_SIZE {
+            for &(dr, dc) in &DIRECTIONS {
+                // 窗口的结束点必须在棋盘内
+                let end_r = r as isize + 4 * dr;
+                let end_c = c as isize + 4 * dc;
+                if end_r < 0
+                    || end_r >= BOARD_SIZE as isize
+                    || end_c < 0
+                    || end_c >= BOARD_SIZE as isize
+                {
+                    continue;
+                }
+
+                let cells = (0..5).map(|i| {
+                    (
+                        (r as isize + i * dr) as usize,
+                        (c as isize + i * dc) as usize,
+                    )
+                });
+                let mut own = 0;
+                let mut blocked = false;
+                for (cr, cc) in cells.clone() {
+                    match game_state.board[cr][cc] {
+                        Some(s) if s == stone => own += 1,
+                        Some(_) => blocked = true,
+                        None => {}
+                    }
+                }
+                if blocked || own != count {
+                    continue;
+                }
+                for (cr, cc) in cells {
+                    if game_state.board[cr][cc].is_none() && !seen[cr][cc] {
+                        seen[cr][cc] = true;
+                        points.push((cr, cc));
+                    }
+                }
+            }
+        }
+    }
+    points
+}
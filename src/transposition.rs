@@ -0,0 +1,165 @@
+use crate::game::{GRID_SIZE, GameState, Stone};
+use std::sync::OnceLock;
+
+const BOARD_SIZE: usize = GRID_SIZE + 1;
+// 置换表的条目数 (2 的幂，便于用哈希值的低位做索引)
+const TABLE_BITS: u32 = 18;
+// 浅层搜索 (热力图、评价落子、复盘分析只搜几层) 用的小置换表的条目数
+const SMALL_TABLE_BITS: u32 = 14;
+
+type ZobristKeys = [[[u64; 2]; BOARD_SIZE]; BOARD_SIZE];
+
+/// 每个 (行, 列, 颜色) 对应一个随机数，局面的哈希值是所有棋子对应随机数的异或
+fn zobrist_keys() -> &'static ZobristKeys {
+    static KEYS: OnceLock<ZobristKeys> = OnceLock::new();
+    KEYS.get_or_init(|| {
+        // splitmix64，固定种子保证每次运行的哈希值相同
+        let mut seed: u64 = 0x9E37_79B9_7F4A_7C15;
+        let mut next = || {
+            seed = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = seed;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^ (z >> 31)
+        };
+        let mut keys = [[[0; 2]; BOARD_SIZE]; BOARD_SIZE];
+        for row in keys.iter_mut() {
+            for cell in row.iter_mut() {
+                *cell = [next(), next()];
+            }
+        }
+        keys
+    })
+}
+
+/// 在 (r, c) 放上或拿走 stone 时哈希值需要异或的值
+pub fn zobrist_key(r: usize, c: usize, stone: Stone) -> u64 {
+    zobrist_keys()[r][c][stone as usize]
+}
+
+/// 计算整个局面的哈希值
+pub fn zobrist_hash(game_state: &GameState) -> u64 {
+    let mut hash = 0;
+    for r in 0..BOARD_SIZE {
+        for c in 0..BOARD_SIZE {
+            if let Some(stone) = game_state.board[r][c] {
+                hash ^= zobrist_key(r, c, stone);
+            }
+        }
+    }
+    hash
+}
+
+/// 棋盘的对称变换数 (旋转和翻转)
+pub const SYMMETRIES: u8 = 8;
+
+/// 对 (行, 列) 做第 symmetry 种对称变换：第 2 位为沿对角线翻转，第 0、1 位为上下、左右翻转
+pub fn transform_point((row, col): (usize, usize), symmetry: u8) -> (usize, usize) {
+    let (mut row, mut col) = if symmetry & 4 != 0 {
+        (col, row)
+    } else {
+        (row, col)
+    };
+    if symmetry & 1 != 0 {
+        row = GRID_SIZE - row;
+    }
+    if symmetry & 2 != 0 {
+        col = GRID_SIZE - col;
+    }
+    (row, col)
+}
+
+/// transform_point 的逆变换
+pub fn inverse_transform_point((row, col): (usize, usize), symmetry: u8) -> (usize, usize) {
+    let row = if symmetry & 1 != 0 { GRID_SIZE - row } else { row };
+    let col = if symmetry & 2 != 0 { GRID_SIZE - col } else { col };
+    if symmetry & 4 != 0 {
+        (col, row)
+    } else {
+        (row, col)
+    }
+}
+
+/// 局面做第 symmetry 种对称变换之后的哈希值
+pub fn symmetric_hash(game_state: &GameState, symmetry: u8) -> u64 {
+    let mut hash = 0;
+    for r in 0..BOARD_SIZE {
+        for c in 0..BOARD_SIZE {
+            if let Some(stone) = game_state.board[r][c] {
+                let (tr, tc) = transform_point((r, c), symmetry);
+                hash ^= zobrist_key(tr, tc, stone);
+            }
+        }
+    }
+    hash
+}
+
+/// 记录的分数与真实值的关系 (Alpha-Beta 剪枝后分数可能只是一个界)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Bound {
+    Exact, // 精确值
+    Lower, // 发生了剪枝，真实值不低于记录的分数
+    Upper, // 所有落子都不超过 alpha，真实值不高于记录的分数
+}
+
+/// 一个局面的搜索结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TtEntry {
+    pub best_move: (usize, usize),
+    pub depth: u32, // 搜索这个局面时剩余的深度
+    pub score: i32,
+    pub bound: Bound,
+}
+
+#[derive(Clone, Copy)]
+struct Slot {
+    hash: u64,
+    entry: TtEntry,
+}
+
+/// 置换表：记录搜索过的局面的分数和最佳落子。同一局面经由不同落子顺序再次出现时
+/// 可以直接使用记录的分数，最佳落子则用于之后的落子排序。
+pub struct TranspositionTable {
+    slots: Vec<Option<Slot>>,
+}
+
+impl TranspositionTable {
+    pub fn new() -> Self {
+        Self::with_bits(TABLE_BITS)
+    }
+
+    /// 只搜索几层的小置换表。对局用的置换表有几 MB，每次分配都要清零，
+    /// 对每个空位各搜一次的热力图等用它就慢得多
+    pub fn small() -> Self {
+        Self::with_bits(SMALL_TABLE_BITS)
+    }
+
+    fn with_bits(bits: u32) -> Self {
+        TranspositionTable {
+            slots: vec![None; 1 << bits],
+        }
+    }
+
+    fn index(&self, hash: u64) -> usize {
+        (hash & (self.slots.len() as u64 - 1)) as usize
+    }
+
+    /// 查询局面的搜索结果
+    pub fn probe(&self, hash: u64) -> Option<TtEntry> {
+        self.slots[self.index(hash)]
+            .filter(|slot| slot.hash == hash)
+            .map(|slot| slot.entry)
+    }
+
+    /// 记录局面的搜索结果，总是覆盖旧条目
+    pub fn store(&mut self, hash: u64, entry: TtEntry) {
+        let index = self.index(hash);
+        self.slots[index] = Some(Slot { hash, entry });
+    }
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
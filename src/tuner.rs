@@ -0,0 +1,271 @@
+use crate::agent::SmartAgent;
+use crate::game::{GameState, Stone, move_to_notation, parse_notation};
+use crate::game_manager::check_victory;
+use crate::weights::{EvalWeights, FEATURE_COUNT, Feature, MAX_WEIGHT};
+
+// 坐标下降每轮初始的乘法步长，没有改进时逐步缩小
+const INITIAL_STEP: f64 = 1.5;
+const MIN_STEP: f64 = 1.01;
+
+/// 一局棋谱：着法序列和黑方得分 (胜 1，和 0.5，负 0)
+#[derive(Debug, Clone)]
+pub struct GameRecord {
+    pub moves: Vec<(usize, usize)>,
+    pub black_score: f64,
+}
+
+/// 解析对局列表。每行一局：结果 (`1-0` 黑胜、`0-1` 白胜、`1/2-1/2` 和棋)
+/// 后跟以空白分隔的着法，如 `1-0 h8 i9 h9 h10`。`#` 开头的行为注释。
+pub fn parse_game_list(text: &str) -> Result<Vec<GameRecord>, String> {
+    let mut games = Vec::new();
+    for (line_no, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut tokens = line.split_whitespace();
+        let black_score = match tokens.next() {
+            Some("1-0") => 1.0,
+            Some("0-1") => 0.0,
+            Some("1/2-1/2") => 0.5,
+            other => {
+                return Err(format!(
+                    "line {}: bad result {:?}",
+                    line_no + 1,
+                    other.unwrap_or("")
+                ));
+            }
+        };
+        let moves = tokens
+            .map(|token| {
+                parse_notation(token)
+                    .ok_or_else(|| format!("line {}: bad move {}", line_no + 1, token))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        games.push(GameRecord { moves, black_score });
+    }
+    Ok(games)
+}
+
+/// 黑方得分对应的结果记号
+pub fn result_notation(black_score: f64) -> &'static str {
+    if black_score > 0.75 {
+        "1-0"
+    } else if black_score < 0.25 {
+        "0-1"
+    } else {
+        "1/2-1/2"
+    }
+}
+
+/// 将一局棋谱格式化为 parse_game_list 可读取的一行
+pub fn format_game(game: &GameRecord) -> String {
+    let mut line = result_notation(game.black_score).to_string();
+    for &(row, col) in &game.moves {
+        line.push(' ');
+        line.push_str(&move_to_notation(row, col));
+    }
+    line
+}
+
+/// 训练样本：某个局面下黑方减白方的特征，以及该局黑方得分
+#[derive(Debug, Clone)]
+pub struct Sample {
+    pub features: [f64; FEATURE_COUNT],
+    pub black_score: f64,
+}
+
+/// 重放每一局，从第 skip_moves 手之后的每个局面提取样本
+pub fn extract_samples(games: &[GameRecord], skip_moves: usize) -> Vec<Sample> {
+    let mut samples = Vec::new();
+    for game in games {
+        let mut state = GameState::new();
+        let mut stone = Stone::Black;
+        for (index, &(row, col)) in game.moves.iter().enumerate() {
+            if state.apply_move(row, col, stone).is_err() {
+                break; // 棋谱中的非法着法，丢弃这局余下的部分
+            }
+            stone = stone.opponent();
+            if index < skip_moves {
+                continue;
+            }
+
+            let black = SmartAgent::board_features(&state, Stone::Black);
+            let white = SmartAgent::board_features(&state, Stone::White);
+            samples.push(Sample {
+                features: std::array::from_fn(|i| (black[i] - white[i]) as f64),
+                black_score: game.black_score,
+            });
+        }
+    }
+    samples
+}
+
+fn sigmoid(x: f64) -> f64 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+/// 预测结果与实际结果的均方误差，预测值为 sigmoid(k × 评估值)
+pub fn mean_squared_error(samples: &[Sample], weights: &[f64; FEATURE_COUNT], k: f64) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let total: f64 = samples
+        .iter()
+        .map(|sample| {
+            let eval: f64 = weights
+                .iter()
+                .zip(&sample.features)
+                .map(|(w, f)| w * f)
+                .sum();
+            let error = sample.black_score - sigmoid(k * eval);
+            error * error
+        })
+        .sum();
+    total / samples.len() as f64
+}
+
+/// 在对数尺度上三分搜索使误差最小的缩放系数 k
+pub fn fit_scale(samples: &[Sample], weights: &[f64; FEATURE_COUNT]) -> f64 {
+    minimise_log_scale(|k| mean_squared_error(samples, weights, k))
+}
+
+/// 在 10^-12 到 1 之间按对数尺度三分搜索使 error 最小的 k
+fn minimise_log_scale(error: impl Fn(f64) -> f64) -> f64 {
+    let (mut low, mut high) = (-12.0_f64, 0.0_f64); // log10(k)
+    for _ in 0..60 {
+        let m1 = low + (high - low) / 3.0;
+        let m2 = high - (high - low) / 3.0;
+        if error(10f64.powf(m1)) < error(10f64.powf(m2)) {
+            high = m2;
+        } else {
+            low = m1;
+        }
+    }
+    10f64.powf((low + high) / 2.0)
+}
+
+/// 胜率校准样本：某个局面下从黑方看的搜索分数，以及该局黑方得分
+#[derive(Debug, Clone, Copy)]
+pub struct ScoreSample {
+    pub score: i32,
+    pub black_score: f64,
+}
+
+/// 重放每一局，对第 skip_moves 手之后、胜负未分的每个局面做 depth 层搜索，
+/// 记录从黑方看的分数。每搜索完一局调用一次 on_game(已完成的局数)
+pub fn extract_score_samples(
+    games: &[GameRecord],
+    skip_moves: usize,
+    weights: &EvalWeights,
+    depth: u32,
+    mut on_game: impl FnMut(usize),
+) -> Vec<ScoreSample> {
+    let mut samples = Vec::new();
+    for (game_index, game) in games.iter().enumerate() {
+        let mut state = GameState::new();
+        let mut stone = Stone::Black;
+        for (index, &(row, col)) in game.moves.iter().enumerate() {
+            if state.apply_move(row, col, stone).is_err() {
+                break; // 棋谱中的非法着法，丢弃这局余下的部分
+            }
+            stone = stone.opponent();
+            if index < skip_moves || check_victory(&state).is_some() {
+                continue;
+            }
+
+            // 搜索分数是轮到走棋的一方 (stone) 的视角
+            let mut agent = SmartAgent::new(stone, depth);
+            agent.set_weights(weights.clone());
+            if let Some(info) = agent.search(&state) {
+                let score = match stone {
+                    Stone::Black => info.score,
+                    Stone::White => -info.score,
+                };
+                samples.push(ScoreSample {
+                    score,
+                    black_score: game.black_score,
+                });
+            }
+        }
+        on_game(game_index + 1);
+    }
+    samples
+}
+
+/// 用 win_probability 的形式 1 / (1 + e^(-分数 / scale)) 预测结果时的均方误差
+pub fn score_error(samples: &[ScoreSample], scale: f64) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let total: f64 = samples
+        .iter()
+        .map(|sample| {
+            let error = sample.black_score - sigmoid(sample.score as f64 / scale);
+            error * error
+        })
+        .sum();
+    total / samples.len() as f64
+}
+
+/// 拟合搜索分数换算为胜率的尺度 (game.rs 的 SCORE_SCALE)，即 fit_scale 中 k 的倒数
+pub fn fit_score_scale(samples: &[ScoreSample]) -> f64 {
+    1.0 / minimise_log_scale(|k| score_error(samples, 1.0 / k))
+}
+
+/// Texel 式调参：固定 k 后对每个权重做乘法步长的坐标下降。
+/// 五连的分值只出现在终局局面，固定不动作为整体尺度的锚点。
+/// 每完成一轮调用一次 on_pass(轮次, 误差)。
+pub fn tune(
+    samples: &[Sample],
+    initial: &EvalWeights,
+    max_passes: usize,
+    mut on_pass: impl FnMut(usize, f64),
+) -> EvalWeights {
+    let mut weights = initial.features.map(|w| w as f64);
+    let k = fit_scale(samples, &weights);
+    let mut best_error = mean_squared_error(samples, &weights, k);
+    let mut step = INITIAL_STEP;
+
+    for pass in 1..=max_passes {
+        let mut improved = false;
+        for feature in Feature::ALL {
+            if feature == Feature::Five {
+                continue;
+            }
+            let i = feature as usize;
+            for factor in [step, 1.0 / step] {
+                let mut candidate = weights;
+                // 至少改变 1，避免小权重在取整后原地不动
+                let scaled = (weights[i] * factor).round();
+                candidate[i] = if scaled == weights[i] {
+                    weights[i] + if factor > 1.0 { 1.0 } else { -1.0 }
+                } else {
+                    scaled
+                };
+                if candidate[i] < 0.0 || candidate[i] > MAX_WEIGHT as f64 {
+                    continue;
+                }
+                let error = mean_squared_error(samples, &candidate, k);
+                if error < best_error {
+                    best_error = error;
+                    weights = candidate;
+                    improved = true;
+                    break;
+                }
+            }
+        }
+        on_pass(pass, best_error);
+
+        if !improved {
+            step = step.sqrt();
+            if step < MIN_STEP {
+                break;
+            }
+        }
+    }
+
+    let mut tuned = initial.clone();
+    tuned.features = weights.map(|w| w.round() as i32);
+    tuned.clamped()
+}
@@ -1,5 +1,9 @@
 use crate::game::{GameState, PlayerScore, Stone, StoneComponent};
-use crate::agent::SmartAgent; // Add this import for SmartAgent
+use crate::adaptive::AdaptiveDifficulty;
+use crate::agent::{SearchInfo, SmartAgent}; // Add this import for SmartAgent
+use crate::external_engine::ExternalOpponent;
+use crate::review::MoveReview;
+use crate::skill::{SKILL_LEVELS, SkillLevel};
 use bevy::prelude::*;
 
 // Add these constants at the top of the file
@@ -30,6 +34,14 @@ pub struct UsageButton;
 #[derive(Component)]
 pub struct UsageWindow;
 
+/// 主菜单中打开玩家档案窗口的按钮 (见 profile.rs)
+#[derive(Component)]
+pub struct ProfileButton;
+
+/// 主菜单中打开对局库浏览窗口的按钮 (见 database.rs)
+#[derive(Component)]
+pub struct GamesButton;
+
 #[derive(Component)]
 pub struct CloseButton;
 
@@ -127,6 +139,72 @@ pub fn setup_main_menu(mut commands: Commands, windows: Query<&Window>) {
                 .with_text_alignment(TextAlignment::Center),
             );
         });
+
+    // 添加玩家档案按钮
+    commands
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(window_width / 2.0 - 100.0),
+                    top: Val::Px(460.0),
+                    width: Val::Px(200.0),
+                    height: Val::Px(65.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::rgb(0.15, 0.15, 0.15).into(),
+                ..default()
+            },
+            ProfileButton,
+        ))
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_section(
+                    "Profile",
+                    TextStyle {
+                        font_size: 30.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                )
+                .with_text_alignment(TextAlignment::Center),
+            );
+        });
+
+    // 添加对局库浏览按钮
+    commands
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(window_width / 2.0 - 100.0),
+                    top: Val::Px(540.0),
+                    width: Val::Px(200.0),
+                    height: Val::Px(65.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::rgb(0.15, 0.15, 0.15).into(),
+                ..default()
+            },
+            GamesButton,
+        ))
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_section(
+                    "Master Games",
+                    TextStyle {
+                        font_size: 30.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                )
+                .with_text_alignment(TextAlignment::Center),
+            );
+        });
 }
 
 // 处理开始按钮点击
@@ -288,7 +366,17 @@ pub fn handle_usage_button(
                             3. The first player to form an unbroken line of five stones horizontally, vertically, or diagonally wins.\n\
                             4. In this version, you play against an AI opponent.\n\
                             5. You can switch between playing as Black or White.\n\
-                            6. Use the Reset button to start a new game.",
+                            6. Use the Reset button to start a new game.\n\
+                            7. Press Hint (or H) to see the engine's suggestion. Hints per game are limited and lower your rating.\n\
+                            8. Press T to mark both players' open threes and fours and the points that complete or block them.\n\
+                            9. Press M for a heatmap of every empty point (E: evaluator or search, V: your view or the opponent's).\n\
+                            10. The bar left of the board shows each side's chance of winning after the last move.\n\
+                            11. Press Ctrl+S to save the game and Ctrl+L to load the saved game.\n\
+                            12. Press Ctrl+E to export the game to gobang.sgf and Ctrl+I to import it (again for the next variation).\n\
+                            13. Press Ctrl+P to export the game to gobang.psq and Ctrl+O to replay it (Left / Right to step).\n\
+                            14. Press B to show the opening book's continuations for the current position.\n\
+                            15. Master Games (main menu) browses the game database; press D to see its next moves for the board.\n\
+                            16. Press Ctrl+C to copy the position to the clipboard and Ctrl+V to set up a copied position.",
                                 TextStyle {
                                     font_size: 18.0,
                                     color: Color::rgb(0.2, 0.2, 0.2),
@@ -386,15 +474,25 @@ pub struct VictoryCloseButton;
 #[derive(Component)]
 pub struct PlayAgainButton;
 
+/// 胜利窗口中开始赛后分析的按钮 (见 analysis.rs)
+#[derive(Component)]
+pub struct AnalyseButton;
+
+#[derive(Component)]
+pub struct AnalyseButtonText;
+
 // 显示胜利窗口
 pub fn show_victory_window(
     mut commands: Commands,
     windows: Query<&Window>,
     game_state: Res<GameState>,
+    opponent: Res<ExternalOpponent>,
+    ai: Res<SmartAgent>,
+    review: Res<MoveReview>,
     victory_window_query: Query<Entity, With<VictoryWindow>>,
 ) {
-    // 如果游戏未结束或已经有窗口，则不创建
-    if !game_state.is_game_over || !victory_window_query.is_empty() {
+    // 如果游戏未结束或已经有窗口，则不创建；等最后几步评价完再创建，统计才完整
+    if !game_state.is_game_over || !victory_window_query.is_empty() || review.is_grading() {
         return;
     }
 
@@ -407,6 +505,11 @@ pub fn show_victory_window(
         Some(Stone::White) => "White Wins!",
         None => "It's a Draw!",
     };
+    // 外部引擎崩溃或超时导致的结束，附上原因
+    let victory_text = match &opponent.failure {
+        Some(reason) => format!("{}\nEngine forfeited: {}", victory_text, reason),
+        None => victory_text.to_string(),
+    };
 
     // 创建胜利窗口 - 调整位置使其靠右
     commands
@@ -415,9 +518,9 @@ pub fn show_victory_window(
                 style: Style {
                     position_type: PositionType::Absolute,
                     left: Val::Px(window_width / 2.0 + 100.0), // 向右移动
-                    top: Val::Px(300.0),
+                    top: Val::Px(200.0),
                     width: Val::Px(300.0),
-                    height: Val::Px(200.0),
+                    height: Val::Px(450.0),
                     flex_direction: FlexDirection::Column,
                     align_items: AlignItems::Center,
                     justify_content: JustifyContent::Center,
@@ -459,12 +562,28 @@ pub fn show_victory_window(
                     },
                 )
                 .with_style(Style {
-                    margin: UiRect::bottom(Val::Px(30.0)),
+                    margin: UiRect::bottom(Val::Px(20.0)),
                     ..default()
                 })
                 .with_text_alignment(TextAlignment::Center),
             );
 
+            // 添加双方落子分类的统计 (棋子上的徽标在对局结束后显示)
+            parent.spawn(
+                TextBundle::from_section(
+                    review.summary_text(ai.get_stone().opponent()),
+                    TextStyle {
+                        font_size: 16.0,
+                        color: Color::rgb(0.2, 0.2, 0.2),
+                        ..default()
+                    },
+                )
+                .with_style(Style {
+                    margin: UiRect::bottom(Val::Px(20.0)),
+                    ..default()
+                }),
+            );
+
             // 添加"再来一局"按钮
             parent
                 .spawn((
@@ -494,6 +613,38 @@ pub fn show_victory_window(
                         .with_text_alignment(TextAlignment::Center),
                     );
                 });
+
+            // 添加"分析"按钮，分析进行中时显示进度
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(180.0),
+                            height: Val::Px(40.0),
+                            margin: UiRect::top(Val::Px(10.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        background_color: Color::rgb(0.15, 0.15, 0.15).into(),
+                        ..default()
+                    },
+                    AnalyseButton,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            "Analyse",
+                            TextStyle {
+                                font_size: 20.0,
+                                color: Color::WHITE,
+                                ..default()
+                            },
+                        )
+                        .with_text_alignment(TextAlignment::Center),
+                        AnalyseButtonText,
+                    ));
+                });
         });
 }
 
@@ -590,20 +741,31 @@ pub struct DifficultyOption {
     pub label: String,
 }
 
+// 难度按钮上显示的文字，自适应难度开启时显示当前实际使用的等级
+fn difficulty_label(ai: &SmartAgent, adaptive: &AdaptiveDifficulty) -> String {
+    let label = match ai.skill_level() {
+        Some(level) => format!("Level {}: {} ", level, SkillLevel::get(level).name),
+        None => format!("Depth {} ", ai.get_depth()),
+    };
+    if adaptive.enabled {
+        format!("Auto {}", label)
+    } else {
+        label
+    }
+}
+
 // 创建难度选择下拉菜单
-pub fn setup_difficulty_selector(mut commands: Commands, ai: Res<SmartAgent>) {
+pub fn setup_difficulty_selector(
+    mut commands: Commands,
+    ai: Res<SmartAgent>,
+    adaptive: Res<AdaptiveDifficulty>,
+) {
     // 难度按钮位置
     let button_x = BOARD_OFFSET + (GRID_SIZE as f32 * CELL_SIZE) + 100.0;
     let button_y = 50.0;
     
     // 获取当前难度
-    let current_level = ai.get_difficulty();
-    let difficulty_label = match current_level {
-        1 => "Easy",
-        3 => "Medium",
-        2 => "Hard",
-        _ => "Medium",
-    };
+    let difficulty_label = difficulty_label(&ai, &adaptive);
     
     // 创建难度选择器按钮
     commands
@@ -613,7 +775,7 @@ pub fn setup_difficulty_selector(mut commands: Commands, ai: Res<SmartAgent>) {
                     position_type: PositionType::Absolute,
                     left: Val::Px(button_x),
                     top: Val::Px(button_y),
-                    width: Val::Px(150.0),
+                    width: Val::Px(240.0),
                     height: Val::Px(40.0),
                     flex_direction: FlexDirection::Column,
                     align_items: AlignItems::Center,
@@ -643,7 +805,7 @@ pub fn setup_difficulty_selector(mut commands: Commands, ai: Res<SmartAgent>) {
                 .with_children(|parent| {
                     parent.spawn(
                         TextBundle::from_section(
-                            format!("Difficulty: {} ", difficulty_label),
+                            difficulty_label,
                             TextStyle {
                                 font_size: 20.0,
                                 color: Color::WHITE,
@@ -663,6 +825,7 @@ pub fn handle_difficulty_dropdown(
     mut text_query: Query<&mut Text>,
     interaction_query: Query<&Interaction, With<Button>>,
     ai: Res<SmartAgent>,
+    adaptive: Res<AdaptiveDifficulty>,
 ) {
     for (entity, mut dropdown, mut bg_color, children) in dropdown_query.iter_mut() {
         let interaction = interaction_query.get(entity).unwrap_or(&Interaction::None);
@@ -685,14 +848,7 @@ pub fn handle_difficulty_dropdown(
                     let text_entity = children.iter().next().unwrap();
                     if let Ok(mut text) = text_query.get_mut(*text_entity) {
                         // 获取当前难度并更改箭头方向
-                        let current_level =ai.get_difficulty();
-                        let difficulty_label = match current_level {
-                            1 => "Easy",
-                            3 => "Medium",
-                            2 => "Hard",
-                            _ => "Medium",
-                        };
-                        text.sections[0].value = format!("Difficulty: {} ", difficulty_label);
+                        text.sections[0].value = difficulty_label(&ai, &adaptive);
                     }
                     
                     // 创建下拉选项
@@ -712,14 +868,11 @@ pub fn handle_difficulty_dropdown(
                                 ..default()
                             })
                             .with_children(|parent| {
-                                // 添加三个难度选项
-                                let options = [
-                                    ("Easy", 1, Color::rgb(0.2, 0.6, 0.2)),
-                                    ("Medium", 3, Color::rgb(0.6, 0.6, 0.2)),
-                                    ("Hard", 2, Color::rgb(0.6, 0.2, 0.2)),
-                                ];
-                                
-                                for (label, level, color) in options {
+                                // 添加各难度等级选项，颜色从绿色渐变到红色
+                                for skill in &SKILL_LEVELS {
+                                    let t = (skill.level - 1) as f32 / (SKILL_LEVELS.len() - 1) as f32;
+                                    let color = Color::rgb(0.2 + 0.4 * t, 0.6 - 0.4 * t, 0.2);
+                                    let label = format!("{}. {}", skill.level, skill.name);
                                     parent
                                         .spawn((
                                             ButtonBundle {
@@ -734,8 +887,8 @@ pub fn handle_difficulty_dropdown(
                                                 ..default()
                                             },
                                             DifficultyOption {
-                                                level,
-                                                label: label.to_string(),
+                                                level: skill.level,
+                                                label: label.clone(),
                                             },
                                         ))
                                         .with_children(|parent| {
@@ -759,14 +912,7 @@ pub fn handle_difficulty_dropdown(
                     let text_entity = children.iter().next().unwrap();
                     if let Ok(mut text) = text_query.get_mut(*text_entity) {
                         // 显示当前选择的难度
-                        let current_level = ai.get_difficulty();
-                        let difficulty_label = match current_level {
-                            1 => "Easy",
-                            3 => "Medium",
-                            2 => "Hard",
-                            _ => "Medium",
-                        };
-                        text.sections[0].value = format!("Difficulty: {} ", difficulty_label);
+                        text.sections[0].value = difficulty_label(&ai, &adaptive);
                     }
                     
                     // 移除所有子元素（除了第一个文本元素）
@@ -796,18 +942,19 @@ pub fn handle_difficulty_options(
     dropdown_query: Query<(Entity, &Children), With<DifficultyDropdown>>,
     mut text_query: Query<&mut Text>,
     mut ai: ResMut<SmartAgent>,
+    adaptive: Res<AdaptiveDifficulty>,
 ) {
     for (interaction, option, parent) in option_query.iter_mut() {
         if *interaction == Interaction::Pressed {
             // 设置AI难度
-            ai.set_depth(option.level);
+            ai.set_skill_level(option.level);
             
             // 更新下拉菜单文本并关闭菜单
             for (dropdown_entity, children) in dropdown_query.iter() {
                 // 更新按钮文本
                 if let Some(&text_entity) = children.iter().next() {
                     if let Ok(mut text) = text_query.get_mut(text_entity) {
-                        text.sections[0].value = format!("Difficulty: {} ", option.label);
+                        text.sections[0].value = difficulty_label(&ai, &adaptive);
                     }
                 }
                 
@@ -820,7 +967,106 @@ pub fn handle_difficulty_options(
                 commands.entity(dropdown_entity).insert(DifficultyDropdown { is_open: false });
             }
             
-            println!("AI difficulty set to: {}", option.level);
+            println!("AI difficulty set to: {}", option.label);
         }
     }
 }
+
+// AI 等级被其他系统 (自适应难度) 改变后刷新难度按钮的文字
+pub fn update_difficulty_label(
+    ai: Res<SmartAgent>,
+    adaptive: Res<AdaptiveDifficulty>,
+    dropdown_query: Query<&Children, With<DifficultyDropdown>>,
+    mut text_query: Query<&mut Text>,
+) {
+    if !ai.is_changed() {
+        return;
+    }
+    for children in dropdown_query.iter() {
+        if let Some(&text_entity) = children.first()
+            && let Ok(mut text) = text_query.get_mut(text_entity)
+        {
+            text.sections[0].value = difficulty_label(&ai, &adaptive);
+        }
+    }
+}
+
+// 引擎信息面板：显示内置 AI 最近一次搜索的深度、节点数、主要变例等
+#[derive(Resource, Default)]
+pub struct EnginePanel {
+    pub visible: bool,
+    pub info: Option<SearchInfo>,
+}
+
+#[derive(Component)]
+pub struct EnginePanelText;
+
+// 创建引擎信息面板，位于右侧按钮下方
+pub fn setup_engine_panel(mut commands: Commands, panel: Res<EnginePanel>) {
+    commands.spawn((
+        TextBundle {
+            text: Text::from_section(
+                engine_panel_text(&panel),
+                TextStyle {
+                    font_size: 18.0,
+                    color: Color::rgb(0.2, 0.2, 0.2),
+                    ..default()
+                },
+            ),
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(780.0),
+                top: Val::Px(420.0),
+                width: Val::Px(400.0),
+                ..default()
+            },
+            visibility: if panel.visible {
+                Visibility::Visible
+            } else {
+                Visibility::Hidden
+            },
+            ..default()
+        },
+        EnginePanelText,
+    ));
+}
+
+fn engine_panel_text(panel: &EnginePanel) -> String {
+    let Some(info) = &panel.info else {
+        return "Engine\nWaiting for the AI to move...".to_string();
+    };
+    format!(
+        "Engine\nDepth: {}\nScore: {}\nNodes: {}\nSpeed: {} nodes/s\nTime: {:.2}s\nPV: {}",
+        info.depth,
+        info.score_text(),
+        info.nodes,
+        info.nps(),
+        info.elapsed.as_secs_f32(),
+        info.pv_text()
+    )
+}
+
+// 按 F2 显示或隐藏引擎信息面板
+pub fn toggle_engine_panel(keys: Res<Input<KeyCode>>, mut panel: ResMut<EnginePanel>) {
+    if keys.just_pressed(KeyCode::F2) {
+        panel.visible = !panel.visible;
+    }
+}
+
+// 面板内容或可见性变化时刷新显示
+pub fn update_engine_panel(
+    panel: Res<EnginePanel>,
+    mut query: Query<(&mut Text, &mut Visibility), With<EnginePanelText>>,
+) {
+    if !panel.is_changed() {
+        return;
+    }
+    for (mut text, mut visibility) in query.iter_mut() {
+        text.sections[0].value = engine_panel_text(&panel);
+        *visibility = if panel.visible {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
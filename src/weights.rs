@@ -0,0 +1,143 @@
+use crate::config::{ConfigFile, ConfigWriter};
+use std::fs;
+use std::path::Path;
+
+/// 评估权重文件的默认位置 (程序工作目录下)
+pub const WEIGHTS_FILE: &str = "weights.toml";
+
+/// SmartAgent 评估函数的特征数量
+pub const FEATURE_COUNT: usize = 8;
+
+/// 权重绝对值的上限 (默认五连分的 10 倍)。权重来自用户文件和调参，读入和调参后都限制在
+/// 这个范围内，加权和不会溢出，静态评估也不会接近表示已算出胜负的分数
+pub const MAX_WEIGHT: i32 = 10_000_000;
+
+/// SmartAgent 评估函数的线性特征，评估值 = Σ 权重 × (己方特征 - 对方特征)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature {
+    Five = 0,   // 五子连珠
+    OpenFour,   // 活四
+    HalfFour,   // 冲四/死四
+    OpenThree,  // 活三
+    HalfThree,  // 眠三/死三
+    OpenTwo,    // 活二
+    HalfTwo,    // 眠二/死二
+    Positional, // 靠近中心的位置分
+}
+
+impl Feature {
+    pub const ALL: [Feature; FEATURE_COUNT] = [
+        Feature::Five,
+        Feature::OpenFour,
+        Feature::HalfFour,
+        Feature::OpenThree,
+        Feature::HalfThree,
+        Feature::OpenTwo,
+        Feature::HalfTwo,
+        Feature::Positional,
+    ];
+
+    /// 权重文件中使用的键名
+    pub fn key(self) -> &'static str {
+        match self {
+            Feature::Five => "five",
+            Feature::OpenFour => "open_four",
+            Feature::HalfFour => "half_four",
+            Feature::OpenThree => "open_three",
+            Feature::HalfThree => "half_three",
+            Feature::OpenTwo => "open_two",
+            Feature::HalfTwo => "half_two",
+            Feature::Positional => "positional",
+        }
+    }
+}
+
+/// 评估权重，保存在 `[agent]` 段。SmartAgent 和 BoardEvaluator 使用同一组棋型分
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvalWeights {
+    pub features: [i32; FEATURE_COUNT],
+}
+
+impl Default for EvalWeights {
+    fn default() -> Self {
+        EvalWeights {
+            features: [
+                1_000_000, // 五子连珠
+                10_000,    // 活四
+                1_000,     // 冲四/死四
+                1_000,     // 活三
+                100,       // 眠三/死三
+                50,        // 活二
+                10,        // 眠二/死二
+                1,         // 位置分
+            ],
+        }
+    }
+}
+
+impl EvalWeights {
+    pub fn get(&self, feature: Feature) -> i32 {
+        self.features[feature as usize]
+    }
+
+    /// 计算一组特征的加权和，超出 i32 范围时取边界值
+    pub fn score(&self, features: &[i32; FEATURE_COUNT]) -> i32 {
+        saturating_sum(
+            self.features
+                .iter()
+                .zip(features)
+                .map(|(&weight, &count)| weight as i64 * count as i64),
+        )
+    }
+
+    /// 把每个权重限制在 ±MAX_WEIGHT 之内
+    pub fn clamped(mut self) -> Self {
+        for weight in &mut self.features {
+            *weight = (*weight).clamp(-MAX_WEIGHT, MAX_WEIGHT);
+        }
+        self
+    }
+
+    /// 读取权重文件，文件中缺失的项使用默认值
+    pub fn load(path: &Path) -> Result<Self, String> {
+        Ok(Self::from_config(&ConfigFile::load(path)?))
+    }
+
+    /// 读取权重文件，出错时打印原因并使用默认权重
+    pub fn load_or_default(path: &Path) -> Self {
+        Self::load(path).unwrap_or_else(|e| {
+            eprintln!("读取评估权重失败，使用默认权重: {}", e);
+            EvalWeights::default()
+        })
+    }
+
+    fn from_config(config: &ConfigFile) -> Self {
+        let mut weights = EvalWeights::default();
+        for feature in Feature::ALL {
+            if let Some(value) = config.get_i32(&format!("agent.{}", feature.key())) {
+                weights.features[feature as usize] = value;
+            }
+        }
+        weights.clamped()
+    }
+
+    pub fn to_toml(&self) -> String {
+        let mut writer = ConfigWriter::default();
+        writer.section("agent");
+        for feature in Feature::ALL {
+            writer.value(feature.key(), self.get(feature));
+        }
+        writer.finish().to_string()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.to_toml()).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+/// 在 i64 中求和，结果超出 i32 范围时取边界值
+pub fn saturating_sum(values: impl Iterator<Item = i64>) -> i32 {
+    values
+        .fold(0i64, i64::saturating_add)
+        .clamp(i32::MIN as i64, i32::MAX as i64) as i32
+}
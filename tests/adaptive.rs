@@ -0,0 +1,76 @@
+use gobang::adaptive::AdaptiveDifficulty;
+use gobang::skill::{MAX_SKILL_LEVEL, MIN_SKILL_LEVEL};
+
+#[test]
+fn settings_are_clamped() {
+    let adaptive = AdaptiveDifficulty::new(true, 1.2, 20);
+    assert_eq!(adaptive.target_win_rate, 0.95);
+    assert_eq!(adaptive.effective_level(), MAX_SKILL_LEVEL);
+    assert_eq!(adaptive.recent_win_rate(), None);
+
+    let adaptive = AdaptiveDifficulty::new(true, 0.0, 0);
+    assert_eq!(adaptive.target_win_rate, 0.05);
+    assert_eq!(adaptive.effective_level(), MIN_SKILL_LEVEL);
+}
+
+/// 玩家赢得多时加强，输得多时减弱，和棋在目标胜率 50% 时不变
+#[test]
+fn results_move_the_base_level_towards_the_target() {
+    let mut adaptive = AdaptiveDifficulty::new(true, 0.5, 5);
+    adaptive.record_game(0.5);
+    assert_eq!(adaptive.effective_level(), 5);
+
+    adaptive.record_game(1.0);
+    adaptive.record_game(1.0);
+    assert_eq!(adaptive.recent_win_rate(), Some(2.5 / 3.0));
+    assert!(adaptive.effective_level() > 5);
+
+    let mut adaptive = AdaptiveDifficulty::new(true, 0.5, 5);
+    adaptive.record_game(0.0);
+    adaptive.record_game(0.0);
+    assert!(adaptive.effective_level() < 5);
+
+    // 一直赢或一直输时停在最高或最低等级
+    let mut adaptive = AdaptiveDifficulty::new(true, 0.5, 5);
+    for _ in 0..20 {
+        adaptive.record_game(1.0);
+    }
+    assert_eq!(adaptive.effective_level(), MAX_SKILL_LEVEL);
+    for _ in 0..40 {
+        adaptive.record_game(0.0);
+    }
+    assert_eq!(adaptive.effective_level(), MIN_SKILL_LEVEL);
+}
+
+/// 近期胜率只看最近 6 局
+#[test]
+fn recent_win_rate_uses_the_last_games() {
+    let mut adaptive = AdaptiveDifficulty::new(true, 0.5, 5);
+    for _ in 0..4 {
+        adaptive.record_game(1.0);
+    }
+    for _ in 0..6 {
+        adaptive.record_game(0.0);
+    }
+    assert_eq!(adaptive.recent_win_rate(), Some(0.0));
+    adaptive.record_game(1.0);
+    assert_eq!(adaptive.recent_win_rate(), Some(1.0 / 6.0));
+}
+
+/// 局中按玩家评分临时升降最多 1.5 级，评分过的步数太少时不调整，下一局重新开始
+#[test]
+fn in_game_rating_shifts_the_level_temporarily() {
+    let mut adaptive = AdaptiveDifficulty::new(true, 0.5, 5);
+    adaptive.update_in_game(100, 3);
+    assert_eq!(adaptive.effective_level(), 5);
+
+    adaptive.update_in_game(100, 10);
+    assert_eq!(adaptive.effective_level(), 7); // 5 + 1.5 四舍五入
+    adaptive.update_in_game(75, 10);
+    assert_eq!(adaptive.effective_level(), 5);
+    adaptive.update_in_game(0, 10);
+    assert_eq!(adaptive.effective_level(), 4); // 5 - 1.5 四舍五入
+
+    adaptive.record_game(0.5);
+    assert_eq!(adaptive.effective_level(), 5);
+}
@@ -0,0 +1,166 @@
+use gobang::analysis::{AnalysedMove, AnalysisReport, analyse_game};
+use gobang::game::{SCORE_SCALE, Stone, move_to_notation, parse_notation};
+use gobang::review::MoveClass;
+use gobang::weights::EvalWeights;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+fn point(text: &str) -> (usize, usize) {
+    parse_notation(text).expect("无效的着法")
+}
+
+fn analysed(number: usize, played: &str, score: i32, best: &str, best_score: i32) -> AnalysedMove {
+    AnalysedMove {
+        number,
+        stone: if number % 2 == 1 {
+            Stone::Black
+        } else {
+            Stone::White
+        },
+        played: point(played),
+        score,
+        best_move: point(best),
+        best_score,
+        best_line: vec![point(best)],
+    }
+}
+
+// 第 2 步白方小失误，第 4 步白方大失误，其余都是最佳着法
+fn report() -> AnalysisReport {
+    let scale = SCORE_SCALE as i32;
+    AnalysisReport {
+        player_stone: Stone::White,
+        depth: 4,
+        moves: vec![
+            analysed(1, "h8", 0, "h8", 0),
+            analysed(2, "a1", -scale / 4, "i9", 0),
+            analysed(3, "i8", scale / 2, "i8", scale / 2),
+            analysed(4, "a2", -2 * scale, "g8", -scale / 2),
+            analysed(5, "j8", 3 * scale, "j8", 3 * scale),
+        ],
+    }
+}
+
+#[test]
+fn report_summarises_each_side() {
+    let report = report();
+    let black = report.accuracy(Stone::Black).unwrap();
+    assert!(black > 99.99, "{}", black);
+    let white = report.accuracy(Stone::White).unwrap();
+    assert!(white < 100.0 && white > 0.0, "{}", white);
+    assert_eq!(
+        AnalysisReport {
+            moves: Vec::new(),
+            ..report.clone()
+        }
+        .accuracy(Stone::Black),
+        None
+    );
+
+    let turning: Vec<usize> = report.turning_points().iter().map(|m| m.number).collect();
+    assert_eq!(turning, [2, 4]);
+    let mistakes: Vec<usize> = report.mistakes().map(|m| m.number).collect();
+    assert_eq!(mistakes, [4]);
+    assert!(matches!(
+        report.moves[3].class(),
+        MoveClass::Mistake | MoveClass::Blunder
+    ));
+
+    // 曲线数据为黑方胜率：白方落子的分数取反
+    assert_eq!(report.moves[0].black_win_rate(), 0.5);
+    assert!(report.moves[1].black_win_rate() > 0.5);
+    assert!(report.moves[2].black_win_rate() > 0.5);
+}
+
+#[test]
+fn text_export_lists_turning_points_and_better_moves() {
+    let text = report().to_text();
+    assert!(
+        text.starts_with("Game analysis (search depth 4)\n"),
+        "{}",
+        text
+    );
+    assert!(text.contains("Black (AI): accuracy 100%"), "{}", text);
+    assert!(text.contains("White (You): accuracy "), "{}", text);
+    assert!(text.contains("4. White a2"), "{}", text);
+    assert!(text.contains("better: g8 (g8)"), "{}", text);
+    // 每步一行
+    let rows = text.lines().skip_while(|line| *line != "Moves").skip(2);
+    assert_eq!(rows.count(), 5);
+}
+
+#[test]
+fn html_export_draws_the_win_chance_graph() {
+    let report = report();
+    let html = report.to_html();
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.trim_end().ends_with("</html>"));
+
+    let points = html
+        .split("<polyline points=\"")
+        .nth(1)
+        .and_then(|rest| rest.split('"').next())
+        .expect("评估曲线");
+    let points: Vec<(f64, f64)> = points
+        .split(' ')
+        .map(|p| {
+            let (x, y) = p.split_once(',').unwrap();
+            (x.parse().unwrap(), y.parse().unwrap())
+        })
+        .collect();
+    assert_eq!(points.len(), report.moves.len());
+    // 横坐标按手数递增；纵坐标 0 为黑方必胜，中线 (高度的一半) 为 50%
+    assert!(points.windows(2).all(|w| w[0].0 < w[1].0));
+    let height = 2.0 * points[0].1;
+    for (point, m) in points.iter().zip(&report.moves) {
+        let expected = (1.0 - m.black_win_rate()) * height;
+        assert!((point.1 - expected).abs() <= 0.05, "{:?}", point);
+    }
+    assert_eq!(html.matches("<tr><td>").count(), 5);
+}
+
+/// 实际分析一局：白方不理会黑方的活三是失误，更好的着法是在同一行封堵
+#[test]
+fn analyse_game_finds_the_mistake() {
+    let moves: Vec<(usize, usize, Stone)> = ["h8", "h9", "i8", "a1", "j8", "a2"]
+        .iter()
+        .zip([Stone::Black, Stone::White].into_iter().cycle())
+        .map(|(text, stone)| {
+            let (row, col) = point(text);
+            (row, col, stone)
+        })
+        .collect();
+    let progress = AtomicUsize::new(0);
+    let report = analyse_game(
+        &EvalWeights::default(),
+        &moves,
+        Stone::White,
+        &progress,
+        &AtomicBool::new(false),
+    );
+    assert_eq!(progress.load(Ordering::Relaxed), moves.len());
+    assert_eq!(report.moves.len(), moves.len());
+    for (m, &(row, col, stone)) in report.moves.iter().zip(&moves) {
+        assert_eq!((m.played, m.stone), ((row, col), stone));
+        assert!(m.best_score >= m.score);
+        assert_eq!(m.best_line.first(), Some(&m.best_move));
+    }
+
+    let last = report.moves.last().unwrap();
+    assert!(
+        report.mistakes().any(|m| m.number == 6),
+        "{}",
+        report.to_text()
+    );
+    let best = move_to_notation(last.best_move.0, last.best_move.1);
+    assert!(best.ends_with('8'), "{}", best);
+
+    // 一开始就要求停止时不分析
+    let stopped = analyse_game(
+        &EvalWeights::default(),
+        &moves,
+        Stone::White,
+        &AtomicUsize::new(0),
+        &AtomicBool::new(true),
+    );
+    assert!(stopped.moves.is_empty());
+}
@@ -0,0 +1,15 @@
+// 各个集成测试共用的辅助函数
+use gobang::game::{GameState, Stone, parse_notation};
+
+/// 黑先交替走 moves (空格分隔的坐标，如 "h8 h9") 得到的局面，轮到下一方走
+pub fn position(moves: &str) -> GameState {
+    let mut game_state = GameState::new();
+    let mut stone = Stone::Black;
+    for text in moves.split_whitespace() {
+        let (row, col) = parse_notation(text).expect("无效的着法");
+        game_state.apply_move(row, col, stone).expect("着法重复");
+        stone = stone.opponent();
+    }
+    game_state.current_turn = stone;
+    game_state
+}
@@ -0,0 +1,110 @@
+use gobang::config::{ConfigFile, ConfigWriter};
+
+#[test]
+fn sections_prefix_keys() {
+    let config = ConfigFile::parse(
+        "top = 1\n\
+         [ai]\n\
+         depth = 3\n\
+         [engine]\n\
+         depth = 5\n",
+    )
+    .unwrap();
+    assert_eq!(config.get_i32("top"), Some(1));
+    assert_eq!(config.get_i32("ai.depth"), Some(3));
+    assert_eq!(config.get_i32("engine.depth"), Some(5));
+    assert_eq!(config.get_i32("depth"), None);
+}
+
+#[test]
+fn value_types() {
+    let config = ConfigFile::parse(
+        "[v]\n\
+         int = -42\n\
+         big = 1_000_000\n\
+         float = 0.25\n\
+         double = 0.059993\n\
+         yes = true\n\
+         no = false\n\
+         word = plain\n",
+    )
+    .unwrap();
+    assert_eq!(config.get_i32("v.int"), Some(-42));
+    assert_eq!(config.get_u64("v.big"), Some(1_000_000));
+    assert_eq!(config.get_f32("v.float"), Some(0.25));
+    // f64 不经过 f32，读出的值与文件中的十进制数最接近
+    assert_eq!(config.get_f64("v.double"), Some(0.059993));
+    assert_eq!(config.get_bool("v.yes"), Some(true));
+    assert_eq!(config.get_bool("v.no"), Some(false));
+    assert_eq!(config.get_str("v.word"), Some("plain"));
+    // 类型不符时视为没有设置
+    assert_eq!(config.get_i32("v.word"), None);
+    assert_eq!(config.get_bool("v.int"), None);
+}
+
+#[test]
+fn quoted_strings() {
+    let config = ConfigFile::parse(
+        r#"
+        plain = "hello world"
+        quote = "say \"hi\""
+        backslash = "a\\b"
+        newline = "one\ntwo"
+        path = "C:\engines\x.exe"
+        spaces = "  padded  "
+        "#,
+    )
+    .unwrap();
+    assert_eq!(config.get_str("plain"), Some("hello world"));
+    assert_eq!(config.get_str("quote"), Some("say \"hi\""));
+    assert_eq!(config.get_str("backslash"), Some("a\\b"));
+    assert_eq!(config.get_str("newline"), Some("one\ntwo"));
+    assert_eq!(config.get_str("path"), Some("C:\\engines\\x.exe"));
+    assert_eq!(config.get_str("spaces"), Some("  padded  "));
+}
+
+#[test]
+fn comments() {
+    let config = ConfigFile::parse(
+        r#"
+        # 整行注释
+        [ai] # 表头后的注释
+        depth = 4 # 行尾注释
+        name = "not # a comment" # 字符串中的 # 不是注释
+        escaped = "a \" # b" # 转义的引号不结束字符串
+        "#,
+    )
+    .unwrap();
+    assert_eq!(config.get_i32("ai.depth"), Some(4));
+    assert_eq!(config.get_str("ai.name"), Some("not # a comment"));
+    assert_eq!(config.get_str("ai.escaped"), Some("a \" # b"));
+}
+
+#[test]
+fn bad_lines_are_rejected() {
+    let error = ConfigFile::parse("[ai]\ndepth = 3\nnonsense\n").unwrap_err();
+    assert!(error.contains("line 3"), "{}", error);
+    assert!(ConfigFile::parse("[ai\n").is_err());
+}
+
+#[test]
+fn later_values_replace_earlier_ones() {
+    let config = ConfigFile::parse("a = 1\na = 2\n").unwrap();
+    assert_eq!(config.get_i32("a"), Some(2));
+}
+
+#[test]
+fn writer_round_trip() {
+    let tricky = "quote \" backslash \\ hash # newline \n tab \t";
+    let mut writer = ConfigWriter::default();
+    writer
+        .section("file")
+        .value("name", format!("{:?}", tricky))
+        .value("count", 7);
+    writer.section("game.0").value("ok", true);
+
+    let config = ConfigFile::parse(writer.finish()).unwrap();
+    assert_eq!(config.get_str("file.name"), Some(tricky));
+    assert_eq!(config.get_i32("file.count"), Some(7));
+    assert_eq!(config.get_bool("game.0.ok"), Some(true));
+}
@@ -0,0 +1,216 @@
+use gobang::database::{DB_VERSION, DbGame, GameDatabase, GameFilter, PositionStats};
+use gobang::game::{GameState, Stone, move_to_notation, parse_notation};
+use gobang::savegame::GameResult;
+use std::fs;
+
+mod common;
+use common::position;
+
+fn db_game(source: &str, moves: &str, result: GameResult) -> DbGame {
+    DbGame {
+        source: source.to_string(),
+        black: "Ana Novak".to_string(),
+        white: "Li \"Wu\"".to_string(),
+        result,
+        opening: "Kagetsu".to_string(),
+        event: "World Championship".to_string(),
+        year: Some(2019),
+        rule: "Renju".to_string(),
+        moves: moves
+            .split_whitespace()
+            .map(|text| parse_notation(text).expect("无效的着法"))
+            .collect(),
+    }
+}
+
+// h8 之后：两局斜向 (其中 g7 是 i9 的对称)、一局直向，另有一局从 a1 开始
+fn database() -> GameDatabase {
+    let mut database = GameDatabase::default();
+    database.games = vec![
+        db_game("rif:1", "h8 i9 i8", GameResult::Win(Stone::Black)),
+        db_game("rif:2", "h8 g7 g8", GameResult::Draw),
+        db_game("rif:3", "h8 h9", GameResult::Win(Stone::White)),
+        db_game("rif:4", "a1", GameResult::Ongoing),
+        db_game("rif:5", "h8 i9", GameResult::Ongoing),
+    ];
+    database
+}
+
+/// 后续着法和 (对局数, 黑胜, 和棋, 白胜)
+fn moves(stats: &PositionStats) -> Vec<(String, (u32, u32, u32, u32))> {
+    stats
+        .moves
+        .iter()
+        .map(|m| {
+            (
+                move_to_notation(m.row, m.col),
+                (m.games, m.black_wins, m.draws, m.white_wins),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn round_trip() {
+    let mut database = database();
+    database.games[2].year = None;
+    database.games[3].black = String::new();
+    database.games[3].source = String::new();
+
+    let path = std::env::temp_dir().join(format!("gobang-db-{}.toml", std::process::id()));
+    database.save(&path).unwrap();
+    let loaded = GameDatabase::load(&path);
+    fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.unwrap().games, database.games);
+
+    // 文件不存在时是空的对局库
+    let missing = std::env::temp_dir().join("gobang-db-missing.toml");
+    assert!(GameDatabase::load(&missing).unwrap().games.is_empty());
+}
+
+#[test]
+fn rejects_other_versions_and_formats() {
+    let text = database().to_toml();
+    let current = format!("version = {}", DB_VERSION);
+    assert!(text.contains(&current));
+
+    let path = std::env::temp_dir().join(format!("gobang-db-bad-{}.toml", std::process::id()));
+    let load = |text: &str| {
+        fs::write(&path, text).unwrap();
+        let result = GameDatabase::load(&path).map(|database| database.games.len());
+        fs::remove_file(&path).unwrap();
+        result
+    };
+    assert_eq!(load(&text), Ok(5));
+    let newer = text.replace(&current, &format!("version = {}", DB_VERSION + 1));
+    assert!(load(&newer).unwrap_err().contains("version"));
+    let zero = text.replace(&current, "version = 0");
+    assert!(load(&zero).unwrap_err().contains("version"));
+    let other_format = text.replace("\"gobang-db\"", "\"gobang-save\"");
+    assert!(
+        load(&other_format)
+            .unwrap_err()
+            .contains("not a gobang game database")
+    );
+    let bad_move = text.replace("\"h8 h9\"", "\"h8 z99\"");
+    let error = load(&bad_move).unwrap_err();
+    assert!(error.contains("game 3: invalid move z99"), "{}", error);
+}
+
+#[test]
+fn merge_skips_known_sources() {
+    let mut database = database();
+    let games = vec![
+        db_game("rif:1", "h8", GameResult::Draw),
+        db_game("rif:6", "h8", GameResult::Draw),
+        db_game("rif:6", "h8 h9", GameResult::Draw),
+        // 没有来源的对局总是加入
+        db_game("", "h8", GameResult::Draw),
+        db_game("", "h8", GameResult::Draw),
+    ];
+    assert_eq!(database.merge(games.clone()), 3);
+    assert_eq!(database.games.len(), 8);
+    assert_eq!(database.games[5].source, "rif:6");
+    assert_eq!(database.games[5].moves.len(), 1);
+    assert_eq!(database.merge(games), 2);
+}
+
+#[test]
+fn filters() {
+    let database = database();
+    let filter = |player: &str, opening: &str, result| GameFilter {
+        player: player.to_string(),
+        opening: opening.to_string(),
+        result,
+    };
+    assert_eq!(database.filtered(&GameFilter::default()).len(), 5);
+    assert_eq!(database.filtered(&filter("ana", "", None)).len(), 5);
+    assert_eq!(database.filtered(&filter("\"wu", "kage", None)).len(), 5);
+    assert!(database.filtered(&filter("jan", "", None)).is_empty());
+    assert!(database.filtered(&filter("", "sosei", None)).is_empty());
+    assert_eq!(
+        database.filtered(&filter("", "", Some(GameResult::Draw))),
+        [1]
+    );
+}
+
+#[test]
+fn position_search_counts_symmetric_games() {
+    let database = database();
+    let all = GameFilter::default();
+
+    // 空棋盘完全对称：a1 代表四个角
+    let stats = database.search_position(&GameState::new(), &all);
+    assert_eq!(stats.games, 5);
+    assert_eq!(
+        moves(&stats),
+        [
+            ("h8".to_string(), (4, 1, 1, 1)),
+            ("a1".to_string(), (1, 0, 0, 0))
+        ]
+    );
+
+    // 只有 h8 时四个斜向的点等价，四个直向的点等价；没有后续着法的对局也计入
+    let stats = database.search_position(&position("h8"), &all);
+    assert_eq!(stats.games, 4);
+    assert_eq!(
+        moves(&stats),
+        [
+            ("g7".to_string(), (3, 1, 1, 0)),
+            ("h7".to_string(), (1, 0, 0, 1))
+        ]
+    );
+
+    // h8 i9 i8 旋转 180 度后是 h8 g7 g8，两局的后续着法合并为一个
+    for current in ["h8 g7", "h8 i9", "h8 i7", "h8 g9"] {
+        let stats = database.search_position(&position(current), &all);
+        assert_eq!(stats.games, 3, "{}", current);
+        assert_eq!(stats.moves.len(), 1, "{}", current);
+        assert_eq!(stats.moves[0].games, 2, "{}", current);
+        assert_eq!(stats.moves[0].black_wins + stats.moves[0].draws, 2);
+    }
+}
+
+#[test]
+fn position_search_maps_moves_to_the_current_board() {
+    let mut database = GameDatabase::default();
+    database.games = vec![db_game("", "h8 h9 a1", GameResult::Win(Stone::Black))];
+    let all = GameFilter::default();
+    // h8 h9 只有左右翻转这一种自身对称，a1 和 o1 等价
+    let stats = database.search_position(&position("h8 h9"), &all);
+    assert_eq!(moves(&stats), [("a1".to_string(), (1, 1, 0, 0))]);
+    // 上下翻转后 h9 变成 h7，a1 变成 a15
+    let stats = database.search_position(&position("h8 h7"), &all);
+    assert_eq!(moves(&stats), [("a15".to_string(), (1, 1, 0, 0))]);
+    // 转 90 度后 h9 变成 g8，a1 变成 o1
+    let stats = database.search_position(&position("h8 g8"), &all);
+    assert_eq!(moves(&stats), [("o1".to_string(), (1, 1, 0, 0))]);
+    // 沿对角线翻转后 h9 变成 i8，a1 不变
+    let stats = database.search_position(&position("h8 i8"), &all);
+    assert_eq!(moves(&stats), [("a1".to_string(), (1, 1, 0, 0))]);
+}
+
+#[test]
+fn position_search_applies_the_filter() {
+    let database = database();
+    let draws = GameFilter {
+        result: Some(GameResult::Draw),
+        ..GameFilter::default()
+    };
+    let stats = database.search_position(&position("h8"), &draws);
+    assert_eq!(stats.games, 1);
+    assert_eq!(moves(&stats), [("g7".to_string(), (1, 0, 1, 0))]);
+
+    // 没有对局到达的局面，以及棋子比对局着法多的局面
+    let all = GameFilter::default();
+    assert_eq!(
+        database.search_position(&position("h8 h9 i9"), &all),
+        PositionStats::default()
+    );
+    assert_eq!(
+        database
+            .search_position(&position("h8 i9 i8 a2"), &all)
+            .games,
+        0
+    );
+}
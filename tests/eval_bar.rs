@@ -0,0 +1,85 @@
+use gobang::agent::{MATE_THRESHOLD, SmartAgent};
+use gobang::eval_bar::black_win_probability;
+use gobang::game::{SCORE_SCALE, Stone, parse_notation, win_probability};
+use gobang::review::{MoveClass, MoveReview, ReviewedMove};
+
+mod common;
+use common::position;
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-9
+}
+
+fn reviewed(text: &str, stone: Stone, score: i32) -> ReviewedMove {
+    let (row, col) = parse_notation(text).expect("无效的着法");
+    ReviewedMove {
+        row,
+        col,
+        stone,
+        best_score: score,
+        score,
+        class: MoveClass::Best,
+    }
+}
+
+/// 分数为 ±SCORE_SCALE 时胜率为 1 / (1 + e^∓1)，两方的胜率之和为 1
+#[test]
+fn win_probability_around_the_scale() {
+    let scale = SCORE_SCALE as i32;
+    assert_eq!(win_probability(0), 0.5);
+    assert!(close(win_probability(scale), 1.0 / (1.0 + (-1.0f64).exp())));
+    assert!(close(win_probability(-scale), 1.0 / (1.0 + 1.0f64.exp())));
+    for score in [1, scale / 2, scale, 2 * scale, 10 * scale] {
+        assert!(close(win_probability(score) + win_probability(-score), 1.0));
+        assert!(win_probability(score) > win_probability(score - 1));
+    }
+}
+
+/// 已算出胜负的分数换算为确定的胜负，极端分数也不会得到 NaN
+#[test]
+fn mate_scores_are_certain() {
+    assert_eq!(win_probability(MATE_THRESHOLD), 1.0);
+    assert_eq!(win_probability(-MATE_THRESHOLD), 0.0);
+    assert_eq!(win_probability(i32::MAX), 1.0);
+    assert_eq!(win_probability(i32::MIN), 0.0);
+    assert!(win_probability(20 * SCORE_SCALE as i32) > 0.999_999);
+}
+
+/// 评估条取最后一步的分数，按落子一方换算为黑方胜率
+#[test]
+fn eval_bar_follows_the_last_move() {
+    let scale = SCORE_SCALE as i32;
+    let game_state = position("h8 h9");
+    let mut review = MoveReview::default();
+    assert_eq!(black_win_probability(&game_state, &review), Some(0.5));
+
+    review.moves.push(reviewed("h8", Stone::Black, scale));
+    let black = black_win_probability(&game_state, &review).unwrap();
+    assert!(close(black, win_probability(scale)));
+
+    review.moves.push(reviewed("h9", Stone::White, scale));
+    let black = black_win_probability(&game_state, &review).unwrap();
+    assert!(close(black, 1.0 - win_probability(scale)));
+
+    // 已经重新开局：记录的落子不在棋盘上
+    let restarted = position("i9");
+    assert_eq!(black_win_probability(&restarted, &review), Some(0.5));
+}
+
+/// 最后一步还在后台评价时不沿用上一步的胜率
+#[test]
+fn eval_bar_is_pending_while_grading() {
+    let game_state = position("h8");
+    let mut review = MoveReview::default();
+    review
+        .moves
+        .push(reviewed("h8", Stone::Black, SCORE_SCALE as i32));
+    let (row, col) = parse_notation("h9").unwrap();
+    let ai = SmartAgent::new(Stone::White, 2);
+    review.grade(&ai, &game_state, (row, col), Stone::White, false);
+
+    let mut after = game_state.clone();
+    after.apply_move(row, col, Stone::White).unwrap();
+    after.current_turn = Stone::Black;
+    assert_eq!(black_win_probability(&after, &review), None);
+}
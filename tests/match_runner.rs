@@ -0,0 +1,90 @@
+use gobang::match_runner::{
+    EngineConfig, MatchConfig, default_openings, parse_openings, run_match, schedule,
+};
+
+#[test]
+fn default_openings_are_valid() {
+    assert_eq!(default_openings().len(), 10);
+}
+
+#[test]
+fn schedule_plays_each_opening_with_both_colours() {
+    let openings = 3;
+    let games: Vec<(usize, bool)> = (0..openings * 2).map(|i| schedule(i, openings)).collect();
+    assert_eq!(
+        games,
+        [
+            (0, true),
+            (0, false),
+            (1, true),
+            (1, false),
+            (2, true),
+            (2, false)
+        ]
+    );
+}
+
+#[test]
+fn schedule_wraps_around_openings() {
+    assert_eq!(schedule(6, 3), (0, true));
+    assert_eq!(schedule(7, 3), (0, false));
+    assert_eq!(schedule(11, 3), (2, false));
+}
+
+// 黑棋已有活四，第一步即连五，对局很快结束
+const WON_OPENINGS: &str = "h8 a1 h9 a3 h10 a5 h11 a7\nb8 o1 b9 o3 b10 o5 b11 o7";
+
+fn config(a: &str, b: &str, games: usize) -> MatchConfig {
+    MatchConfig {
+        engine_a: EngineConfig::from_spec(a, "A").unwrap(),
+        engine_b: EngineConfig::from_spec(b, "B").unwrap(),
+        openings: parse_openings(WON_OPENINGS).unwrap(),
+        games,
+        concurrency: 2,
+        sprt: None,
+    }
+}
+
+#[test]
+fn deterministic_engines() {
+    assert!(
+        EngineConfig::from_spec("depth=2", "A")
+            .unwrap()
+            .is_deterministic()
+    );
+    assert!(
+        EngineConfig::from_spec("skill=7", "A")
+            .unwrap()
+            .is_deterministic()
+    );
+    assert!(
+        !EngineConfig::from_spec("skill=3", "A")
+            .unwrap()
+            .is_deterministic()
+    );
+    assert!(
+        !EngineConfig::from_spec("skill=9", "A")
+            .unwrap()
+            .is_deterministic()
+    );
+    assert!(
+        !EngineConfig::from_spec("depth=2,time=50", "A")
+            .unwrap()
+            .is_deterministic()
+    );
+
+    assert_eq!(config("depth=1", "depth=1", 10).distinct_games(), Some(4));
+    assert_eq!(config("depth=1", "skill=2", 10).distinct_games(), None);
+}
+
+#[test]
+fn deterministic_match_does_not_repeat_games() {
+    let mut played = Vec::new();
+    let stats = run_match(&config("depth=1", "depth=1", 10), |result, _| {
+        played.push(result.index);
+    });
+    played.sort();
+    assert_eq!(played, [0, 1, 2, 3]);
+    // 黑棋都赢，A 与 B 各执黑两局
+    assert_eq!((stats.wins, stats.losses), (2, 2));
+}
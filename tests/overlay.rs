@@ -0,0 +1,120 @@
+use gobang::game::{Stone, move_to_notation};
+use gobang::overlay::{key_points, shown_threats};
+use gobang::patterns::{Pattern, Threat};
+use std::collections::HashSet;
+
+mod common;
+use common::position;
+
+fn cells(cells: &[(usize, usize)]) -> Vec<String> {
+    let mut cells: Vec<String> = cells
+        .iter()
+        .map(|&(row, col)| move_to_notation(row, col))
+        .collect();
+    cells.sort();
+    cells
+}
+
+/// 叠加层中的威胁，写成 (棋型, 一方, 棋子)
+fn shown(moves: &str) -> Vec<(Pattern, Stone, Vec<String>)> {
+    shown_threats(&position(moves))
+        .into_iter()
+        .map(|t| (t.pattern, t.stone, cells(&t.stones)))
+        .collect()
+}
+
+fn threat(moves: &str, stone: Stone) -> Threat {
+    let mut threats = shown_threats(&position(moves));
+    threats.retain(|t| t.stone == stone);
+    assert_eq!(threats.len(), 1, "{}", moves);
+    threats.remove(0)
+}
+
+fn strings(texts: &[&str]) -> Vec<String> {
+    texts.iter().map(|text| text.to_string()).collect()
+}
+
+#[test]
+fn open_threes_and_their_key_points() {
+    // 连三和跳三都标出，关键点包括两端和中间的空位
+    let three = threat("h8 a1 i8 a3 j8", Stone::Black);
+    assert_eq!(three.pattern, Pattern::OpenThree);
+    assert_eq!(cells(&three.stones), strings(&["h8", "i8", "j8"]));
+    assert!(three.points.contains(&(7, 6)) && three.points.contains(&(7, 10)));
+
+    let split = threat("h8 a1 i8 a3 k8", Stone::Black);
+    assert_eq!(split.pattern, Pattern::OpenThree);
+    assert_eq!(cells(&split.stones), strings(&["h8", "i8", "k8"]));
+    assert!(split.points.contains(&(7, 9)), "{:?}", cells(&split.points));
+}
+
+#[test]
+fn fours_have_the_completing_point() {
+    // 一端被挡的连四只有一个成五点，跳四的成五点在中间
+    let four = threat("h8 g8 i8 a1 j8 a3 k8", Stone::Black);
+    assert_eq!(four.pattern, Pattern::Four);
+    assert_eq!(cells(&four.points), strings(&["l8"]));
+
+    let split = threat("h8 a1 i8 a3 k8 a5 l8", Stone::Black);
+    assert_eq!(split.pattern, Pattern::Four);
+    assert_eq!(cells(&split.points), strings(&["j8"]));
+
+    let open = threat("h8 a1 i8 a3 j8 a5 k8", Stone::Black);
+    assert_eq!(open.pattern, Pattern::OpenFour);
+    assert_eq!(cells(&open.points), strings(&["g8", "l8"]));
+}
+
+#[test]
+fn quiet_shapes_are_not_shown() {
+    assert!(shown("").is_empty());
+    // 活二和被挡住一端的眠三
+    assert!(shown("h8 a1 i8").is_empty());
+    assert!(shown("h8 g8 i8 a1 j8").is_empty());
+}
+
+/// 双方的威胁都标出，四排在活三之前
+#[test]
+fn both_sides_are_marked_in_order_of_urgency() {
+    // 黑方 h8-j8 活三，白方 b7-b10 被 b11 黑子挡住的冲四
+    let found = shown("h8 b7 i8 b8 j8 b9 b11 b10");
+    assert_eq!(
+        found,
+        [
+            (
+                Pattern::Four,
+                Stone::White,
+                strings(&["b10", "b7", "b8", "b9"])
+            ),
+            (
+                Pattern::OpenThree,
+                Stone::Black,
+                strings(&["h8", "i8", "j8"])
+            ),
+        ]
+    );
+}
+
+#[test]
+fn shared_key_points_are_marked_once_per_side() {
+    // 黑方 h8 i8 j8 横向活三和 k9 k10 k11 纵向活三，k8 是两者共同的关键点
+    let game_state = position("h8 a1 i8 a3 j8 a5 k9 a7 k10 a9 k11");
+    let threats = shown_threats(&game_state);
+    let black: Vec<&Threat> = threats.iter().filter(|t| t.stone == Stone::Black).collect();
+    assert_eq!(black.len(), 2);
+    let points = key_points(&threats);
+    let k8 = points.iter().filter(|(p, _, _)| *p == (7, 10)).count();
+    assert_eq!(k8, 1, "{:?}", points);
+    let unique: HashSet<_> = points
+        .iter()
+        .map(|&(p, stone, _)| (p, stone as usize))
+        .collect();
+    assert_eq!(unique.len(), points.len());
+
+    // 高一级的威胁的关键点取高的棋型：冲四的成五点同时是另一个活三的关键点
+    let game_state = position("h8 g8 i8 a1 j8 a3 k8 a5 l9 a7 l10");
+    let threats = shown_threats(&game_state);
+    let points = key_points(&threats);
+    let l8: Vec<_> = points.iter().filter(|(p, _, _)| *p == (7, 11)).collect();
+    assert_eq!(l8.len(), 1, "{:?}", points);
+    assert_eq!(l8[0].2, Pattern::Four);
+}
@@ -0,0 +1,102 @@
+use gobang::agent::SmartAgent;
+use gobang::game::{GameState, Stone, parse_notation};
+use gobang::patterns::{PATTERN_COUNT, Pattern, analyse_cell, analyse_position};
+
+const CORPUS: &str = include_str!("data/patterns.txt");
+
+struct Case {
+    name: String,
+    state: GameState,
+    cell: Option<(usize, usize)>,
+    black: [u32; PATTERN_COUNT],
+    white: [u32; PATTERN_COUNT],
+}
+
+fn parse_counts(text: &str) -> [u32; PATTERN_COUNT] {
+    let mut counts = [0; PATTERN_COUNT];
+    for item in text.split_whitespace() {
+        let (key, value) = item.split_once('=').expect("棋型数应写成 key=value");
+        let pattern = Pattern::from_key(key).unwrap_or_else(|| panic!("未知的棋型: {}", key));
+        counts[pattern as usize] = value.parse().expect("棋型数不是整数");
+    }
+    counts
+}
+
+fn parse_corpus() -> Vec<Case> {
+    let mut cases = Vec::new();
+    for block in CORPUS.split("\n\n") {
+        let lines: Vec<&str> = block
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+            .collect();
+        if lines.is_empty() {
+            continue;
+        }
+
+        let mut case = Case {
+            name: String::new(),
+            state: GameState::new(),
+            cell: None,
+            black: [0; PATTERN_COUNT],
+            white: [0; PATTERN_COUNT],
+        };
+        for line in lines {
+            let (key, value) = line.split_once(':').expect("每行应写成 key: value");
+            let value = value.trim();
+            match key {
+                "name" => case.name = value.to_string(),
+                "black" | "white" => {
+                    let stone = if key == "black" {
+                        Stone::Black
+                    } else {
+                        Stone::White
+                    };
+                    for text in value.split_whitespace() {
+                        let (row, col) = parse_notation(text).expect("无效的位置");
+                        case.state.board[row][col] = Some(stone);
+                    }
+                }
+                "cell" => case.cell = Some(parse_notation(value).expect("无效的位置")),
+                "expect black" => case.black = parse_counts(value),
+                "expect white" => case.white = parse_counts(value),
+                _ => panic!("未知的键: {}", key),
+            }
+        }
+        cases.push(case);
+    }
+    cases
+}
+
+#[test]
+fn corpus_patterns_match_labels() {
+    let cases = parse_corpus();
+    assert!(!cases.is_empty());
+    for case in cases {
+        let report = match case.cell {
+            Some((row, col)) => analyse_cell(&case.state, row, col),
+            None => analyse_position(&case.state),
+        };
+        assert_eq!(report.black.as_array(), case.black, "{}: 黑方", case.name);
+        assert_eq!(report.white.as_array(), case.white, "{}: 白方", case.name);
+    }
+}
+
+#[test]
+fn agent_features_use_the_pattern_counts() {
+    for case in parse_corpus().into_iter().filter(|c| c.cell.is_none()) {
+        let report = analyse_position(&case.state);
+        for stone in [Stone::Black, Stone::White] {
+            let features = SmartAgent::board_features(&case.state, stone);
+            for pattern in Pattern::ALL {
+                assert_eq!(
+                    features[pattern.feature() as usize],
+                    report.get(stone).windows(pattern) as i32,
+                    "{}: {:?} {}",
+                    case.name,
+                    stone,
+                    pattern.name()
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use gobang::piskvork::{BoardField, Command, format_coords, parse_board_line, parse_coords};
+use gobang::position::Position;
+use std::io::Write;
+use std::process::{self, Stdio};
+
+mod common;
+use common::position;
+
+/// 运行 pbrain-gobang，一次写入全部命令，返回引擎的全部输出行 (收到 END 后引擎退出)
+fn run_engine(input: &str) -> Vec<String> {
+    let mut child = process::Command::new(env!("CARGO_BIN_EXE_pbrain-gobang"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("无法启动引擎");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+#[test]
+fn coordinates() {
+    // 协议坐标为 "x,y"，即 (列, 行)
+    assert_eq!(parse_coords("7,8"), Some((8, 7)));
+    assert_eq!(parse_coords(" 0 , 14 "), Some((14, 0)));
+    assert_eq!(format_coords(8, 7), "7,8");
+    for bad in ["15,0", "0,15", "7", "7,8,9", "-1,2", "a,b", ""] {
+        assert_eq!(parse_coords(bad), None, "{}", bad);
+    }
+}
+
+#[test]
+fn board_lines() {
+    assert_eq!(parse_board_line("7,8,1"), Some((8, 7, BoardField::Own)));
+    assert_eq!(
+        parse_board_line(" 0,14,2 "),
+        Some((14, 0, BoardField::Opponent))
+    );
+    // 3 表示连续棋局中的棋子，不支持
+    assert_eq!(parse_board_line("7,8,3"), None);
+    for bad in ["7,8", "7,8,x", "15,0,1", "7,,1", ""] {
+        assert_eq!(parse_board_line(bad), None, "{}", bad);
+    }
+}
+
+#[test]
+fn commands() {
+    let cases = [
+        ("START 15", Command::Start(15)),
+        ("RECTSTART 20,15", Command::RectStart(20, 15)),
+        ("restart", Command::Restart),
+        ("  BEGIN  ", Command::Begin),
+        ("TURN 7,8", Command::Turn(8, 7)),
+        ("turn 0,14", Command::Turn(14, 0)),
+        ("BOARD", Command::Board),
+        ("TAKEBACK 3,4", Command::Takeback(4, 3)),
+        ("END", Command::End),
+        ("ABOUT", Command::About),
+        ("PLAY 7,8", Command::Unknown("PLAY 7,8".to_string())),
+    ];
+    for (line, expected) in cases {
+        assert_eq!(line.parse::<Command>(), Ok(expected.clone()), "{}", line);
+        assert_eq!(expected.to_string().parse::<Command>(), Ok(expected));
+    }
+
+    for bad in ["START x", "RECTSTART 20", "TURN 7", "TURN 15,0", "TAKEBACK"] {
+        assert!(bad.parse::<Command>().is_err(), "{}", bad);
+    }
+}
+
+#[test]
+fn info_commands() {
+    let info = |line: &str| line.parse::<Command>().unwrap();
+    assert_eq!(
+        info("INFO timeout_turn 5000"),
+        Command::Info("timeout_turn".to_string(), "5000".to_string())
+    );
+    // 键名不区分大小写，值保留其中的空格
+    assert_eq!(
+        info("INFO Rule 1"),
+        Command::Info("rule".to_string(), "1".to_string())
+    );
+    assert_eq!(
+        info("INFO folder C:\\some dir"),
+        Command::Info("folder".to_string(), "C:\\some dir".to_string())
+    );
+    assert_eq!(info("INFO"), Command::Info(String::new(), String::new()));
+}
+
+/// 引擎按协议回复：BOARD 中错误的行 (包括字段 3) 逐行报错但不中断，DONE 之后落子；
+/// 每轮迭代加深用 MESSAGE 报告
+#[test]
+fn engine_session() {
+    let output = run_engine(
+        "START 20\n\
+         START 15\n\
+         INFO timeout_turn 200\n\
+         INFO rule 1\n\
+         BOARD\n\
+         7,7,2\n\
+         8,8,1\n\
+         7,8,2\n\
+         9,9,3\n\
+         oops\n\
+         DONE\n\
+         TAKEBACK 0,0\n\
+         TURN 99,0\n\
+         FOO\n\
+         ABOUT\n\
+         END\n",
+    );
+    let (messages, replies): (Vec<_>, Vec<_>) =
+        output.iter().partition(|line| line.starts_with("MESSAGE "));
+    assert!(!messages.is_empty(), "{:?}", output);
+    for (i, message) in messages.iter().enumerate() {
+        let prefix = format!("MESSAGE depth {} ", i + 1);
+        assert!(message.starts_with(&prefix), "{}", message);
+    }
+
+    assert_eq!(replies.len(), 9, "{:?}", replies);
+    assert_eq!(replies[0], "ERROR unsupported board size 20");
+    assert_eq!(replies[1], "OK");
+    assert_eq!(replies[2], "ERROR bad board line 9,9,3");
+    assert_eq!(replies[3], "ERROR bad board line oops");
+    let reply = parse_coords(replies[4]).expect("落子");
+    assert!(![(7, 7), (8, 8), (8, 7)].contains(&reply), "{:?}", reply);
+    assert_eq!(replies[5], "ERROR no stone at 0,0");
+    assert_eq!(replies[6], "ERROR bad coordinates: 99,0");
+    assert_eq!(replies[7], "UNKNOWN FOO");
+    assert!(replies[8].starts_with("name=\"gobang\""), "{}", replies[8]);
+
+    // MESSAGE 都在落子之前
+    let move_index = output.iter().position(|line| line == replies[4]).unwrap();
+    assert!(
+        output[move_index + 1..]
+            .iter()
+            .all(|line| !line.starts_with("MESSAGE "))
+    );
+}
+
+/// --position 模式每完成一轮迭代加深输出一行 info，深度逐行加一，最后给出最佳着法
+#[test]
+fn analyse_mode_reports_each_depth() {
+    let output = process::Command::new(env!("CARGO_BIN_EXE_pbrain-gobang"))
+        .arg("--position")
+        .arg(Position::from_game(&position("h8 h9")).to_string())
+        .args(["--time", "300"])
+        .output()
+        .expect("无法启动引擎");
+    assert!(output.status.success());
+    let text = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    let (bestmove, infos) = lines.split_last().expect("有输出");
+
+    assert!(!infos.is_empty(), "{}", text);
+    for (i, line) in infos.iter().enumerate() {
+        assert!(
+            line.starts_with(&format!("info depth {} score ", i + 1)),
+            "{}",
+            line
+        );
+    }
+    let best = bestmove.strip_prefix("bestmove ").expect("最佳着法");
+    let last_pv = infos.last().unwrap().split(" pv ").nth(1).unwrap();
+    assert_eq!(last_pv.split(' ').next(), Some(best));
+}
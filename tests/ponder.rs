@@ -0,0 +1,153 @@
+use gobang::agent::SmartAgent;
+use gobang::game::Stone;
+use std::thread;
+use std::time::{Duration, Instant};
+
+mod common;
+use common::position;
+
+/// 猜中后后台搜索还没结束时立即返回，不阻塞调用者；
+/// 后台搜索不超过每步时间上限，结束后给出结果
+#[test]
+fn ponder_hit_does_not_block_and_respects_time_limit() {
+    let time_limit = Duration::from_millis(300);
+    let mut ai = SmartAgent::new(Stone::White, 10);
+    ai.set_time_limit(Some(time_limit));
+
+    let mut game_state = position("h8 h9 i10");
+    let info = ai.search(&game_state).expect("有可走的落子");
+    let (row, col) = info.best_move;
+    game_state.apply_move(row, col, Stone::White).unwrap();
+    game_state.current_turn = Stone::Black;
+
+    let started = Instant::now();
+    let mut ponder = ai
+        .start_ponder(&game_state, &info)
+        .expect("主要变例中有猜测的应手");
+    let (row, col) = ponder.guess();
+    game_state.apply_move(row, col, Stone::Black).unwrap();
+    game_state.current_turn = Stone::White;
+    assert!(ponder.is_hit(&game_state));
+
+    let mut pending = 0;
+    let chosen = loop {
+        let polled = Instant::now();
+        match ai.choose_move_after_ponder(&game_state, ponder) {
+            Ok(chosen) => break chosen,
+            Err(waiting) => {
+                assert!(polled.elapsed() < Duration::from_millis(50), "blocked");
+                pending += 1;
+                ponder = *waiting;
+                thread::sleep(Duration::from_millis(10));
+            }
+        }
+        assert!(
+            started.elapsed() < time_limit * 10,
+            "ponder ignores the time limit"
+        );
+    };
+    assert!(pending > 0, "depth 10 should not finish instantly");
+    let chosen = chosen.expect("有可走的落子");
+    assert!(game_state.board[chosen.best_move.0][chosen.best_move.1].is_none());
+}
+
+/// 改变了搜索参数时后台搜索的结果不再可用
+#[test]
+fn ponder_is_discarded_when_search_settings_change() {
+    let ai = SmartAgent::with_skill_level(Stone::White, 7);
+    let mut game_state = position("h8 h9 i10");
+    let info = ai.search(&game_state).expect("有可走的落子");
+    let (row, col) = info.best_move;
+    game_state.apply_move(row, col, Stone::White).unwrap();
+    game_state.current_turn = Stone::Black;
+    let ponder = ai.start_ponder(&game_state, &info).expect("有猜测的应手");
+
+    assert!(ponder.matches(&ai));
+    // 6 级与 7 级的深度相同，但多了评估扰动，搜索结果不同
+    assert!(!ponder.matches(&SmartAgent::with_skill_level(Stone::White, 6)));
+    let mut deeper = ai.clone();
+    deeper.set_depth(4);
+    assert!(!ponder.matches(&deeper));
+    let mut other_side = ai.clone();
+    other_side.set_stone(Stone::Black);
+    assert!(!ponder.matches(&other_side));
+}
+
+/// 不影响搜索的改动 (重新选择同一难度、更换开局库、切换热力图等只改界面的开关)
+/// 之后后台思考继续有效，猜中后给出落子
+#[test]
+fn ponder_is_kept_when_settings_outside_the_search_change() {
+    let mut ai = SmartAgent::with_skill_level(Stone::White, 7);
+    let mut game_state = position("h8 h9 i10");
+    let info = ai.search(&game_state).expect("有可走的落子");
+    let (row, col) = info.best_move;
+    game_state.apply_move(row, col, Stone::White).unwrap();
+    game_state.current_turn = Stone::Black;
+    let ponder = ai.start_ponder(&game_state, &info).expect("有猜测的应手");
+
+    ai.set_skill_level(7);
+    ai.set_book(None);
+    assert!(ponder.matches(&ai));
+    // 界面开关不改变棋盘，局面仍可能走到猜测的局面
+    assert!(!ponder.is_stale(&game_state));
+
+    let (row, col) = ponder.guess();
+    game_state.apply_move(row, col, Stone::Black).unwrap();
+    game_state.current_turn = Stone::White;
+    assert!(ponder.is_hit(&game_state));
+    assert!(!ponder.is_stale(&game_state));
+
+    let mut ponder = ponder;
+    let chosen = loop {
+        match ai.choose_move_after_ponder(&game_state, ponder) {
+            Ok(chosen) => break chosen,
+            Err(waiting) => {
+                ponder = *waiting;
+                thread::sleep(Duration::from_millis(10));
+            }
+        }
+    };
+    let chosen = chosen.expect("有可走的落子");
+    assert!(game_state.board[chosen.best_move.0][chosen.best_move.1].is_none());
+}
+
+/// AI 自己回合的搜索在后台进行：开始后立即返回，搜完后给出与同步搜索相同深度的结果；
+/// 局面改变后旧的搜索作废，重新开始
+#[test]
+fn background_search_does_not_block() {
+    let mut ai = SmartAgent::new(Stone::White, 4);
+    let game_state = position("h8 h9 i10");
+
+    let started = Instant::now();
+    let mut thinking = match ai.choose_move_in_background(&game_state, None) {
+        Ok(info) => panic!("searched synchronously: {:?}", info),
+        Err(thinking) => thinking,
+    };
+    assert!(started.elapsed() < Duration::from_millis(50), "blocked");
+    let chosen = loop {
+        match ai.choose_move_in_background(&game_state, Some(*thinking)) {
+            Ok(chosen) => break chosen,
+            Err(waiting) => {
+                thinking = waiting;
+                thread::sleep(Duration::from_millis(10));
+            }
+        }
+    };
+    let chosen = chosen.expect("有可走的落子");
+    assert_eq!(chosen.depth, 4);
+    assert!(game_state.board[chosen.best_move.0][chosen.best_move.1].is_none());
+
+    let thinking = ai
+        .choose_move_in_background(&game_state, None)
+        .expect_err("需要搜索");
+    let moved = position("h8 h9 i10 i9 j10");
+    assert!(thinking.is_stale(&moved, &ai));
+    assert!(!thinking.is_stale(&game_state, &ai));
+    ai.set_depth(3);
+    assert!(thinking.is_stale(&game_state, &ai));
+    // 过时的搜索被丢弃，按新的设置重新开始
+    let restarted = ai
+        .choose_move_in_background(&game_state, Some(*thinking))
+        .expect_err("重新开始搜索");
+    assert!(!restarted.is_stale(&game_state, &ai));
+}
@@ -0,0 +1,127 @@
+use gobang::game::{GRID_SIZE, GameState, Stone};
+use gobang::position::Position;
+
+mod common;
+
+const EMPTY: &str = "15 15/15/15/15/15/15/15/15/15/15/15/15/15/15/15 b freestyle";
+
+fn position(moves: &str) -> Position {
+    Position::from_game(&common::position(moves))
+}
+
+fn round_trip(position: &Position) {
+    let text = position.to_string();
+    assert_eq!(text.parse::<Position>().as_ref(), Ok(position), "{}", text);
+}
+
+#[test]
+fn empty_board() {
+    let empty = Position::from_game(&GameState::new());
+    assert_eq!(empty.to_string(), EMPTY);
+    round_trip(&empty);
+    assert!(empty.saved_game().moves.is_empty());
+}
+
+#[test]
+fn full_board() {
+    // 黑白相间摆满棋盘：113 个黑子、112 个白子，轮到白方
+    let mut full = Position::from_game(&GameState::new());
+    for row in 0..=GRID_SIZE {
+        for col in 0..=GRID_SIZE {
+            full.board[row][col] = Some(if (row + col) % 2 == 0 {
+                Stone::Black
+            } else {
+                Stone::White
+            });
+        }
+    }
+    full.side_to_move = Stone::White;
+    let text = full.to_string();
+    assert!(
+        text.starts_with("15 xoxoxoxoxoxoxox/oxoxoxoxoxoxoxo/"),
+        "{}",
+        text
+    );
+    round_trip(&full);
+}
+
+#[test]
+fn mixed_board_and_side_to_move() {
+    // h8 在第 8 行的第 8 个点，i9 在第 9 行
+    let white_to_move = position("h8");
+    assert_eq!(
+        white_to_move.to_string(),
+        "15 15/15/15/15/15/15/15/7x7/15/15/15/15/15/15/15 w freestyle"
+    );
+    round_trip(&white_to_move);
+
+    let corners = position("h8 i9 a1 o15 o1");
+    let text = corners.to_string();
+    assert_eq!(
+        text,
+        "15 14o/15/15/15/15/15/8o6/7x7/15/15/15/15/15/15/x13x w freestyle"
+    );
+    round_trip(&corners);
+    let black_to_move = position("h8 i9");
+    assert_eq!(black_to_move.side_to_move, Stone::Black);
+    assert!(black_to_move.to_string().ends_with(" b freestyle"));
+    round_trip(&black_to_move);
+
+    // 摆出的局面与原局面相同
+    let parsed: Position = text.parse().unwrap();
+    let replayed = Position::from_game(&parsed.saved_game().replay());
+    assert_eq!(replayed, corners);
+}
+
+#[test]
+fn rejects_bad_fields() {
+    let cases = [
+        ("", "expected 4 fields"),
+        ("15 15/15 b", "expected 4 fields"),
+        (&EMPTY.replace("15 ", "19 ")[..], "board size"),
+        (&EMPTY.replace("freestyle", "renju")[..], "rule"),
+        (&EMPTY.replace(" b ", " x ")[..], "side to move"),
+        (
+            &EMPTY.replace(" b ", " w ")[..],
+            "cannot have white to move",
+        ),
+    ];
+    for (text, expected) in cases {
+        let error = text.parse::<Position>().unwrap_err();
+        assert!(error.contains(expected), "{:?}: {}", text, error);
+    }
+}
+
+#[test]
+fn rejects_bad_rows() {
+    let row = |text: &str| EMPTY.replacen("15/", &format!("{}/", text), 1);
+    let cases = [
+        (EMPTY.replacen("15/", "", 1), "expected 15 rows, got 14"),
+        (
+            EMPTY.replacen("15/", "15/15/", 1),
+            "expected 15 rows, got 16",
+        ),
+        (row("14"), "row 15: 14 points instead of 15"),
+        (row("16"), "row 15: more than 15 points"),
+        (row("15x"), "row 15: more than 15 points"),
+        (row("x14x"), "row 15: more than 15 points"),
+        (row("7y7"), "row 15: unexpected character 'y'"),
+        (row(""), "row 15: 0 points instead of 15"),
+    ];
+    for (text, expected) in cases {
+        let error = text.parse::<Position>().unwrap_err();
+        assert!(error.contains(expected), "{:?}: {}", text, error);
+    }
+}
+
+#[test]
+fn rejects_overflowing_runs() {
+    for run in ["99999999999999999999999", "18446744073709551615", "015999"] {
+        let text = EMPTY.replacen("15/", &format!("{}/", run), 1);
+        let error = text.parse::<Position>().unwrap_err();
+        assert!(error.contains("more than 15 points"), "{}: {}", run, error);
+    }
+    // 前导 0 不改变空位个数
+    let text = EMPTY.replacen("15/", "0015/", 1);
+    assert_eq!(text.parse::<Position>(), EMPTY.parse::<Position>());
+}
@@ -0,0 +1,96 @@
+use gobang::elo::Glicko2;
+use gobang::profile::PlayerProfile;
+use std::fs;
+
+fn close(a: f64, b: f64, tolerance: f64) -> bool {
+    (a - b).abs() <= tolerance
+}
+
+/// Glickman 的 Glicko-2 论文中的算例 (τ = 0.5)：1500 ± 200 的玩家在一个评分周期内
+/// 胜 1400 ± 30，负 1550 ± 100 和 1700 ± 300
+#[test]
+fn glicko2_matches_the_published_example() {
+    let player = Glicko2 {
+        rating: 1500.0,
+        deviation: 200.0,
+        volatility: 0.06,
+    };
+    let opponent = |rating: f64, deviation: f64| Glicko2 {
+        rating,
+        deviation,
+        volatility: 0.06,
+    };
+    let updated = player.update_period(&[
+        (opponent(1400.0, 30.0), 1.0),
+        (opponent(1550.0, 100.0), 0.0),
+        (opponent(1700.0, 300.0), 0.0),
+    ]);
+    assert!(close(updated.rating, 1464.06, 0.01), "{:?}", updated);
+    assert!(close(updated.deviation, 151.52, 0.01), "{:?}", updated);
+    assert!(close(updated.volatility, 0.05999, 0.00001), "{:?}", updated);
+}
+
+#[test]
+fn glicko2_single_game_and_empty_period() {
+    let player = Glicko2::default();
+    let opponent = Glicko2 {
+        rating: 1600.0,
+        deviation: 50.0,
+        ..Glicko2::default()
+    };
+    assert_eq!(
+        player.update(&opponent, 1.0),
+        player.update_period(&[(opponent, 1.0)])
+    );
+    assert!(player.update(&opponent, 1.0).rating > player.rating);
+    assert!(player.update(&opponent, 0.0).rating < player.rating);
+
+    // 没有对局的周期只增大评分偏差
+    let idle = opponent.update_period(&[]);
+    assert_eq!(idle.rating, opponent.rating);
+    assert!(idle.deviation > opponent.deviation);
+}
+
+#[test]
+fn profile_round_trip() {
+    let mut profile = PlayerProfile::new("Round \"trip\"");
+    profile.record_game(3, 1.0, 82, 1_700_000_000);
+    profile.record_game(7, 0.5, 64, 1_700_000_600);
+    profile.record_game(7, 0.0, 41, 1_700_001_200);
+
+    let path = std::env::temp_dir().join(format!("gobang-profile-{}.toml", std::process::id()));
+    fs::write(&path, profile.to_toml()).unwrap();
+    let loaded = PlayerProfile::load(&path);
+    fs::remove_file(&path).unwrap();
+    let loaded = loaded.unwrap();
+
+    // 评分保存为一位小数，波动率六位小数
+    let same_rating = |a: &Glicko2, b: &Glicko2| {
+        close(a.rating, b.rating, 0.05)
+            && close(a.deviation, b.deviation, 0.05)
+            && close(a.volatility, b.volatility, 1e-6)
+    };
+    assert_eq!(loaded.name, profile.name);
+    assert!(same_rating(&loaded.rating, &profile.rating));
+    assert_eq!(
+        loaded.level_ratings.keys().collect::<Vec<_>>(),
+        vec![&3, &7]
+    );
+    for (level, rating) in &profile.level_ratings {
+        assert!(
+            same_rating(&loaded.level_ratings[level], rating),
+            "level {}",
+            level
+        );
+    }
+    assert_eq!(loaded.stats, profile.stats);
+    assert_eq!(loaded.games.len(), 3);
+    for (loaded, game) in loaded.games.iter().zip(&profile.games) {
+        assert_eq!(
+            (loaded.time, loaded.level, loaded.result, loaded.accuracy),
+            (game.time, game.level, game.result, game.accuracy)
+        );
+        assert!(close(loaded.rating, game.rating, 0.05));
+    }
+    assert_eq!(loaded.games_against(7), 2);
+}
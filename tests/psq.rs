@@ -0,0 +1,96 @@
+use gobang::game::{GRID_SIZE, Stone, move_to_notation, parse_notation};
+use gobang::psq::{PsqGame, parse_psq_point, psq_point};
+use gobang::savegame::GameResult;
+
+// 两个引擎的一局，黑方第 9 手在第 8 行连五
+const GAME: &str = include_str!("data/game.psq");
+
+fn notation(game: &PsqGame) -> Vec<String> {
+    game.moves
+        .iter()
+        .map(|m| move_to_notation(m.row, m.col))
+        .collect()
+}
+
+#[test]
+fn coordinates() {
+    // Piskvork 的原点在左上角，从 1 开始
+    assert_eq!(psq_point(GRID_SIZE, 0), (1, 1));
+    assert_eq!(psq_point(0, 0), (1, 15));
+    let (row, col) = parse_notation("h8").unwrap();
+    assert_eq!(psq_point(row, col), (8, 8));
+    for row in 0..=GRID_SIZE {
+        for col in 0..=GRID_SIZE {
+            let (x, y) = psq_point(row, col);
+            assert_eq!(parse_psq_point(x as u64, y as u64), Some((row, col)));
+        }
+    }
+    for (x, y) in [(0, 1), (1, 0), (16, 1), (1, 16)] {
+        assert_eq!(parse_psq_point(x, y), None);
+    }
+}
+
+#[test]
+fn parse_fixture() {
+    let game = PsqGame::parse(GAME).unwrap();
+    assert_eq!(game.black, "pbrain-alpha.exe");
+    assert_eq!(game.white, "pbrain-beta.exe");
+    assert_eq!(
+        notation(&game),
+        ["h8", "h9", "i8", "i9", "j8", "j9", "k8", "k9", "l8"]
+    );
+    let stones: Vec<Stone> = game.moves.iter().map(|m| m.stone).collect();
+    assert_eq!(stones[..3], [Stone::Black, Stone::White, Stone::Black]);
+    // 导入的 time 是从开局起累计的思考时间
+    let times: Vec<u64> = game.moves.iter().map(|m| m.time).collect();
+    assert_eq!(times, [0, 1520, 2350, 4560, 5200, 7175, 7585, 10635, 10755]);
+
+    let final_position = game.saved_game(game.moves.len()).replay();
+    assert!(final_position.is_game_over);
+    assert_eq!(final_position.winner, Some(Stone::Black));
+    let start = game.saved_game(0);
+    assert!(start.moves.is_empty());
+    assert_eq!(start.result, GameResult::Ongoing);
+    assert_eq!(game.saved_game(100).moves.len(), game.moves.len());
+}
+
+#[test]
+fn export_round_trip() {
+    let game = PsqGame::parse(GAME).unwrap();
+    assert_eq!(game.to_psq(), GAME);
+    assert_eq!(PsqGame::parse(&game.to_psq()), Ok(game.clone()));
+
+    // 导出时换行不能出现在名字中
+    let mut renamed = game;
+    renamed.black = "two\nlines".to_string();
+    let parsed = PsqGame::parse(&renamed.to_psq()).unwrap();
+    assert_eq!(parsed.black, "two lines");
+    assert_eq!(parsed.moves, renamed.moves);
+}
+
+#[test]
+fn optional_fields() {
+    // 没有思考时间、没有名字、有空行和 Windows 换行。y 从上往下数，9,9 是 i7
+    let game = PsqGame::parse("Piskvorky 15x15, 11:11, 0\r\n8,8\r\n\r\n9,9\r\n").unwrap();
+    assert_eq!(notation(&game), ["h8", "i7"]);
+    assert_eq!(game.moves[1].time, 0);
+    assert_eq!((game.black.as_str(), game.white.as_str()), ("", ""));
+}
+
+#[test]
+fn rejected_records() {
+    let cases = [
+        ("", "Piskvorky"),
+        ("Gomoku 15x15\n8,8,0\n", "Piskvorky"),
+        ("Piskvorky 20x20, 11:11, 0\n8,8,0\n", "board size"),
+        (
+            "Piskvorky 15x15, 11:11, 0\n8,8,0\n16,1,0\n",
+            "off the board",
+        ),
+        ("Piskvorky 15x15, 11:11, 0\n8,8,0\n8,8,0\n", "occupied"),
+    ];
+    for (text, expected) in cases {
+        let error = PsqGame::parse(text).unwrap_err();
+        assert!(error.contains(expected), "{:?}: {}", text, error);
+    }
+}
@@ -0,0 +1,142 @@
+use gobang::game::{GameState, move_to_notation, parse_notation};
+use gobang::renlib::{OpeningBook, RenLib};
+
+mod common;
+use common::position;
+
+// 小棋谱库 (RenLib 3.0)：
+// 根节点 (没有着法) - h8 "center" - i9 (标记) - j10 "best" (扩展标志：棋盘文字 "A")
+//                                 \ h9
+const LIBRARY: &[u8] = include_bytes!("data/opening.lib");
+const HEADER_LEN: usize = 20;
+
+fn point(text: &str) -> Option<(usize, usize)> {
+    Some(parse_notation(text).expect("无效的着法"))
+}
+
+/// 文件头加上 body 中的节点
+fn library(body: &[u8]) -> Vec<u8> {
+    let mut bytes = LIBRARY[..HEADER_LEN].to_vec();
+    bytes.extend_from_slice(body);
+    bytes
+}
+
+#[test]
+fn parse_fixture() {
+    let library = RenLib::parse(LIBRARY).unwrap();
+    assert_eq!(library.version, (3, 0));
+    let nodes = &library.nodes;
+    assert_eq!(nodes.len(), 6);
+
+    // nodes[0] 是空棋盘，nodes[1] 是文件中没有着法的根节点
+    assert_eq!(nodes[0].children, [1]);
+    assert_eq!(nodes[1].point, None);
+    assert_eq!(nodes[1].children, [2]);
+
+    assert_eq!(nodes[2].point, point("h8"));
+    assert_eq!(nodes[2].comment.as_deref(), Some("center"));
+    // RIGHT 标志：i9 的兄弟 h9 在 i9 的整个子树之后
+    assert_eq!(nodes[2].children, [3, 5]);
+
+    assert_eq!(nodes[3].point, point("i9"));
+    assert!(nodes[3].marked);
+    assert_eq!(nodes[3].comment, None);
+    assert_eq!(nodes[3].children, [4]);
+
+    assert_eq!(nodes[4].point, point("j10"));
+    assert_eq!(nodes[4].comment.as_deref(), Some("best"));
+    assert_eq!(nodes[4].board_text.as_deref(), Some("A"));
+    assert!(nodes[4].children.is_empty());
+
+    assert_eq!(nodes[5].point, point("h9"));
+    assert!(!nodes[5].marked);
+    assert!(nodes[5].children.is_empty());
+}
+
+#[test]
+fn bad_headers() {
+    assert!(RenLib::parse(&[]).is_err());
+    assert!(RenLib::parse(&LIBRARY[..HEADER_LEN - 1]).is_err());
+
+    let mut magic = LIBRARY.to_vec();
+    magic[1] = b'r';
+    assert_eq!(
+        RenLib::parse(&magic),
+        Err("not a RenLib library".to_string())
+    );
+
+    let mut version = LIBRARY.to_vec();
+    version[8] = 4;
+    let error = RenLib::parse(&version).unwrap_err();
+    assert!(error.contains("version 4.0"), "{}", error);
+
+    // 只有文件头的棋谱库只有空棋盘
+    let empty = RenLib::parse(&LIBRARY[..HEADER_LEN]).unwrap();
+    assert_eq!(empty.nodes.len(), 1);
+}
+
+#[test]
+fn bad_nodes() {
+    // 行号超出棋盘 (第 16 行)
+    let error = RenLib::parse(&library(&[0xF1, 0x00])).unwrap_err();
+    assert!(error.contains("off the board"), "{}", error);
+    // 注释没有结尾的 0
+    let error = RenLib::parse(&library(&[0x78, 0x08, b'a', b'b'])).unwrap_err();
+    assert!(error.contains("truncated comment"), "{}", error);
+    // 扩展标志缺少两个字节
+    let error = RenLib::parse(&library(&[0x78, 0x01])).unwrap_err();
+    assert!(error.contains("extension"), "{}", error);
+
+    // 位置为 0 的节点没有着法；旧版本的注释与注释相同
+    let library = RenLib::parse(&library(&[0x00, 0x80, 0x78, 0x20, b'o', b'k', 0, 0])).unwrap();
+    assert_eq!(library.nodes[1].point, None);
+    assert_eq!(library.nodes[2].point, point("h8"));
+    assert_eq!(library.nodes[2].comment.as_deref(), Some("ok"));
+}
+
+#[test]
+fn book_lookup() {
+    let book = OpeningBook::from_library(&RenLib::parse(LIBRARY).unwrap());
+    // 空棋盘、h8、h8 i9、h8 i9 j10、h8 h9
+    assert_eq!(book.len(), 5);
+
+    let moves = |game_state: &GameState| -> Vec<(String, bool)> {
+        book.moves(game_state)
+            .iter()
+            .map(|m| (move_to_notation(m.row, m.col), m.marked))
+            .collect()
+    };
+    assert_eq!(moves(&GameState::new()), [("h8".to_string(), false)]);
+    assert_eq!(
+        moves(&position("h8")),
+        [("i9".to_string(), true), ("h9".to_string(), false)]
+    );
+    assert_eq!(
+        book.lookup(&position("h8")).unwrap().comment.as_deref(),
+        Some("center")
+    );
+    assert_eq!(moves(&position("h8 i9")), [("j10".to_string(), false)]);
+
+    let end = book.lookup(&position("h8 i9 j10")).unwrap();
+    assert!(end.moves.is_empty());
+    assert_eq!(end.comment.as_deref(), Some("best"));
+
+    assert!(book.lookup(&position("a1")).is_none());
+    assert!(moves(&position("h8 i9 j10 k11")).is_empty());
+}
+
+#[test]
+fn book_lookup_uses_symmetries() {
+    let book = OpeningBook::from_library(&RenLib::parse(LIBRARY).unwrap());
+    // h8 g7 是 h8 i9 旋转 180 度 (或沿另一条对角线翻转) 后的局面，后续着法也跟着变换
+    let moves: Vec<String> = book
+        .moves(&position("h8 g7"))
+        .iter()
+        .map(|m| move_to_notation(m.row, m.col))
+        .collect();
+    assert_eq!(moves, ["f6"]);
+
+    // h8 h7 对应 h8 h9 (上下翻转)，库中这个局面没有后续着法
+    let entry = book.lookup(&position("h8 h7")).unwrap();
+    assert!(entry.moves.is_empty());
+}
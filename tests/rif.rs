@@ -0,0 +1,159 @@
+use gobang::game::Stone;
+use gobang::game::move_to_notation;
+use gobang::rif::{XmlElement, XmlEvent, XmlReader, parse_rif};
+use gobang::savegame::GameResult;
+
+// 三名棋手、两个赛事、六局对局，其中三局因着法重复、没有着法、着法无法识别而跳过
+const DATABASE: &str = include_str!("data/rif.xml");
+
+fn events(text: &str) -> Result<Vec<XmlEvent>, String> {
+    XmlReader::new(text).collect()
+}
+
+fn start(name: &str, attributes: &[(&str, &str)]) -> XmlEvent {
+    XmlEvent::Start(XmlElement {
+        name: name.to_string(),
+        attributes: attributes
+            .iter()
+            .map(|&(key, value)| (key.to_string(), value.to_string()))
+            .collect(),
+    })
+}
+
+fn end(name: &str) -> XmlEvent {
+    XmlEvent::End(name.to_string())
+}
+
+fn text(text: &str) -> XmlEvent {
+    XmlEvent::Text(text.to_string())
+}
+
+#[test]
+fn xml_events() {
+    let xml = r#"<?xml version="1.0"?>
+<!DOCTYPE a>
+<!-- 注释 -->
+<a x="1" y = 'two words'>
+  <b/>
+  <c k="&lt;&amp;&gt;">x &quot;&#65;&#x42;&apos; &unknown; &amp</c>
+  <d><![CDATA[<raw> &amp;]]></d >
+</a>"#;
+    assert_eq!(
+        events(xml),
+        Ok(vec![
+            start("a", &[("x", "1"), ("y", "two words")]),
+            // 自闭合标签也产生结束事件
+            start("b", &[]),
+            end("b"),
+            start("c", &[("k", "<&>")]),
+            // 不认识的实体原样保留
+            text("x \"AB' &unknown; &amp"),
+            end("c"),
+            start("d", &[]),
+            // CDATA 中的内容不替换实体
+            text("<raw> &amp;"),
+            end("d"),
+            end("a"),
+        ])
+    );
+    assert_eq!(events(""), Ok(vec![]));
+}
+
+#[test]
+fn xml_errors_report_the_line() {
+    let cases = [
+        ("<a>\n<!-- open", "line 2: missing -->"),
+        ("<a>\n\n<b", "line 3: missing >"),
+        ("<a>\n<![CDATA[x", "line 2: missing ]]>"),
+        ("<a x=1>", "line 1: unquoted attribute x in <a>"),
+        ("<a x>", "attribute without value in <a>"),
+        ("<a x=\"1>", "unterminated attribute x in <a>"),
+        ("<>", "empty tag"),
+    ];
+    for (xml, expected) in cases {
+        let error = events(xml).unwrap_err();
+        assert!(error.contains(expected), "{:?}: {}", xml, error);
+    }
+
+    // 出错后不再产生事件
+    let mut reader = XmlReader::new("<a x=1><b/>");
+    assert!(reader.next().unwrap().is_err());
+    assert!(reader.next().is_none());
+}
+
+#[test]
+fn import_fixture() {
+    let import = parse_rif(DATABASE).unwrap();
+    assert_eq!(import.skipped, 3);
+    assert_eq!(import.games.len(), 3);
+
+    let game = &import.games[0];
+    assert_eq!(game.source, "rif:100");
+    assert_eq!(
+        (game.black.as_str(), game.white.as_str()),
+        ("Ana Novak", "Li & Wu")
+    );
+    assert_eq!(game.result, GameResult::Win(Stone::Black));
+    assert_eq!(game.opening, "Kagetsu");
+    assert_eq!(game.event, "World Championship");
+    assert_eq!(game.year, Some(2019));
+    assert_eq!(game.rule, "Renju");
+
+    // CDATA 和普通文字中的着法连在一起，名字去掉首尾空白
+    let game = &import.games[1];
+    assert_eq!(
+        (game.black.as_str(), game.white.as_str()),
+        ("Jan Kowalski", "Ana Novak")
+    );
+    assert_eq!(game.result, GameResult::Draw);
+    assert_eq!(game.event, "Open \"Cup\"");
+    assert_eq!(game.year, Some(2021));
+    assert_eq!(game.opening, "");
+
+    // 没有赛事的对局
+    let game = &import.games[2];
+    assert_eq!(game.result, GameResult::Win(Stone::White));
+    assert_eq!((game.event.as_str(), game.year), ("", None));
+
+    let moves: Vec<Vec<String>> = import
+        .games
+        .iter()
+        .map(|game| {
+            game.moves
+                .iter()
+                .map(|&(row, col)| move_to_notation(row, col))
+                .collect()
+        })
+        .collect();
+    assert_eq!(
+        moves,
+        [
+            vec!["h8", "i9", "i8"],
+            vec!["h8", "g7", "g8"],
+            vec!["h8", "h9"]
+        ]
+    );
+}
+
+#[test]
+fn import_results_and_rejections() {
+    let game = |bresult: &str| {
+        let xml = format!(
+            r#"<database><game id="1" bresult="{}"><move>h8</move></game></database>"#,
+            bresult
+        );
+        parse_rif(&xml).unwrap().games[0].result
+    };
+    assert_eq!(game("1"), GameResult::Win(Stone::Black));
+    assert_eq!(game(" 0 "), GameResult::Win(Stone::White));
+    assert_eq!(game("0.5"), GameResult::Draw);
+    assert_eq!(game(""), GameResult::Ongoing);
+
+    let error = parse_rif("<games><game id=\"1\"><move>h8</move></game></games>").unwrap_err();
+    assert!(error.contains("<database>"), "{}", error);
+    assert!(parse_rif("<database><game id=1>").is_err());
+
+    // <game> 外面的 <move> 不属于任何对局
+    let import = parse_rif("<database><move>h8</move></database>").unwrap();
+    assert!(import.games.is_empty());
+}
@@ -0,0 +1,134 @@
+use gobang::game::{PlayerScore, Stone, parse_notation};
+use gobang::savegame::{GameResult, Player, SAVE_VERSION, SavedGame, TimedMove};
+use std::path::PathBuf;
+
+fn saved_game(moves: &str, result: GameResult) -> SavedGame {
+    let mut stone = Stone::Black;
+    let moves = moves
+        .split_whitespace()
+        .enumerate()
+        .map(|(index, text)| {
+            let (row, col) = parse_notation(text).expect("无效的着法");
+            let m = TimedMove {
+                row,
+                col,
+                stone,
+                time: 1_700_000_000_000 + index as u64 * 1500,
+            };
+            stone = stone.opponent();
+            m
+        })
+        .collect();
+    SavedGame::from_moves(moves, result)
+}
+
+#[test]
+fn round_trip() {
+    let mut saved = saved_game("h8 h9 i10 g8 j11", GameResult::Ongoing);
+    saved.black = Player::Human;
+    saved.white = Player::Ai {
+        level: Some(4),
+        depth: 2,
+        weights: PathBuf::from("weights \"tuned\".toml"),
+    };
+    let mut score = PlayerScore::new();
+    score.add_move(120, 150);
+    score.add_move(-400, 300);
+    score.use_hint();
+    saved.score = score;
+
+    let parsed = SavedGame::parse(&saved.to_toml()).unwrap();
+    assert_eq!(parsed.rule, saved.rule);
+    assert_eq!(parsed.board_size, saved.board_size);
+    assert_eq!(parsed.black, saved.black);
+    assert_eq!(parsed.white, saved.white);
+    assert_eq!(parsed.moves, saved.moves);
+    assert_eq!(parsed.result, saved.result);
+    assert_eq!(parsed.score.move_count, saved.score.move_count);
+    assert_eq!(parsed.score.current_rating, saved.score.current_rating);
+    assert_eq!(parsed.score.current_move_score, -400);
+    assert_eq!(parsed.score.best_move_score, 300);
+    assert_eq!(parsed.score.move_scores, saved.score.move_scores);
+    assert_eq!(parsed.score.hints_used, 1);
+
+    let game_state = parsed.replay();
+    assert!(!game_state.is_game_over);
+    assert_eq!(game_state.current_turn, Stone::White);
+    assert_eq!(game_state.board[7][7], Some(Stone::Black));
+    assert_eq!(game_state.board[8][7], Some(Stone::White));
+}
+
+#[test]
+fn round_trip_results_and_players() {
+    for result in [
+        GameResult::Win(Stone::Black),
+        GameResult::Win(Stone::White),
+        GameResult::Draw,
+    ] {
+        let mut saved = saved_game("h8 h9", result);
+        saved.black = Player::Ai {
+            level: None,
+            depth: 5,
+            weights: PathBuf::from("gobang-weights.toml"),
+        };
+        saved.white = Player::Engine {
+            path: PathBuf::from(r"C:\engines\pbrain.exe"),
+        };
+        let parsed = SavedGame::parse(&saved.to_toml()).unwrap();
+        assert_eq!(parsed.result, result);
+        assert_eq!(parsed.black, saved.black);
+        assert_eq!(parsed.white, saved.white);
+        // 棋盘上没有连五时以存档中的结果为准
+        let game_state = parsed.replay();
+        assert!(game_state.is_game_over);
+        assert_eq!(GameResult::of(&game_state), result);
+    }
+}
+
+#[test]
+fn five_in_a_row_overrides_the_saved_result() {
+    let saved = saved_game("h8 a1 i8 a2 j8 a3 k8 a4 l8", GameResult::Ongoing);
+    let game_state = SavedGame::parse(&saved.to_toml()).unwrap().replay();
+    assert!(game_state.is_game_over);
+    assert_eq!(game_state.winner, Some(Stone::Black));
+}
+
+#[test]
+fn rejects_other_versions_and_formats() {
+    let text = saved_game("h8 h9", GameResult::Ongoing).to_toml();
+    let current = format!("version = {}", SAVE_VERSION);
+    assert!(text.contains(&current));
+
+    let newer = text.replace(&current, &format!("version = {}", SAVE_VERSION + 1));
+    let error = SavedGame::parse(&newer).unwrap_err();
+    assert!(error.contains("version"), "{}", error);
+    let missing = text.replace(&current, "");
+    assert!(SavedGame::parse(&missing).unwrap_err().contains("version"));
+    let zero = text.replace(&current, "version = 0");
+    assert!(SavedGame::parse(&zero).unwrap_err().contains("version"));
+
+    let other_format = text.replace("\"gobang-save\"", "\"chess-save\"");
+    assert!(SavedGame::parse(&other_format).is_err());
+    assert!(SavedGame::parse("").is_err());
+}
+
+#[test]
+fn rejects_bad_games() {
+    let text = saved_game("h8 h9", GameResult::Ongoing).to_toml();
+    let renju = text.replace("\"freestyle\"", "\"renju\"");
+    assert!(SavedGame::parse(&renju).unwrap_err().contains("rule"));
+    let other_size = text.replace("board_size = 15", "board_size = 19");
+    assert!(SavedGame::parse(&other_size).unwrap_err().contains("board size"));
+    let occupied = text.replace("\"h9\"", "\"h8\"");
+    assert!(
+        SavedGame::parse(&occupied)
+            .unwrap_err()
+            .contains("occupied")
+    );
+    let off_board = text.replace("\"h9\"", "\"z99\"");
+    assert!(
+        SavedGame::parse(&off_board)
+            .unwrap_err()
+            .contains("bad point")
+    );
+}
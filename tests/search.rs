@@ -0,0 +1,198 @@
+use gobang::agent::SmartAgent;
+use gobang::game::move_to_notation;
+
+mod common;
+use common::position;
+
+// 固定的测试局面 (黑先交替的着法)，包括开局、有活三和冲四的中局
+const POSITIONS: [&str; 5] = [
+    "h8 h9",
+    "h8 h9 i10 g8 i9",
+    "h8 i9 j8 i8 i7 g9 h9",
+    "h8 h9 i8 g8 j8 k8 i9 i10 h7",
+    "g7 h8 h7 i8 i7 j7 g9 g8 f8 e9 h6",
+];
+
+/// 置换表、落子排序、PVS 和渴望窗口只影响搜索的效率：
+/// 分数与不带这些优化的 Alpha-Beta 相同，选出的落子在参考搜索中也是最佳分数，
+/// 但访问的节点少得多
+fn compare(positions: &[&str], depth: u32) {
+    let (mut optimized_nodes, mut reference_nodes) = (0, 0);
+    for &moves in positions {
+        let game_state = position(moves);
+        let stone = game_state.current_turn;
+        let agent = SmartAgent::new(stone, depth);
+        let optimized = agent.search(&game_state).expect("有可走的落子");
+        let reference = agent.search_alpha_beta(&game_state).expect("有可走的落子");
+
+        assert_eq!(
+            optimized.score, reference.score,
+            "{} (depth {}): score differs",
+            moves, depth
+        );
+        let chosen = agent
+            .alpha_beta_score_for_move(&game_state, optimized.best_move)
+            .expect("最佳落子有效");
+        assert_eq!(
+            chosen,
+            reference.score,
+            "{} (depth {}): {} is not a best move ({} vs {} for {})",
+            moves,
+            depth,
+            move_to_notation(optimized.best_move.0, optimized.best_move.1),
+            chosen,
+            reference.score,
+            move_to_notation(reference.best_move.0, reference.best_move.1)
+        );
+        println!(
+            "{:40} depth {}: {:8} nodes, plain alpha-beta {:8} nodes",
+            moves, depth, optimized.nodes, reference.nodes
+        );
+        optimized_nodes += optimized.nodes;
+        reference_nodes += reference.nodes;
+    }
+
+    // 迭代加深的各轮都计入 optimized_nodes，仍应明显少于参考搜索
+    assert!(
+        optimized_nodes * 2 < reference_nodes,
+        "depth {}: {} nodes vs {} for plain alpha-beta",
+        depth,
+        optimized_nodes,
+        reference_nodes
+    );
+}
+
+#[test]
+fn depth_2_matches_alpha_beta_with_fewer_nodes() {
+    compare(&POSITIONS, 2);
+}
+
+#[test]
+fn depth_3_matches_alpha_beta_with_fewer_nodes() {
+    // 后两个局面参考搜索要访问 5 万到 80 万个节点，调试版太慢
+    compare(&POSITIONS[..3], 3);
+}
+
+/// 已算出胜负的局面：置换表中的胜负分数换算成距本节点的层数，
+/// 从不同着法顺序取出时报告的步数与不用置换表的搜索相同
+#[test]
+fn forced_wins_report_the_same_distance_as_alpha_beta() {
+    let cases = [
+        // 黑方活三，黑先：走成活四，两手连五
+        ("h8 a1 i8 a3 j8 a5", Some(2)),
+        // 黑方活四，白先：白方一手后被连五
+        ("h8 a1 i8 a3 j8 a5 k8", Some(-1)),
+        // 双方都有活四，白先：白方直接连五
+        ("h8 h9 i8 i9 j8 j9 k8 k9 a1", Some(1)),
+    ];
+    for (moves, expected) in cases {
+        let game_state = position(moves);
+        let stone = game_state.current_turn;
+        let agent = SmartAgent::new(stone, 3);
+        let optimized = agent.search(&game_state).expect("有可走的落子");
+        let reference = agent.search_alpha_beta(&game_state).expect("有可走的落子");
+        assert_eq!(
+            optimized.mate_in(),
+            reference.mate_in(),
+            "{}: {} vs {}",
+            moves,
+            optimized.score_text(),
+            reference.score_text()
+        );
+        assert_eq!(optimized.mate_in(), expected, "{}", moves);
+    }
+}
+
+/// 迭代加深每完成一轮报告一次 (引擎面板和 MESSAGE 输出都用它)：深度逐轮加一，
+/// 节点数不减，主要变例从这一轮的最佳落子开始；最终结果与最后一轮的报告一致
+#[test]
+fn search_reports_each_completed_depth() {
+    let game_state = position(POSITIONS[0]);
+    let agent = SmartAgent::new(game_state.current_turn, 3);
+    let mut reports = Vec::new();
+    let info = agent
+        .search_with(&game_state, |info| reports.push(info.clone()))
+        .expect("有可走的落子");
+
+    let depths: Vec<u32> = reports.iter().map(|report| report.depth).collect();
+    assert_eq!(depths, [1, 2, 3]);
+    assert!(reports.windows(2).all(|w| w[0].nodes <= w[1].nodes));
+    for report in &reports {
+        assert_eq!(report.pv.first(), Some(&report.best_move));
+        assert!(report.pv.len() <= report.depth as usize);
+    }
+    let last = reports.last().unwrap();
+    assert_eq!(
+        (info.best_move, info.score, info.depth, &info.pv),
+        (last.best_move, last.score, last.depth, &last.pv)
+    );
+    assert!(info.nodes >= last.nodes);
+    let (row, col) = info.best_move;
+    assert!(game_state.board[row][col].is_none());
+    assert!(info.to_string().starts_with("depth 3 score "), "{}", info);
+    assert!(info.to_string().ends_with(&info.pv_text()), "{}", info);
+}
+
+/// 找到必胜着法后不再加深，最后一轮报告连五的步数
+#[test]
+fn search_stops_deepening_at_a_forced_win() {
+    let game_state = position("h8 a1 i8 a3 j8 a5");
+    let agent = SmartAgent::new(game_state.current_turn, 5);
+    let mut reports = Vec::new();
+    let info = agent
+        .search_with(&game_state, |info| reports.push(info.clone()))
+        .expect("有可走的落子");
+
+    // 叶节点的冲四延伸可能在很浅的一轮就找到胜着
+    let (last, earlier) = reports.split_last().unwrap();
+    assert!(last.depth < 5, "{}", last);
+    assert!(earlier.iter().all(|report| report.mate_in().is_none()));
+    assert_eq!(last.mate_in(), Some(2));
+    assert_eq!(info.mate_in(), Some(2));
+    assert_eq!(info.score_text(), "mate in 2");
+    assert!(info.to_string().contains("score mate in 2"), "{}", info);
+}
+
+// 黑方 h8 i8 j8 (g8 被白方挡住) 和 l9 l10：l8 冲四同时连成 l8-l10 活三，
+// 白方只能封 k8，黑方 l11 走成活四获胜
+const VCF: &str = "h8 g8 i8 a1 j8 a3 l9 a15 l10";
+
+/// 叶节点继续走冲四和封堵：1 层搜索就能看到冲四之后的连续冲四胜，
+/// 也能看到对手已有的活四
+#[test]
+fn quiescence_sees_fours_beyond_the_horizon() {
+    let game_state = position(&format!("{} o1", VCF));
+    let agent = SmartAgent::new(game_state.current_turn, 1);
+    let info = agent.search(&game_state).expect("有可走的落子");
+    assert_eq!(move_to_notation(info.best_move.0, info.best_move.1), "l8");
+    assert_eq!(info.mate_in(), Some(3), "{}", info);
+
+    let game_state = position("h8 a1 i8 a3 j8 a5 k8");
+    let agent = SmartAgent::new(game_state.current_turn, 1);
+    let info = agent.search(&game_state).expect("有可走的落子");
+    assert_eq!(info.mate_in(), Some(-1), "{}", info);
+}
+
+/// 对手有地平线外的连续冲四胜时，2 层搜索就会提前化解
+#[test]
+fn quiescence_defends_against_a_hidden_vcf() {
+    let mut game_state = position(VCF);
+    let white = game_state.current_turn;
+    let info = SmartAgent::new(white, 2)
+        .search(&game_state)
+        .expect("有可走的落子");
+    assert_eq!(info.mate_in(), None, "{}", info);
+
+    let (row, col) = info.best_move;
+    game_state.apply_move(row, col, white).unwrap();
+    game_state.current_turn = white.opponent();
+    let reply = SmartAgent::new(white.opponent(), 3)
+        .search(&game_state)
+        .expect("有可走的落子");
+    assert!(
+        reply.mate_in().is_none_or(|n| n < 0),
+        "{} / {}",
+        info,
+        reply
+    );
+}
@@ -0,0 +1,172 @@
+use gobang::game::{GRID_SIZE, Stone, parse_notation};
+use gobang::savegame::GameResult;
+use gobang::sgf::{SgfGame, SgfMove, parse_sgf, parse_sgf_point, sgf_point};
+
+/// 黑先交替的一条变化，`h8:注释` 给这步加上注释
+fn line(moves: &str) -> Vec<SgfMove> {
+    let mut stone = Stone::Black;
+    moves
+        .split_whitespace()
+        .map(|text| {
+            let (point, comment) = match text.split_once(':') {
+                Some((point, comment)) => (point, Some(comment.replace('_', " "))),
+                None => (text, None),
+            };
+            let (row, col) = parse_notation(point).expect("无效的着法");
+            let m = SgfMove {
+                row,
+                col,
+                stone,
+                comment,
+            };
+            stone = stone.opponent();
+            m
+        })
+        .collect()
+}
+
+fn game(lines: &[&str]) -> SgfGame {
+    SgfGame {
+        black: "Alice".to_string(),
+        white: "Bob".to_string(),
+        result: GameResult::Ongoing,
+        rule: "freestyle".to_string(),
+        comment: None,
+        lines: lines.iter().map(|moves| line(moves)).collect(),
+    }
+}
+
+fn import(text: &str) -> Result<SgfGame, String> {
+    SgfGame::from_tree(&parse_sgf(text)?[0])
+}
+
+#[test]
+fn coordinates() {
+    // SGF 的第一行在上方：a1 (左下角) 是 "ao"，o15 (右上角) 是 "oa"
+    assert_eq!(sgf_point(0, 0), "ao");
+    assert_eq!(sgf_point(GRID_SIZE, GRID_SIZE), "oa");
+    assert_eq!(sgf_point(0, GRID_SIZE), "oo");
+    let (row, col) = parse_notation("h8").unwrap();
+    assert_eq!(sgf_point(row, col), "hh");
+    let (row, col) = parse_notation("i10").unwrap();
+    assert_eq!(sgf_point(row, col), "if");
+
+    for row in 0..=GRID_SIZE {
+        for col in 0..=GRID_SIZE {
+            assert_eq!(parse_sgf_point(&sgf_point(row, col)), Some((row, col)));
+        }
+    }
+    for bad in ["", "a", "abc", "pa", "ap", "A1", "hh "] {
+        assert_eq!(parse_sgf_point(bad), None, "{:?}", bad);
+    }
+}
+
+#[test]
+fn export_import_round_trip() {
+    let mut exported = game(&["h8 h9:Good:_-1.2%_win_rate i10 g8:Blunder:_-40.0%_win_rate j11"]);
+    exported.comment = Some("friendly game".to_string());
+    for result in [
+        GameResult::Ongoing,
+        GameResult::Win(Stone::Black),
+        GameResult::Win(Stone::White),
+        GameResult::Draw,
+    ] {
+        exported.result = result;
+        let text = exported.to_sgf();
+        assert!(text.starts_with("(;GM[4]"), "{}", text);
+        assert_eq!(import(&text), Ok(exported.clone()), "{}", text);
+    }
+}
+
+#[test]
+fn win_without_five_is_marked_as_forfeit() {
+    let mut exported = game(&["h8 h9 i10"]);
+    exported.result = GameResult::Win(Stone::White);
+    assert!(exported.to_sgf().contains("RE[W+F]"));
+
+    let mut five = game(&["h8 a1 i8 a2 j8 a3 k8 a4 l8"]);
+    five.result = GameResult::Win(Stone::Black);
+    assert!(five.to_sgf().contains("RE[B+]"));
+}
+
+#[test]
+fn escaped_brackets_in_comments() {
+    let text = r"(;GM[4]SZ[15]C[see [1\] and a\\b];B[hh]C[x \] y]
+        ;W[hg]C[soft\
+break])";
+    let imported = import(text).unwrap();
+    assert_eq!(imported.comment.as_deref(), Some(r"see [1] and a\b"));
+    assert_eq!(imported.lines[0][0].comment.as_deref(), Some("x ] y"));
+    assert_eq!(imported.lines[0][1].comment.as_deref(), Some("softbreak"));
+
+    // 导出时转义 ] 和 \，读回来不变
+    let mut exported = game(&[r"h8:a]b\c h9"]);
+    exported.comment = Some(r"[x] \ [y]".to_string());
+    let text = exported.to_sgf();
+    assert!(text.contains(r"C[a\]b\\c]"), "{}", text);
+    assert_eq!(import(&text), Ok(exported));
+}
+
+#[test]
+fn variations_branch_where_they_differ() {
+    let exported = game(&[
+        "h8 h9 i10",
+        "h8 h9 j10",
+        "h8 g9",
+        // 已有变化的前缀不再单独列出
+        "h8 h9",
+    ]);
+    let text = exported.to_sgf();
+    assert!(
+        text.ends_with("PW[Bob];B[hh](;W[hg](;B[if])(;B[jf]))(;W[gg]))\n"),
+        "{}",
+        text
+    );
+
+    let imported = import(&text).unwrap();
+    assert_eq!(imported.lines, exported.lines[..3].to_vec());
+    // 变化的结局由棋盘判断，结果只属于主线
+    assert_eq!(imported.saved_game(1).moves.len(), 3);
+    assert_eq!(imported.saved_game(2).result, GameResult::Ongoing);
+}
+
+#[test]
+fn variation_from_the_first_move() {
+    let exported = game(&["h8 h9", "i9 h8"]);
+    let text = exported.to_sgf();
+    assert!(
+        text.ends_with("PW[Bob](;B[hh];W[hg])(;B[ig];W[hh]))\n"),
+        "{}",
+        text
+    );
+    assert_eq!(import(&text).unwrap().lines, exported.lines);
+}
+
+#[test]
+fn rejected_records() {
+    let cases = [
+        ("(;GM[1]SZ[19];B[pd])", "GM[1]"),
+        ("(;GM[4]SZ[19];B[hh])", "SZ[19]"),
+        ("(;GM[4]AB[hh][hg];W[ii])", "setup"),
+        ("(;GM[4];W[hh])", "expected"),
+        ("(;GM[4];B[hh];W[hh])", "occupied"),
+        ("(;GM[4];B[zz])", "bad point"),
+        ("(;GM[4];B[hh]W[hg])", "both"),
+        ("(;GM[4];B[hh](;W[hg])(;B[ii]))", "variation 1"),
+    ];
+    for (text, expected) in cases {
+        let error = import(text).unwrap_err();
+        assert!(error.contains(expected), "{}: {}", text, error);
+    }
+    assert!(parse_sgf("").is_err());
+    assert!(parse_sgf("(;GM[4]C[unterminated)").is_err());
+    assert!(parse_sgf("(;GM[4]").is_err());
+}
+
+#[test]
+fn several_games_in_one_file() {
+    let text = game(&["h8"]).to_sgf() + &game(&["i9 h8"]).to_sgf();
+    let trees = parse_sgf(&text).unwrap();
+    assert_eq!(trees.len(), 2);
+    assert_eq!(SgfGame::from_tree(&trees[1]).unwrap().lines[0].len(), 2);
+}
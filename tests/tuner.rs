@@ -0,0 +1,154 @@
+use gobang::evaluator::BoardEvaluator;
+use gobang::game::{Stone, parse_notation};
+use gobang::tuner::{Sample, fit_scale, mean_squared_error, tune};
+use gobang::weights::{EvalWeights, FEATURE_COUNT, Feature, MAX_WEIGHT};
+use std::fs;
+use std::path::PathBuf;
+
+mod common;
+use common::position;
+
+fn temp_file(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("gobang-{}-{}.toml", name, std::process::id()))
+}
+
+fn sigmoid(x: f64) -> f64 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+/// 按 weights 和缩放系数 k 生成的理想样本：活三、活二和位置分之差取遍一个小网格，
+/// 黑方得分正好是预测的胜率
+fn samples(weights: &[f64; FEATURE_COUNT], k: f64) -> Vec<Sample> {
+    let mut samples = Vec::new();
+    for three in -3..=3 {
+        for two in -3..=3 {
+            for positional in [-40, 0, 40] {
+                let mut features = [0.0; FEATURE_COUNT];
+                features[Feature::OpenThree as usize] = three as f64;
+                features[Feature::OpenTwo as usize] = two as f64;
+                features[Feature::Positional as usize] = positional as f64;
+                let eval: f64 = weights.iter().zip(&features).map(|(w, f)| w * f).sum();
+                samples.push(Sample {
+                    features,
+                    black_score: sigmoid(k * eval),
+                });
+            }
+        }
+    }
+    samples
+}
+
+fn as_f64(weights: &EvalWeights) -> [f64; FEATURE_COUNT] {
+    weights.features.map(|w| w as f64)
+}
+
+#[test]
+fn weights_round_trip() {
+    let mut weights = EvalWeights::default();
+    weights.features[Feature::OpenThree as usize] = 1_234;
+    weights.features[Feature::Positional as usize] = -2;
+
+    let path = temp_file("weights");
+    weights.save(&path).unwrap();
+    let loaded = EvalWeights::load(&path);
+    fs::remove_file(&path).unwrap();
+    assert_eq!(loaded, Ok(weights));
+
+    // 文件不存在时使用默认权重，缺失的项也使用默认值
+    let missing = std::env::temp_dir().join("gobang-weights-missing.toml");
+    assert_eq!(EvalWeights::load(&missing), Ok(EvalWeights::default()));
+}
+
+#[test]
+fn weights_are_clamped_on_load() {
+    let path = temp_file("weights-large");
+    fs::write(
+        &path,
+        "[agent]\nfive = 2000000000\nhalf_two = -2000000000\nopen_two = 70\n",
+    )
+    .unwrap();
+    let loaded = EvalWeights::load(&path);
+    fs::remove_file(&path).unwrap();
+    let loaded = loaded.unwrap();
+    assert_eq!(loaded.get(Feature::Five), MAX_WEIGHT);
+    assert_eq!(loaded.get(Feature::HalfTwo), -MAX_WEIGHT);
+    assert_eq!(loaded.get(Feature::OpenTwo), 70);
+    assert_eq!(
+        loaded.get(Feature::OpenFour),
+        EvalWeights::default().get(Feature::OpenFour)
+    );
+}
+
+#[test]
+fn weighted_sums_saturate() {
+    let weights = EvalWeights::default();
+    let mut features = [0; FEATURE_COUNT];
+    features[Feature::OpenThree as usize] = 2;
+    features[Feature::OpenTwo as usize] = -1;
+    features[Feature::Positional as usize] = 7;
+    assert_eq!(weights.score(&features), 2 * 1_000 - 50 + 7);
+
+    // 权重文件中的大权重与很多棋型相乘时取 i32 的边界值，不会溢出
+    let huge = EvalWeights {
+        features: [i32::MAX; FEATURE_COUNT],
+    };
+    assert_eq!(huge.score(&[1_000; FEATURE_COUNT]), i32::MAX);
+    assert_eq!(huge.score(&[-1_000; FEATURE_COUNT]), i32::MIN);
+
+    let game_state = position("h8 a1 i8 a3 j8");
+    let (row, col) = parse_notation("k8").unwrap();
+    let score = BoardEvaluator::evaluate_position(&game_state, row, col, Stone::Black, &huge);
+    assert_eq!(score, i32::MAX);
+}
+
+#[test]
+fn fit_scale_recovers_the_scale() {
+    let weights = as_f64(&EvalWeights::default());
+    for k in [1e-4, 5e-4, 2e-3] {
+        let fitted = fit_scale(&samples(&weights, k), &weights);
+        assert!(
+            (fitted / k - 1.0).abs() < 0.01,
+            "k {} fitted as {}",
+            k,
+            fitted
+        );
+    }
+    assert_eq!(mean_squared_error(&[], &weights, 1e-3), 0.0);
+}
+
+#[test]
+fn tune_moves_weights_towards_the_data() {
+    // 数据按活三 3000 分生成，从默认的 1000 分开始调
+    let initial = EvalWeights::default();
+    let mut target = as_f64(&initial);
+    target[Feature::OpenThree as usize] = 3_000.0;
+    let samples = samples(&target, 1.0 / 2_000.0);
+
+    let mut errors = Vec::new();
+    let tuned = tune(&samples, &initial, 30, |pass, error| {
+        errors.push((pass, error))
+    });
+    assert!(!errors.is_empty() && errors.len() <= 30);
+    assert!(errors.windows(2).all(|w| w[1].1 <= w[0].1), "{:?}", errors);
+
+    let k = fit_scale(&samples, &as_f64(&initial));
+    let before = mean_squared_error(&samples, &as_f64(&initial), k);
+    let after = mean_squared_error(&samples, &as_f64(&tuned), k);
+    assert!(after < before / 10.0, "{} -> {}", before, after);
+
+    // 五连的分值作为尺度的锚点不变。没有五连的样本时 k 与权重的整体倍数无法区分，
+    // 只比较活三与活二之比：初始为 20，数据中为 60
+    assert_eq!(tuned.get(Feature::Five), initial.get(Feature::Five));
+    let ratio = tuned.get(Feature::OpenThree) as f64 / tuned.get(Feature::OpenTwo) as f64;
+    assert!((ratio / 60.0 - 1.0).abs() < 0.1, "{:?}", tuned);
+    assert!(tuned.features.iter().all(|w| (0..=MAX_WEIGHT).contains(w)));
+}
+
+#[test]
+fn tune_clamps_the_result() {
+    let mut initial = EvalWeights::default();
+    initial.features[Feature::OpenFour as usize] = i32::MAX;
+    let samples = samples(&as_f64(&EvalWeights::default()), 1.0 / 2_000.0);
+    let tuned = tune(&samples, &initial, 2, |_, _| {});
+    assert_eq!(tuned.get(Feature::OpenFour), MAX_WEIGHT);
+}